@@ -2,6 +2,7 @@
 use sdl3_gs::callbacks::App;
 use sdl3_gs::device::*;
 use sdl3_gs::event::{Event, WindowEventKind, SDL_Scancode};
+use sdl3_gs::render_target::{RenderTarget, RenderTargetDesc};
 use sdl3_gs::sys::gpu;
 
 #[repr(C)]
@@ -34,56 +35,15 @@ fn generate_checkerboard(width: u32, height: u32, cell_size: u32) -> Vec<u8> {
     pixels
 }
 
-struct MsaaTargets {
-    msaa: Texture,
-    resolve: Texture,
-    width: u32,
-    height: u32,
-}
-
-impl MsaaTargets {
-    fn create(device: &Device, width: u32, height: u32, format: SDL_GPUTextureFormat) -> Self {
-        let msaa = device.create_texture(&SDL_GPUTextureCreateInfo {
-            r#type: SDL_GPUTextureType::_2D,
-            format,
-            usage: SDL_GPUTextureUsageFlags::COLOR_TARGET,
-            width,
-            height,
-            layer_count_or_depth: 1,
-            num_levels: 1,
-            sample_count: SAMPLE_COUNT,
-            props: sdl3_gs::sys::properties::SDL_PropertiesID(0),
-        }).expect("Failed to create MSAA texture");
-
-        let resolve = device.create_texture(&SDL_GPUTextureCreateInfo {
-            r#type: SDL_GPUTextureType::_2D,
-            format,
-            usage: SDL_GPUTextureUsageFlags::COLOR_TARGET | SDL_GPUTextureUsageFlags::SAMPLER,
-            width,
-            height,
-            layer_count_or_depth: 1,
-            num_levels: 1,
-            sample_count: SDL_GPUSampleCount::_1,
-            props: sdl3_gs::sys::properties::SDL_PropertiesID(0),
-        }).expect("Failed to create resolve texture");
-
-        Self { msaa, resolve, width, height }
-    }
-
-    fn destroy(&mut self, device: &Device) {
-        self.msaa.destroy(device);
-        self.resolve.destroy(device);
-    }
-}
-
 struct Renderer {
     pipeline: GraphicsPipeline,
     vertex_buffer: GPUBuffer,
     index_buffer: GPUBuffer,
     checkerboard_texture: Texture,
     sampler: Sampler,
-    targets: MsaaTargets,
+    target: RenderTarget,
     swapchain_format: SDL_GPUTextureFormat,
+    screenshot_requested: bool,
 }
 
 impl Renderer {
@@ -97,6 +57,7 @@ impl Renderer {
             num_storage_textures: 0,
             num_storage_buffers: 0,
             num_uniform_buffers: 0,
+            name: Some("textured.vert"),
         }).expect("Failed to create vertex shader");
 
         let mut fragment_shader = device.create_shader(&ShaderCreateInfo {
@@ -108,6 +69,7 @@ impl Renderer {
             num_storage_textures: 0,
             num_storage_buffers: 0,
             num_uniform_buffers: 1,
+            name: Some("textured.frag"),
         }).expect("Failed to create fragment shader");
 
         let swapchain_format = device.get_swapchain_texture_format();
@@ -153,6 +115,7 @@ impl Renderer {
             }],
             depth_stencil_format: Default::default(),
             has_depth_stencil_target: false,
+            name: Some("textured quad".to_string()),
         }).expect("Failed to create graphics pipeline");
 
         vertex_shader.destroy(device);
@@ -160,7 +123,7 @@ impl Renderer {
 
         // Vertex buffer
         let vertex_data_size = std::mem::size_of_val(&QUAD_VERTICES) as u32;
-        let vertex_buffer = device.create_buffer(SDL_GPUBufferUsageFlags::VERTEX, vertex_data_size)
+        let vertex_buffer = device.create_buffer_named(SDL_GPUBufferUsageFlags::VERTEX, vertex_data_size, "quad vertices")
             .expect("Failed to create vertex buffer");
         device.upload_to_buffer(None, vertex_buffer, 0, bytemuck::cast_slice(&QUAD_VERTICES))
             .expect("Failed to upload vertex data");
@@ -214,7 +177,12 @@ impl Renderer {
             props: sdl3_gs::sys::properties::SDL_PropertiesID(0),
         }).expect("Failed to create sampler");
 
-        let targets = MsaaTargets::create(device, 1280, 720, swapchain_format);
+        let target = RenderTarget::new(RenderTargetDesc {
+            color_usage: SDL_GPUTextureUsageFlags::COLOR_TARGET,
+            sample_count: SAMPLE_COUNT,
+            resolve: false,
+            ..RenderTargetDesc::new(swapchain_format)
+        });
 
         Self {
             pipeline,
@@ -222,33 +190,27 @@ impl Renderer {
             index_buffer,
             checkerboard_texture,
             sampler,
-            targets,
+            target,
             swapchain_format,
+            screenshot_requested: false,
         }
     }
 
     fn render_frame(&mut self, device: &Device) -> Result<(), &'static str> {
         let mut cmd = device.acquire_command_buffer()?;
-        let Some(_swapchain) = cmd.acquire_swapchain_texture()? else {
+        let window = device.get_window().ok_or("Device has no window")?;
+        let Some(swapchain) = cmd.acquire_swapchain_texture(window)? else {
             return Ok(());
         };
 
-        let (sw, sh) = cmd.device().get_texture_res(Texture::SWAPCHAIN);
-
-        if self.targets.width != sw || self.targets.height != sh {
-            self.targets.destroy(device);
-            self.targets = MsaaTargets::create(device, sw, sh, self.swapchain_format);
-        }
-
-        let mut target = ColorTargetInfo::new(self.targets.msaa);
-        target.clear_color = SDL_FColor { r: 0.1, g: 0.1, b: 0.1, a: 1.0 };
-        target.load_op = SDL_GPULoadOp::CLEAR;
-        target.store_op = SDL_GPUStoreOp::RESOLVE;
-        target.resolve_texture = Some(Texture::SWAPCHAIN);
-        target.cycle = true;
-        target.cycle_resolve_texture = true;
+        self.target.update_for(device, swapchain.texture())?;
+        let target = self.target.color_target_resolving_into(
+            Some(swapchain.texture()),
+            Some(SDL_FColor { r: 0.1, g: 0.1, b: 0.1, a: 1.0 }),
+        );
 
-        let pass = cmd.begin_render_pass(&[target], None)?;
+        let mut group = cmd.debug_group("quad");
+        let pass = group.begin_render_pass(&[target], None)?;
         pass.bind_graphics_pipeline(self.pipeline);
         pass.bind_vertex_buffers(0, &[GPUBufferBinding { buffer: self.vertex_buffer, offset: 0 }]);
         pass.bind_index_buffer(&GPUBufferBinding { buffer: self.index_buffer, offset: 0 }, SDL_GPUIndexElementSize::_16BIT);
@@ -260,13 +222,27 @@ impl Renderer {
         pass.push_fragment_uniform_data(0, bytemuck::cast_slice(&tint_color));
         pass.draw_indexed_primitives(6, 1, 0, 0, 0);
         drop(pass);
+        drop(group);
 
         // cmd.blit_texture(&BlitInfo::new(
-        //     BlitRegion::full(self.targets.resolve, sw, sh),
+        //     BlitRegion::full(self.target.texture(), sw, sh),
         //     BlitRegion::full(Texture::SWAPCHAIN, sw, sh),
         // ));
 
+        let screenshot = if self.screenshot_requested {
+            self.screenshot_requested = false;
+            Some(cmd.capture_swapchain()?)
+        } else {
+            None
+        };
+
         cmd.submit();
+
+        if let Some(screenshot) = screenshot {
+            screenshot.save_png("screenshot.png")?;
+            device.set_swapchain_capture(false);
+            println!("Saved screenshot.png");
+        }
         Ok(())
     }
 }
@@ -285,6 +261,7 @@ fn run_compute_fill(device: &Device) {
         threadcount_x: 64,
         threadcount_y: 1,
         threadcount_z: 1,
+        name: Some("fill_array"),
     }).expect("Failed to create compute pipeline");
 
     let mut buffer = device.create_buffer(
@@ -339,10 +316,11 @@ impl App for DemoApp {
                 | sdl3_gs::SDL_WindowFlags::RESIZABLE,
         )?;
 
-        let mut device = Device::new(SDL_GPUShaderFormat::SPIRV, Some(window))
+        let device = Device::new(SDL_GPUShaderFormat::SPIRV, Some(window))
             .map_err(|e| e.to_string())?;
+        device.set_draw_validation(cfg!(debug_assertions));
 
-        let renderer = Renderer::new(&mut device);
+        let renderer = Renderer::new(&device);
 
         run_compute_fill(&device);
 
@@ -365,6 +343,10 @@ impl App for DemoApp {
                 if scancode == SDL_Scancode::ESCAPE {
                     return false;
                 }
+                if scancode == SDL_Scancode::F12 && !repeat {
+                    self.device.set_swapchain_capture(true);
+                    self.renderer.screenshot_requested = true;
+                }
                 if !repeat {
                     println!("Key pressed: {:?}", scancode.0);
                 }
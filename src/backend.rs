@@ -0,0 +1,600 @@
+use std::ffi::CStr;
+
+use sdl3_sys as sys;
+use sys::gpu;
+use sys::video::SDL_Window;
+
+/// The GPU calls [`Device`](crate::device::Device), the command buffer and
+/// the pass types make, one method per `SDL_*GPU*` function and with the
+/// same raw types, so that they can run against SDL or against the
+/// recording [`NullBackend`](crate::null_backend::NullBackend).
+///
+/// Pointers passed in are ones the same backend handed out. Backends are
+/// shared with [`SharedDevice`](crate::shared_device::SharedDevice)s on
+/// other threads; command buffer and pass calls only ever come from the
+/// thread that acquired the command buffer.
+pub(crate) trait Backend: Send + Sync {
+    fn create_texture(&self, info: &gpu::SDL_GPUTextureCreateInfo) -> *mut gpu::SDL_GPUTexture;
+    fn release_texture(&self, texture: *mut gpu::SDL_GPUTexture);
+    fn create_shader(&self, info: &gpu::SDL_GPUShaderCreateInfo) -> *mut gpu::SDL_GPUShader;
+    fn release_shader(&self, shader: *mut gpu::SDL_GPUShader);
+    fn create_graphics_pipeline(&self, info: &gpu::SDL_GPUGraphicsPipelineCreateInfo) -> *mut gpu::SDL_GPUGraphicsPipeline;
+    fn release_graphics_pipeline(&self, pipeline: *mut gpu::SDL_GPUGraphicsPipeline);
+    fn create_compute_pipeline(&self, info: &gpu::SDL_GPUComputePipelineCreateInfo) -> *mut gpu::SDL_GPUComputePipeline;
+    fn release_compute_pipeline(&self, pipeline: *mut gpu::SDL_GPUComputePipeline);
+    fn create_buffer(&self, info: &gpu::SDL_GPUBufferCreateInfo) -> *mut gpu::SDL_GPUBuffer;
+    fn release_buffer(&self, buffer: *mut gpu::SDL_GPUBuffer);
+    fn create_sampler(&self, info: &gpu::SDL_GPUSamplerCreateInfo) -> *mut gpu::SDL_GPUSampler;
+    fn release_sampler(&self, sampler: *mut gpu::SDL_GPUSampler);
+    fn create_transfer_buffer(&self, info: &gpu::SDL_GPUTransferBufferCreateInfo) -> *mut gpu::SDL_GPUTransferBuffer;
+    fn release_transfer_buffer(&self, transfer: *mut gpu::SDL_GPUTransferBuffer);
+    fn map_transfer_buffer(&self, transfer: *mut gpu::SDL_GPUTransferBuffer, cycle: bool) -> *mut u8;
+    fn unmap_transfer_buffer(&self, transfer: *mut gpu::SDL_GPUTransferBuffer);
+    fn set_texture_name(&self, texture: *mut gpu::SDL_GPUTexture, name: &CStr);
+    fn set_buffer_name(&self, buffer: *mut gpu::SDL_GPUBuffer, name: &CStr);
+
+    fn claim_window(&self, window: *mut SDL_Window) -> bool;
+    fn release_window(&self, window: *mut SDL_Window);
+    fn window_supports_present_mode(&self, window: *mut SDL_Window, mode: gpu::SDL_GPUPresentMode) -> bool;
+    fn window_supports_swapchain_composition(&self, window: *mut SDL_Window, composition: gpu::SDL_GPUSwapchainComposition) -> bool;
+    fn set_swapchain_parameters(
+        &self,
+        window: *mut SDL_Window,
+        composition: gpu::SDL_GPUSwapchainComposition,
+        present_mode: gpu::SDL_GPUPresentMode,
+    ) -> bool;
+    fn set_allowed_frames_in_flight(&self, frames: u32) -> bool;
+    fn swapchain_texture_format(&self, window: *mut SDL_Window) -> gpu::SDL_GPUTextureFormat;
+    fn wait_for_swapchain(&self, window: *mut SDL_Window) -> bool;
+    fn shader_formats(&self) -> gpu::SDL_GPUShaderFormat;
+    fn driver_name(&self) -> String;
+    fn device_properties(&self) -> sys::properties::SDL_PropertiesID;
+
+    fn acquire_command_buffer(&self) -> *mut gpu::SDL_GPUCommandBuffer;
+    fn submit_command_buffer(&self, cmd: *mut gpu::SDL_GPUCommandBuffer) -> bool;
+    fn submit_command_buffer_and_acquire_fence(&self, cmd: *mut gpu::SDL_GPUCommandBuffer) -> *mut gpu::SDL_GPUFence;
+    fn cancel_command_buffer(&self, cmd: *mut gpu::SDL_GPUCommandBuffer);
+    fn query_fence(&self, fence: *mut gpu::SDL_GPUFence) -> bool;
+    fn wait_for_fence(&self, fence: *mut gpu::SDL_GPUFence) -> bool;
+    fn release_fence(&self, fence: *mut gpu::SDL_GPUFence);
+    /// The swapchain texture and its size, null while the window has none.
+    /// `None` if the call failed.
+    fn acquire_swapchain_texture(
+        &self,
+        cmd: *mut gpu::SDL_GPUCommandBuffer,
+        window: *mut SDL_Window,
+        wait: bool,
+    ) -> Option<(*mut gpu::SDL_GPUTexture, u32, u32)>;
+    fn blit_texture(&self, cmd: *mut gpu::SDL_GPUCommandBuffer, info: &gpu::SDL_GPUBlitInfo);
+    fn insert_debug_label(&self, cmd: *mut gpu::SDL_GPUCommandBuffer, text: &CStr);
+    fn push_debug_group(&self, cmd: *mut gpu::SDL_GPUCommandBuffer, name: &CStr);
+    fn pop_debug_group(&self, cmd: *mut gpu::SDL_GPUCommandBuffer);
+    fn push_vertex_uniform_data(&self, cmd: *mut gpu::SDL_GPUCommandBuffer, slot_index: u32, data: &[u8]);
+    fn push_fragment_uniform_data(&self, cmd: *mut gpu::SDL_GPUCommandBuffer, slot_index: u32, data: &[u8]);
+    fn push_compute_uniform_data(&self, cmd: *mut gpu::SDL_GPUCommandBuffer, slot_index: u32, data: &[u8]);
+
+    fn begin_copy_pass(&self, cmd: *mut gpu::SDL_GPUCommandBuffer) -> *mut gpu::SDL_GPUCopyPass;
+    fn upload_to_buffer(
+        &self,
+        pass: *mut gpu::SDL_GPUCopyPass,
+        source: &gpu::SDL_GPUTransferBufferLocation,
+        destination: &gpu::SDL_GPUBufferRegion,
+        cycle: bool,
+    );
+    fn upload_to_texture(
+        &self,
+        pass: *mut gpu::SDL_GPUCopyPass,
+        source: &gpu::SDL_GPUTextureTransferInfo,
+        destination: &gpu::SDL_GPUTextureRegion,
+        cycle: bool,
+    );
+    fn download_from_buffer(
+        &self,
+        pass: *mut gpu::SDL_GPUCopyPass,
+        source: &gpu::SDL_GPUBufferRegion,
+        destination: &gpu::SDL_GPUTransferBufferLocation,
+    );
+    fn download_from_texture(
+        &self,
+        pass: *mut gpu::SDL_GPUCopyPass,
+        source: &gpu::SDL_GPUTextureRegion,
+        destination: &gpu::SDL_GPUTextureTransferInfo,
+    );
+    fn copy_buffer_to_buffer(
+        &self,
+        pass: *mut gpu::SDL_GPUCopyPass,
+        source: &gpu::SDL_GPUBufferLocation,
+        destination: &gpu::SDL_GPUBufferLocation,
+        size: u32,
+        cycle: bool,
+    );
+    fn end_copy_pass(&self, pass: *mut gpu::SDL_GPUCopyPass);
+
+    fn begin_render_pass(
+        &self,
+        cmd: *mut gpu::SDL_GPUCommandBuffer,
+        color_targets: &[gpu::SDL_GPUColorTargetInfo],
+        depth_stencil_target: Option<&gpu::SDL_GPUDepthStencilTargetInfo>,
+    ) -> *mut gpu::SDL_GPURenderPass;
+    fn bind_graphics_pipeline(&self, pass: *mut gpu::SDL_GPURenderPass, pipeline: *mut gpu::SDL_GPUGraphicsPipeline);
+    fn bind_vertex_buffers(&self, pass: *mut gpu::SDL_GPURenderPass, first_slot: u32, bindings: &[gpu::SDL_GPUBufferBinding]);
+    fn bind_index_buffer(
+        &self,
+        pass: *mut gpu::SDL_GPURenderPass,
+        binding: &gpu::SDL_GPUBufferBinding,
+        index_element_size: gpu::SDL_GPUIndexElementSize,
+    );
+    fn bind_fragment_samplers(&self, pass: *mut gpu::SDL_GPURenderPass, first_slot: u32, bindings: &[gpu::SDL_GPUTextureSamplerBinding]);
+    fn bind_fragment_storage_textures(&self, pass: *mut gpu::SDL_GPURenderPass, first_slot: u32, textures: &[*mut gpu::SDL_GPUTexture]);
+    fn bind_fragment_storage_buffers(&self, pass: *mut gpu::SDL_GPURenderPass, first_slot: u32, buffers: &[*mut gpu::SDL_GPUBuffer]);
+    fn bind_vertex_storage_buffers(&self, pass: *mut gpu::SDL_GPURenderPass, first_slot: u32, buffers: &[*mut gpu::SDL_GPUBuffer]);
+    fn bind_vertex_samplers(&self, pass: *mut gpu::SDL_GPURenderPass, first_slot: u32, bindings: &[gpu::SDL_GPUTextureSamplerBinding]);
+    fn bind_vertex_storage_textures(&self, pass: *mut gpu::SDL_GPURenderPass, first_slot: u32, textures: &[*mut gpu::SDL_GPUTexture]);
+    fn draw_primitives(&self, pass: *mut gpu::SDL_GPURenderPass, num_vertices: u32, num_instances: u32, first_vertex: u32, first_instance: u32);
+    fn draw_indexed_primitives(
+        &self,
+        pass: *mut gpu::SDL_GPURenderPass,
+        num_indices: u32,
+        num_instances: u32,
+        first_index: u32,
+        vertex_offset: i32,
+        first_instance: u32,
+    );
+    fn draw_primitives_indirect(&self, pass: *mut gpu::SDL_GPURenderPass, buffer: *mut gpu::SDL_GPUBuffer, offset: u32, draw_count: u32);
+    fn draw_indexed_primitives_indirect(&self, pass: *mut gpu::SDL_GPURenderPass, buffer: *mut gpu::SDL_GPUBuffer, offset: u32, draw_count: u32);
+    fn set_viewport(&self, pass: *mut gpu::SDL_GPURenderPass, viewport: &gpu::SDL_GPUViewport);
+    fn set_scissor(&self, pass: *mut gpu::SDL_GPURenderPass, rect: &sys::rect::SDL_Rect);
+    fn set_stencil_reference(&self, pass: *mut gpu::SDL_GPURenderPass, reference: u8);
+    fn set_blend_constants(&self, pass: *mut gpu::SDL_GPURenderPass, blend_constants: sys::pixels::SDL_FColor);
+    fn end_render_pass(&self, pass: *mut gpu::SDL_GPURenderPass);
+
+    fn begin_compute_pass(
+        &self,
+        cmd: *mut gpu::SDL_GPUCommandBuffer,
+        storage_textures: &[gpu::SDL_GPUStorageTextureReadWriteBinding],
+        storage_buffers: &[gpu::SDL_GPUStorageBufferReadWriteBinding],
+    ) -> *mut gpu::SDL_GPUComputePass;
+    fn bind_compute_pipeline(&self, pass: *mut gpu::SDL_GPUComputePass, pipeline: *mut gpu::SDL_GPUComputePipeline);
+    fn bind_compute_storage_textures(&self, pass: *mut gpu::SDL_GPUComputePass, first_slot: u32, textures: &[*mut gpu::SDL_GPUTexture]);
+    fn bind_compute_storage_buffers(&self, pass: *mut gpu::SDL_GPUComputePass, first_slot: u32, buffers: &[*mut gpu::SDL_GPUBuffer]);
+    fn bind_compute_samplers(&self, pass: *mut gpu::SDL_GPUComputePass, first_slot: u32, bindings: &[gpu::SDL_GPUTextureSamplerBinding]);
+    fn dispatch_compute(&self, pass: *mut gpu::SDL_GPUComputePass, groupcount_x: u32, groupcount_y: u32, groupcount_z: u32);
+    fn dispatch_compute_indirect(&self, pass: *mut gpu::SDL_GPUComputePass, buffer: *mut gpu::SDL_GPUBuffer, offset: u32);
+    fn end_compute_pass(&self, pass: *mut gpu::SDL_GPUComputePass);
+}
+
+/// Null for an empty slice, as SDL expects for zero counts.
+fn slice_ptr<T>(slice: &[T]) -> *const T {
+    if slice.is_empty() { std::ptr::null() } else { slice.as_ptr() }
+}
+
+/// The real thing: an `SDL_GPUDevice`, destroyed on drop.
+pub(crate) struct SdlBackend {
+    device: *mut gpu::SDL_GPUDevice,
+}
+
+impl SdlBackend {
+    pub fn new(format: gpu::SDL_GPUShaderFormat) -> Result<Self, &'static str> {
+        let device = unsafe { gpu::SDL_CreateGPUDevice(format, true, std::ptr::null()) };
+        if device.is_null() {
+            return Err("SDL CreateGPUDevice failed.");
+        }
+        Ok(Self { device })
+    }
+}
+
+// SAFETY: SDL GPU devices may be used from any thread; SDL only requires
+// each command buffer to stay on the thread that acquired it, which the
+// command buffer types, being neither Send nor Sync, ensure.
+unsafe impl Send for SdlBackend {}
+unsafe impl Sync for SdlBackend {}
+
+impl Drop for SdlBackend {
+    fn drop(&mut self) {
+        unsafe { gpu::SDL_DestroyGPUDevice(self.device) }
+    }
+}
+
+impl Backend for SdlBackend {
+    fn create_texture(&self, info: &gpu::SDL_GPUTextureCreateInfo) -> *mut gpu::SDL_GPUTexture {
+        unsafe { gpu::SDL_CreateGPUTexture(self.device, info) }
+    }
+
+    fn release_texture(&self, texture: *mut gpu::SDL_GPUTexture) {
+        unsafe { gpu::SDL_ReleaseGPUTexture(self.device, texture) }
+    }
+
+    fn create_shader(&self, info: &gpu::SDL_GPUShaderCreateInfo) -> *mut gpu::SDL_GPUShader {
+        unsafe { gpu::SDL_CreateGPUShader(self.device, info) }
+    }
+
+    fn release_shader(&self, shader: *mut gpu::SDL_GPUShader) {
+        unsafe { gpu::SDL_ReleaseGPUShader(self.device, shader) }
+    }
+
+    fn create_graphics_pipeline(&self, info: &gpu::SDL_GPUGraphicsPipelineCreateInfo) -> *mut gpu::SDL_GPUGraphicsPipeline {
+        unsafe { gpu::SDL_CreateGPUGraphicsPipeline(self.device, info) }
+    }
+
+    fn release_graphics_pipeline(&self, pipeline: *mut gpu::SDL_GPUGraphicsPipeline) {
+        unsafe { gpu::SDL_ReleaseGPUGraphicsPipeline(self.device, pipeline) }
+    }
+
+    fn create_compute_pipeline(&self, info: &gpu::SDL_GPUComputePipelineCreateInfo) -> *mut gpu::SDL_GPUComputePipeline {
+        unsafe { gpu::SDL_CreateGPUComputePipeline(self.device, info) }
+    }
+
+    fn release_compute_pipeline(&self, pipeline: *mut gpu::SDL_GPUComputePipeline) {
+        unsafe { gpu::SDL_ReleaseGPUComputePipeline(self.device, pipeline) }
+    }
+
+    fn create_buffer(&self, info: &gpu::SDL_GPUBufferCreateInfo) -> *mut gpu::SDL_GPUBuffer {
+        unsafe { gpu::SDL_CreateGPUBuffer(self.device, info) }
+    }
+
+    fn release_buffer(&self, buffer: *mut gpu::SDL_GPUBuffer) {
+        unsafe { gpu::SDL_ReleaseGPUBuffer(self.device, buffer) }
+    }
+
+    fn create_sampler(&self, info: &gpu::SDL_GPUSamplerCreateInfo) -> *mut gpu::SDL_GPUSampler {
+        unsafe { gpu::SDL_CreateGPUSampler(self.device, info) }
+    }
+
+    fn release_sampler(&self, sampler: *mut gpu::SDL_GPUSampler) {
+        unsafe { gpu::SDL_ReleaseGPUSampler(self.device, sampler) }
+    }
+
+    fn create_transfer_buffer(&self, info: &gpu::SDL_GPUTransferBufferCreateInfo) -> *mut gpu::SDL_GPUTransferBuffer {
+        unsafe { gpu::SDL_CreateGPUTransferBuffer(self.device, info) }
+    }
+
+    fn release_transfer_buffer(&self, transfer: *mut gpu::SDL_GPUTransferBuffer) {
+        unsafe { gpu::SDL_ReleaseGPUTransferBuffer(self.device, transfer) }
+    }
+
+    fn map_transfer_buffer(&self, transfer: *mut gpu::SDL_GPUTransferBuffer, cycle: bool) -> *mut u8 {
+        unsafe { gpu::SDL_MapGPUTransferBuffer(self.device, transfer, cycle) as *mut u8 }
+    }
+
+    fn unmap_transfer_buffer(&self, transfer: *mut gpu::SDL_GPUTransferBuffer) {
+        unsafe { gpu::SDL_UnmapGPUTransferBuffer(self.device, transfer) }
+    }
+
+    fn set_texture_name(&self, texture: *mut gpu::SDL_GPUTexture, name: &CStr) {
+        unsafe { gpu::SDL_SetGPUTextureName(self.device, texture, name.as_ptr()) }
+    }
+
+    fn set_buffer_name(&self, buffer: *mut gpu::SDL_GPUBuffer, name: &CStr) {
+        unsafe { gpu::SDL_SetGPUBufferName(self.device, buffer, name.as_ptr()) }
+    }
+
+    fn claim_window(&self, window: *mut SDL_Window) -> bool {
+        unsafe { gpu::SDL_ClaimWindowForGPUDevice(self.device, window) }
+    }
+
+    fn release_window(&self, window: *mut SDL_Window) {
+        // A window destroyed while claimed is gone; SDL validates the
+        // pointer here without dereferencing it.
+        if unsafe { sys::video::SDL_GetWindowID(window) } == 0 {
+            return;
+        }
+        unsafe { gpu::SDL_ReleaseWindowFromGPUDevice(self.device, window) }
+    }
+
+    fn window_supports_present_mode(&self, window: *mut SDL_Window, mode: gpu::SDL_GPUPresentMode) -> bool {
+        unsafe { gpu::SDL_WindowSupportsGPUPresentMode(self.device, window, mode) }
+    }
+
+    fn window_supports_swapchain_composition(&self, window: *mut SDL_Window, composition: gpu::SDL_GPUSwapchainComposition) -> bool {
+        unsafe { gpu::SDL_WindowSupportsGPUSwapchainComposition(self.device, window, composition) }
+    }
+
+    fn set_swapchain_parameters(
+        &self,
+        window: *mut SDL_Window,
+        composition: gpu::SDL_GPUSwapchainComposition,
+        present_mode: gpu::SDL_GPUPresentMode,
+    ) -> bool {
+        unsafe { gpu::SDL_SetGPUSwapchainParameters(self.device, window, composition, present_mode) }
+    }
+
+    fn set_allowed_frames_in_flight(&self, frames: u32) -> bool {
+        unsafe { gpu::SDL_SetGPUAllowedFramesInFlight(self.device, frames) }
+    }
+
+    fn swapchain_texture_format(&self, window: *mut SDL_Window) -> gpu::SDL_GPUTextureFormat {
+        unsafe { gpu::SDL_GetGPUSwapchainTextureFormat(self.device, window) }
+    }
+
+    fn wait_for_swapchain(&self, window: *mut SDL_Window) -> bool {
+        unsafe { gpu::SDL_WaitForGPUSwapchain(self.device, window) }
+    }
+
+    fn shader_formats(&self) -> gpu::SDL_GPUShaderFormat {
+        unsafe { gpu::SDL_GetGPUShaderFormats(self.device) }
+    }
+
+    fn driver_name(&self) -> String {
+        unsafe { std::ffi::CStr::from_ptr(gpu::SDL_GetGPUDeviceDriver(self.device)).to_string_lossy().to_string() }
+    }
+
+    fn device_properties(&self) -> sys::properties::SDL_PropertiesID {
+        unsafe { gpu::SDL_GetGPUDeviceProperties(self.device) }
+    }
+
+    fn acquire_command_buffer(&self) -> *mut gpu::SDL_GPUCommandBuffer {
+        unsafe { gpu::SDL_AcquireGPUCommandBuffer(self.device) }
+    }
+
+    fn submit_command_buffer(&self, cmd: *mut gpu::SDL_GPUCommandBuffer) -> bool {
+        unsafe { gpu::SDL_SubmitGPUCommandBuffer(cmd) }
+    }
+
+    fn submit_command_buffer_and_acquire_fence(&self, cmd: *mut gpu::SDL_GPUCommandBuffer) -> *mut gpu::SDL_GPUFence {
+        unsafe { gpu::SDL_SubmitGPUCommandBufferAndAcquireFence(cmd) }
+    }
+
+    fn cancel_command_buffer(&self, cmd: *mut gpu::SDL_GPUCommandBuffer) {
+        unsafe { gpu::SDL_CancelGPUCommandBuffer(cmd); }
+    }
+
+    fn query_fence(&self, fence: *mut gpu::SDL_GPUFence) -> bool {
+        unsafe { gpu::SDL_QueryGPUFence(self.device, fence) }
+    }
+
+    fn wait_for_fence(&self, fence: *mut gpu::SDL_GPUFence) -> bool {
+        unsafe { gpu::SDL_WaitForGPUFences(self.device, true, &fence, 1) }
+    }
+
+    fn release_fence(&self, fence: *mut gpu::SDL_GPUFence) {
+        unsafe { gpu::SDL_ReleaseGPUFence(self.device, fence) }
+    }
+
+    fn acquire_swapchain_texture(
+        &self,
+        cmd: *mut gpu::SDL_GPUCommandBuffer,
+        window: *mut SDL_Window,
+        wait: bool,
+    ) -> Option<(*mut gpu::SDL_GPUTexture, u32, u32)> {
+        let mut texture: *mut gpu::SDL_GPUTexture = std::ptr::null_mut();
+        let mut width: u32 = 0;
+        let mut height: u32 = 0;
+        let ok = unsafe {
+            if wait {
+                gpu::SDL_WaitAndAcquireGPUSwapchainTexture(cmd, window, &mut texture, &mut width, &mut height)
+            } else {
+                gpu::SDL_AcquireGPUSwapchainTexture(cmd, window, &mut texture, &mut width, &mut height)
+            }
+        };
+        ok.then_some((texture, width, height))
+    }
+
+    fn blit_texture(&self, cmd: *mut gpu::SDL_GPUCommandBuffer, info: &gpu::SDL_GPUBlitInfo) {
+        unsafe { gpu::SDL_BlitGPUTexture(cmd, info) }
+    }
+
+    fn insert_debug_label(&self, cmd: *mut gpu::SDL_GPUCommandBuffer, text: &CStr) {
+        unsafe { gpu::SDL_InsertGPUDebugLabel(cmd, text.as_ptr()) }
+    }
+
+    fn push_debug_group(&self, cmd: *mut gpu::SDL_GPUCommandBuffer, name: &CStr) {
+        unsafe { gpu::SDL_PushGPUDebugGroup(cmd, name.as_ptr()) }
+    }
+
+    fn pop_debug_group(&self, cmd: *mut gpu::SDL_GPUCommandBuffer) {
+        unsafe { gpu::SDL_PopGPUDebugGroup(cmd) }
+    }
+
+    fn push_vertex_uniform_data(&self, cmd: *mut gpu::SDL_GPUCommandBuffer, slot_index: u32, data: &[u8]) {
+        unsafe { gpu::SDL_PushGPUVertexUniformData(cmd, slot_index, data.as_ptr() as *const std::ffi::c_void, data.len() as u32) }
+    }
+
+    fn push_fragment_uniform_data(&self, cmd: *mut gpu::SDL_GPUCommandBuffer, slot_index: u32, data: &[u8]) {
+        unsafe { gpu::SDL_PushGPUFragmentUniformData(cmd, slot_index, data.as_ptr() as *const std::ffi::c_void, data.len() as u32) }
+    }
+
+    fn push_compute_uniform_data(&self, cmd: *mut gpu::SDL_GPUCommandBuffer, slot_index: u32, data: &[u8]) {
+        unsafe { gpu::SDL_PushGPUComputeUniformData(cmd, slot_index, data.as_ptr() as *const std::ffi::c_void, data.len() as u32) }
+    }
+
+    fn begin_copy_pass(&self, cmd: *mut gpu::SDL_GPUCommandBuffer) -> *mut gpu::SDL_GPUCopyPass {
+        unsafe { gpu::SDL_BeginGPUCopyPass(cmd) }
+    }
+
+    fn upload_to_buffer(
+        &self,
+        pass: *mut gpu::SDL_GPUCopyPass,
+        source: &gpu::SDL_GPUTransferBufferLocation,
+        destination: &gpu::SDL_GPUBufferRegion,
+        cycle: bool,
+    ) {
+        unsafe { gpu::SDL_UploadToGPUBuffer(pass, source, destination, cycle) }
+    }
+
+    fn upload_to_texture(
+        &self,
+        pass: *mut gpu::SDL_GPUCopyPass,
+        source: &gpu::SDL_GPUTextureTransferInfo,
+        destination: &gpu::SDL_GPUTextureRegion,
+        cycle: bool,
+    ) {
+        unsafe { gpu::SDL_UploadToGPUTexture(pass, source, destination, cycle) }
+    }
+
+    fn download_from_buffer(
+        &self,
+        pass: *mut gpu::SDL_GPUCopyPass,
+        source: &gpu::SDL_GPUBufferRegion,
+        destination: &gpu::SDL_GPUTransferBufferLocation,
+    ) {
+        unsafe { gpu::SDL_DownloadFromGPUBuffer(pass, source, destination) }
+    }
+
+    fn download_from_texture(
+        &self,
+        pass: *mut gpu::SDL_GPUCopyPass,
+        source: &gpu::SDL_GPUTextureRegion,
+        destination: &gpu::SDL_GPUTextureTransferInfo,
+    ) {
+        unsafe { gpu::SDL_DownloadFromGPUTexture(pass, source, destination) }
+    }
+
+    fn copy_buffer_to_buffer(
+        &self,
+        pass: *mut gpu::SDL_GPUCopyPass,
+        source: &gpu::SDL_GPUBufferLocation,
+        destination: &gpu::SDL_GPUBufferLocation,
+        size: u32,
+        cycle: bool,
+    ) {
+        unsafe { gpu::SDL_CopyGPUBufferToBuffer(pass, source, destination, size, cycle) }
+    }
+
+    fn end_copy_pass(&self, pass: *mut gpu::SDL_GPUCopyPass) {
+        unsafe { gpu::SDL_EndGPUCopyPass(pass) }
+    }
+
+    fn begin_render_pass(
+        &self,
+        cmd: *mut gpu::SDL_GPUCommandBuffer,
+        color_targets: &[gpu::SDL_GPUColorTargetInfo],
+        depth_stencil_target: Option<&gpu::SDL_GPUDepthStencilTargetInfo>,
+    ) -> *mut gpu::SDL_GPURenderPass {
+        let depth_stencil_target = depth_stencil_target
+            .map(|ds| ds as *const gpu::SDL_GPUDepthStencilTargetInfo)
+            .unwrap_or(std::ptr::null());
+        unsafe {
+            gpu::SDL_BeginGPURenderPass(cmd, color_targets.as_ptr(), color_targets.len() as u32, depth_stencil_target)
+        }
+    }
+
+    fn bind_graphics_pipeline(&self, pass: *mut gpu::SDL_GPURenderPass, pipeline: *mut gpu::SDL_GPUGraphicsPipeline) {
+        unsafe { gpu::SDL_BindGPUGraphicsPipeline(pass, pipeline) }
+    }
+
+    fn bind_vertex_buffers(&self, pass: *mut gpu::SDL_GPURenderPass, first_slot: u32, bindings: &[gpu::SDL_GPUBufferBinding]) {
+        unsafe { gpu::SDL_BindGPUVertexBuffers(pass, first_slot, bindings.as_ptr(), bindings.len() as u32) }
+    }
+
+    fn bind_index_buffer(
+        &self,
+        pass: *mut gpu::SDL_GPURenderPass,
+        binding: &gpu::SDL_GPUBufferBinding,
+        index_element_size: gpu::SDL_GPUIndexElementSize,
+    ) {
+        unsafe { gpu::SDL_BindGPUIndexBuffer(pass, binding, index_element_size) }
+    }
+
+    fn bind_fragment_samplers(&self, pass: *mut gpu::SDL_GPURenderPass, first_slot: u32, bindings: &[gpu::SDL_GPUTextureSamplerBinding]) {
+        unsafe { gpu::SDL_BindGPUFragmentSamplers(pass, first_slot, bindings.as_ptr(), bindings.len() as u32) }
+    }
+
+    fn bind_fragment_storage_textures(&self, pass: *mut gpu::SDL_GPURenderPass, first_slot: u32, textures: &[*mut gpu::SDL_GPUTexture]) {
+        unsafe { gpu::SDL_BindGPUFragmentStorageTextures(pass, first_slot, textures.as_ptr(), textures.len() as u32) }
+    }
+
+    fn bind_fragment_storage_buffers(&self, pass: *mut gpu::SDL_GPURenderPass, first_slot: u32, buffers: &[*mut gpu::SDL_GPUBuffer]) {
+        unsafe { gpu::SDL_BindGPUFragmentStorageBuffers(pass, first_slot, buffers.as_ptr(), buffers.len() as u32) }
+    }
+
+    fn bind_vertex_storage_buffers(&self, pass: *mut gpu::SDL_GPURenderPass, first_slot: u32, buffers: &[*mut gpu::SDL_GPUBuffer]) {
+        unsafe { gpu::SDL_BindGPUVertexStorageBuffers(pass, first_slot, buffers.as_ptr(), buffers.len() as u32) }
+    }
+
+    fn bind_vertex_samplers(&self, pass: *mut gpu::SDL_GPURenderPass, first_slot: u32, bindings: &[gpu::SDL_GPUTextureSamplerBinding]) {
+        unsafe { gpu::SDL_BindGPUVertexSamplers(pass, first_slot, bindings.as_ptr(), bindings.len() as u32) }
+    }
+
+    fn bind_vertex_storage_textures(&self, pass: *mut gpu::SDL_GPURenderPass, first_slot: u32, textures: &[*mut gpu::SDL_GPUTexture]) {
+        unsafe { gpu::SDL_BindGPUVertexStorageTextures(pass, first_slot, textures.as_ptr(), textures.len() as u32) }
+    }
+
+    fn draw_primitives(&self, pass: *mut gpu::SDL_GPURenderPass, num_vertices: u32, num_instances: u32, first_vertex: u32, first_instance: u32) {
+        unsafe { gpu::SDL_DrawGPUPrimitives(pass, num_vertices, num_instances, first_vertex, first_instance) }
+    }
+
+    fn draw_indexed_primitives(
+        &self,
+        pass: *mut gpu::SDL_GPURenderPass,
+        num_indices: u32,
+        num_instances: u32,
+        first_index: u32,
+        vertex_offset: i32,
+        first_instance: u32,
+    ) {
+        unsafe { gpu::SDL_DrawGPUIndexedPrimitives(pass, num_indices, num_instances, first_index, vertex_offset, first_instance) }
+    }
+
+    fn draw_primitives_indirect(&self, pass: *mut gpu::SDL_GPURenderPass, buffer: *mut gpu::SDL_GPUBuffer, offset: u32, draw_count: u32) {
+        unsafe { gpu::SDL_DrawGPUPrimitivesIndirect(pass, buffer, offset, draw_count) }
+    }
+
+    fn draw_indexed_primitives_indirect(&self, pass: *mut gpu::SDL_GPURenderPass, buffer: *mut gpu::SDL_GPUBuffer, offset: u32, draw_count: u32) {
+        unsafe { gpu::SDL_DrawGPUIndexedPrimitivesIndirect(pass, buffer, offset, draw_count) }
+    }
+
+    fn set_viewport(&self, pass: *mut gpu::SDL_GPURenderPass, viewport: &gpu::SDL_GPUViewport) {
+        unsafe { gpu::SDL_SetGPUViewport(pass, viewport) }
+    }
+
+    fn set_scissor(&self, pass: *mut gpu::SDL_GPURenderPass, rect: &sys::rect::SDL_Rect) {
+        unsafe { gpu::SDL_SetGPUScissor(pass, rect) }
+    }
+
+    fn set_stencil_reference(&self, pass: *mut gpu::SDL_GPURenderPass, reference: u8) {
+        unsafe { gpu::SDL_SetGPUStencilReference(pass, reference) }
+    }
+
+    fn set_blend_constants(&self, pass: *mut gpu::SDL_GPURenderPass, blend_constants: sys::pixels::SDL_FColor) {
+        unsafe { gpu::SDL_SetGPUBlendConstants(pass, blend_constants) }
+    }
+
+    fn end_render_pass(&self, pass: *mut gpu::SDL_GPURenderPass) {
+        unsafe { gpu::SDL_EndGPURenderPass(pass) }
+    }
+
+    fn begin_compute_pass(
+        &self,
+        cmd: *mut gpu::SDL_GPUCommandBuffer,
+        storage_textures: &[gpu::SDL_GPUStorageTextureReadWriteBinding],
+        storage_buffers: &[gpu::SDL_GPUStorageBufferReadWriteBinding],
+    ) -> *mut gpu::SDL_GPUComputePass {
+        unsafe {
+            gpu::SDL_BeginGPUComputePass(
+                cmd,
+                slice_ptr(storage_textures),
+                storage_textures.len() as u32,
+                slice_ptr(storage_buffers),
+                storage_buffers.len() as u32,
+            )
+        }
+    }
+
+    fn bind_compute_pipeline(&self, pass: *mut gpu::SDL_GPUComputePass, pipeline: *mut gpu::SDL_GPUComputePipeline) {
+        unsafe { gpu::SDL_BindGPUComputePipeline(pass, pipeline) }
+    }
+
+    fn bind_compute_storage_textures(&self, pass: *mut gpu::SDL_GPUComputePass, first_slot: u32, textures: &[*mut gpu::SDL_GPUTexture]) {
+        unsafe { gpu::SDL_BindGPUComputeStorageTextures(pass, first_slot, textures.as_ptr(), textures.len() as u32) }
+    }
+
+    fn bind_compute_storage_buffers(&self, pass: *mut gpu::SDL_GPUComputePass, first_slot: u32, buffers: &[*mut gpu::SDL_GPUBuffer]) {
+        unsafe { gpu::SDL_BindGPUComputeStorageBuffers(pass, first_slot, buffers.as_ptr(), buffers.len() as u32) }
+    }
+
+    fn bind_compute_samplers(&self, pass: *mut gpu::SDL_GPUComputePass, first_slot: u32, bindings: &[gpu::SDL_GPUTextureSamplerBinding]) {
+        unsafe { gpu::SDL_BindGPUComputeSamplers(pass, first_slot, bindings.as_ptr(), bindings.len() as u32) }
+    }
+
+    fn dispatch_compute(&self, pass: *mut gpu::SDL_GPUComputePass, groupcount_x: u32, groupcount_y: u32, groupcount_z: u32) {
+        unsafe { gpu::SDL_DispatchGPUCompute(pass, groupcount_x, groupcount_y, groupcount_z) }
+    }
+
+    fn dispatch_compute_indirect(&self, pass: *mut gpu::SDL_GPUComputePass, buffer: *mut gpu::SDL_GPUBuffer, offset: u32) {
+        unsafe { gpu::SDL_DispatchGPUComputeIndirect(pass, buffer, offset) }
+    }
+
+    fn end_compute_pass(&self, pass: *mut gpu::SDL_GPUComputePass) {
+        unsafe { gpu::SDL_EndGPUComputePass(pass) }
+    }
+}
@@ -0,0 +1,125 @@
+use std::path::Path;
+
+use crate::device::{
+    ColorTargetInfo, DepthStencilTargetInfo, Device, SDL_FColor, SDL_GPULoadOp, SDL_GPUStoreOp, SDL_GPUTextureFormat,
+    SDL_GPUTextureUsageFlags, Texture, TextureDesc, TextureRegion,
+};
+use crate::image::Image;
+
+/// An offscreen color and depth target of a fixed size, for rendering
+/// without a window, e.g. on a [`Device::new_headless`] device.
+///
+/// Render into it with [`color_target`](Self::color_target) and
+/// [`depth_target`](Self::depth_target), submit, then read it back with
+/// [`read_image`](Self::read_image) or [`save`](Self::save).
+pub struct Canvas {
+    color: Texture,
+    depth: Texture,
+    width: u32,
+    height: u32,
+}
+
+impl Canvas {
+    /// An RGBA8 canvas with a 16-bit depth buffer.
+    pub fn new(device: &Device, width: u32, height: u32) -> Result<Self, &'static str> {
+        Self::with_formats(
+            device,
+            width,
+            height,
+            SDL_GPUTextureFormat::R8G8B8A8_UNORM,
+            Some(SDL_GPUTextureFormat::D16_UNORM),
+        )
+    }
+
+    /// A canvas with the given color format and optional depth format.
+    /// Reading back needs an 8-bit RGBA or BGRA color format.
+    pub fn with_formats(
+        device: &Device,
+        width: u32,
+        height: u32,
+        color_format: SDL_GPUTextureFormat,
+        depth_format: Option<SDL_GPUTextureFormat>,
+    ) -> Result<Self, &'static str> {
+        let usage = SDL_GPUTextureUsageFlags::COLOR_TARGET | SDL_GPUTextureUsageFlags::SAMPLER;
+        let color = device.create_texture(&TextureDesc::new(color_format, width, height, usage).to_create_info())?;
+        let depth = match depth_format {
+            Some(format) => {
+                let desc = TextureDesc::new(format, width, height, SDL_GPUTextureUsageFlags::DEPTH_STENCIL_TARGET);
+                match device.create_texture(&desc.to_create_info()) {
+                    Ok(depth) => depth,
+                    Err(e) => {
+                        let mut color = color;
+                        color.destroy(device);
+                        return Err(e);
+                    }
+                }
+            }
+            None => Texture::NONE,
+        };
+        Ok(Self { color, depth, width, height })
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    pub fn color_texture(&self) -> Texture {
+        self.color
+    }
+
+    pub fn depth_texture(&self) -> Option<Texture> {
+        self.depth.is_valid().then_some(self.depth)
+    }
+
+    /// Color target info clearing to `clear`, or loading if `None`.
+    pub fn color_target(&self, clear: Option<SDL_FColor>) -> ColorTargetInfo {
+        let mut info = ColorTargetInfo::new(self.color);
+        match clear {
+            Some(color) => {
+                info.clear_color = color;
+                info.load_op = SDL_GPULoadOp::CLEAR;
+            }
+            None => info.load_op = SDL_GPULoadOp::LOAD,
+        }
+        info.store_op = SDL_GPUStoreOp::STORE;
+        info
+    }
+
+    /// Depth target info clearing to `clear`, or loading if `None`. `None`
+    /// without a depth buffer.
+    pub fn depth_target(&self, clear: Option<f32>) -> Option<DepthStencilTargetInfo> {
+        let depth = self.depth_texture()?;
+        let mut info = DepthStencilTargetInfo::new(depth);
+        match clear {
+            Some(value) => {
+                info.clear_depth = value;
+                info.load_op = SDL_GPULoadOp::CLEAR;
+            }
+            None => info.load_op = SDL_GPULoadOp::LOAD,
+        }
+        info.store_op = SDL_GPUStoreOp::STORE;
+        info.stencil_load_op = SDL_GPULoadOp::DONT_CARE;
+        info.stencil_store_op = SDL_GPUStoreOp::DONT_CARE;
+        Some(info)
+    }
+
+    /// Download the color texture into an RGBA image. Waits for the command
+    /// buffers submitted so far to finish.
+    pub fn read_image(&self, device: &Device) -> Result<Image, &'static str> {
+        let data = device.download_from_texture(&TextureRegion::full(self.color, device))?;
+        Image::from_texture_data(device.get_texture_format(self.color), self.width, self.height, &data)
+    }
+
+    /// Read the color texture back and write it to `path`, see [`Image::save`].
+    pub fn save(&self, device: &Device, path: impl AsRef<Path>) -> Result<(), &'static str> {
+        self.read_image(device)?.save(path)
+    }
+
+    pub fn destroy(&mut self, device: &Device) {
+        for texture in [&mut self.color, &mut self.depth] {
+            if texture.is_valid() {
+                texture.destroy(device);
+            }
+        }
+    }
+}
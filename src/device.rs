@@ -1,5 +1,10 @@
-use std::cell::{Cell, RefCell};
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::cell::{Cell, OnceCell, RefCell};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::panic::Location;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 
 use sdl3_sys as sys;
 use sys::*;
@@ -41,8 +46,23 @@ pub use sys::pixels::SDL_FColor;
 pub use sys::rect::SDL_Rect;
 pub use sys::surface::SDL_FlipMode;
 pub use gpu::SDL_GPUViewport;
-
-use crate::slot_map::SlotMapRefCell;
+pub use gpu::SDL_GPUPresentMode;
+pub use gpu::SDL_GPUSwapchainComposition;
+
+use crate::backend::{Backend, SdlBackend};
+use crate::inventory::{self, LiveResource, ResourceInventory, ResourceKind};
+use crate::properties::{read_string_property, StringProperty};
+use crate::null_backend::{CommandLog, NullBackend};
+use crate::slot_map::SlotMapMutex;
+use crate::screenshot::PendingScreenshot;
+use crate::shared_device::SharedDevice;
+use crate::texture_pool::{TexturePool, TexturePoolStats};
+use crate::validation::{self, ComputePassState, ComputeRequirements, GraphicsRequirements, RenderPassState, StageResources, TargetFormats};
+use crate::window::{Window, WindowId};
+#[cfg(feature = "naga")]
+use crate::shader_compiler::{self, ShaderSourceError, ShaderStage, SourceLanguage};
+#[cfg(feature = "naga")]
+use crate::tools::ShaderTarget;
 
 fn validate_sample_count(sample_count: gpu::SDL_GPUSampleCount) -> Result<(), &'static str> {
     match sample_count {
@@ -173,6 +193,90 @@ impl DepthStencilTargetInfo {
     }
 }
 
+/// Presentation settings for a window's swapchain, applied with
+/// [`Device::set_swapchain_config`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SwapchainConfig {
+    pub present_mode: SDL_GPUPresentMode,
+    pub composition: SDL_GPUSwapchainComposition,
+    /// How many frames the CPU may get ahead of the GPU, 1 to 3.
+    pub frames_in_flight: u32,
+}
+
+impl Default for SwapchainConfig {
+    /// SDL's defaults: VSYNC, SDR and two frames in flight.
+    fn default() -> Self {
+        Self {
+            present_mode: SDL_GPUPresentMode::VSYNC,
+            composition: SDL_GPUSwapchainComposition::SDR,
+            frames_in_flight: 2,
+        }
+    }
+}
+
+/// Commands recorded during a frame, read with [`Device::frame_stats`] and
+/// reset with [`Device::take_frame_stats`].
+///
+/// Indirect draws and dispatches count once per draw, with unknown
+/// instance counts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrameStats {
+    /// Draw calls, indexed or not.
+    pub draws: u32,
+    /// Draw calls that read an index buffer.
+    pub indexed_draws: u32,
+    /// Instances drawn by direct draw calls.
+    pub instances: u64,
+    pub dispatches: u32,
+    /// Graphics and compute pipeline binds.
+    pub pipeline_binds: u32,
+    /// Sampled and storage textures bound.
+    pub texture_binds: u32,
+    pub sampler_binds: u32,
+    /// Bytes pushed to uniform slots.
+    pub uniform_bytes: u64,
+    /// Bytes staged for buffer and texture uploads.
+    pub upload_bytes: u64,
+    /// Bytes read back from buffers and textures.
+    pub download_bytes: u64,
+}
+
+impl FrameStats {
+    pub fn non_indexed_draws(&self) -> u32 {
+        self.draws - self.indexed_draws
+    }
+}
+
+/// The properties that make two 2D textures interchangeable.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextureDesc {
+    pub format: SDL_GPUTextureFormat,
+    pub width: u32,
+    pub height: u32,
+    pub usage: SDL_GPUTextureUsageFlags,
+    pub sample_count: SDL_GPUSampleCount,
+}
+
+impl TextureDesc {
+    pub fn new(format: SDL_GPUTextureFormat, width: u32, height: u32, usage: SDL_GPUTextureUsageFlags) -> Self {
+        Self { format, width, height, usage, sample_count: SDL_GPUSampleCount::_1 }
+    }
+
+    pub fn to_create_info(&self) -> gpu::SDL_GPUTextureCreateInfo {
+        gpu::SDL_GPUTextureCreateInfo {
+            r#type: SDL_GPUTextureType::_2D,
+            format: self.format,
+            usage: self.usage,
+            width: self.width,
+            height: self.height,
+            layer_count_or_depth: 1,
+            num_levels: 1,
+            sample_count: self.sample_count,
+            props: sys::properties::SDL_PropertiesID(0),
+        }
+    }
+}
+
 /// A region of a texture, using a safe `Texture` handle instead of a raw pointer.
 pub struct TextureRegion {
     pub texture: Texture,
@@ -328,22 +432,28 @@ pub struct ShaderCreateInfo<'a> {
     pub num_storage_buffers: u32,
     /// The number of uniform buffers defined in the shader.
     pub num_uniform_buffers: u32,
+    /// Name shown in graphics debuggers.
+    pub name: Option<&'a str>,
 }
 
 pub struct Device
 {
-    inner: *mut gpu::SDL_GPUDevice,
+    backend: Arc<dyn Backend>,
     window : Option<crate::window::Window>,
-    textures: SlotMapRefCell<TextureSlot>,
-    shaders: SlotMapRefCell<ShaderSlot>,
-    graphics_pipelines: SlotMapRefCell<GraphicsPipelineSlot>,
-    compute_pipelines: SlotMapRefCell<ComputePipelineSlot>,
-    buffers: SlotMapRefCell<BufferSlot>,
-    samplers: SlotMapRefCell<SamplerSlot>,
-    swapchain: Cell<(*mut gpu::SDL_GPUTexture, u32, u32)>,
+    resources: Arc<Resources>,
+    windows: RefCell<HashMap<WindowId, ClaimedWindow>>,
     upload_transfer_buffer: Cell<(*mut gpu::SDL_GPUTransferBuffer, u32)>,
     cmd_buf_count: AtomicU32,
+    next_cmd_buf_id: Cell<u64>,
     pending_transfer_buffers: RefCell<Vec<*mut gpu::SDL_GPUTransferBuffer>>,
+    texture_pool: RefCell<TexturePool>,
+    /// Configuration of the device's own window, applied again when it is
+    /// claimed again after a release.
+    swapchain_config: Cell<SwapchainConfig>,
+    swapchain_capture: Cell<bool>,
+    leak_report: Cell<bool>,
+    frame_stats: Cell<FrameStats>,
+    draw_validation: Cell<bool>,
 }
 
 impl Device {
@@ -354,240 +464,520 @@ impl Device {
 
     pub fn new(format : gpu::SDL_GPUShaderFormat, window : Option<crate::window::Window>) -> Result<Self,&'static str>
     {
-        unsafe {
-            let sys_device = gpu::SDL_CreateGPUDevice(
-                format,
-                true,
-                std::ptr::null(),
-            );
+        let backend = SdlBackend::new(format)?;
+        if let Some(window) = &window
+            && !backend.claim_window(window.raw())
+        {
+            return Err("SDL_ClaimWindowForGPUDevice failed");
+        }
+        Ok(Self::with_backend(Arc::new(backend), window))
+    }
 
-            if sys_device.is_null()
-            {
-                return Err("SDL CreateGPUDevice failed.");
-            }
+    /// Create a device on the null backend, which needs no GPU: nothing is
+    /// drawn, and every call that would reach the GPU is recorded in the
+    /// returned log instead. Shaders are accepted in `format`.
+    ///
+    /// See [`crate::null_backend`] for what it simulates.
+    pub fn new_null(format: gpu::SDL_GPUShaderFormat) -> (Self, CommandLog) {
+        let (backend, log) = NullBackend::new(format);
+        (Self::with_backend(Arc::new(backend), None), log)
+    }
 
+    /// `window`, if any, must already be claimed by `backend`.
+    fn with_backend(backend: Arc<dyn Backend>, window: Option<crate::window::Window>) -> Self
+    {
+        {
+            let mut windows = HashMap::new();
             if let Some(window) = &window
             {
-                gpu::SDL_ClaimWindowForGPUDevice(sys_device,window.raw());
+                windows.insert(window.id(), ClaimedWindow::new(0, window.raw()));
             }
             
-            Ok(Device {
-                inner: sys_device,
+            Device {
+                resources: Arc::new(Resources::new(backend.clone())),
+                backend,
                 window,
-                textures: SlotMapRefCell::default(),
-                shaders: SlotMapRefCell::default(),
-                graphics_pipelines: SlotMapRefCell::default(),
-                compute_pipelines: SlotMapRefCell::default(),
-                buffers: SlotMapRefCell::default(),
-                samplers: SlotMapRefCell::default(),
-                swapchain: Cell::new((std::ptr::null_mut(), 0, 0)),
+                windows: RefCell::new(windows),
                 upload_transfer_buffer: Cell::new((std::ptr::null_mut(), 0)),
                 cmd_buf_count: AtomicU32::new(0),
+                next_cmd_buf_id: Cell::new(0),
                 pending_transfer_buffers: RefCell::new(Vec::new()),
-            })
+                texture_pool: RefCell::new(TexturePool::new()),
+                swapchain_config: Cell::new(SwapchainConfig::default()),
+                swapchain_capture: Cell::new(false),
+                leak_report: Cell::new(false),
+                frame_stats: Cell::new(FrameStats::default()),
+                draw_validation: Cell::new(false),
+            }
         }
-        
     }
 
-    /// Release the window from the GPU device. Must be called on Android
-    /// when the app enters the background (`SDL_EVENT_DID_ENTER_BACKGROUND`).
-    pub fn release_window(&self) {
-        if let Some(window) = &self.window {
-            unsafe { gpu::SDL_ReleaseWindowFromGPUDevice(self.inner, window.raw()); }
+    /// Create a device without a window, for offscreen rendering into a
+    /// [`Canvas`](crate::canvas::Canvas). No event loop is needed.
+    pub fn new_headless(format: gpu::SDL_GPUShaderFormat) -> Result<Self, &'static str> {
+        Self::new(format, None)
+    }
+
+    /// Release `window` from the GPU device. Windows claimed with
+    /// [`claim_window`](Self::claim_window) must be released before they are
+    /// dropped. On Android the device's own window must be released when the
+    /// app enters the background (`SDL_EVENT_DID_ENTER_BACKGROUND`).
+    pub fn release_window(&self, window: &Window) {
+        let claimed = self.windows.borrow_mut().remove(&window.id());
+        if let Some(mut claimed) = claimed {
+            if self.window.as_ref().is_some_and(|w| w.id() == window.id()) {
+                self.swapchain_config.set(claimed.config);
+            }
+            if claimed.capture.is_valid() {
+                claimed.capture.destroy(self);
+            }
+            self.backend.release_window(window.raw());
+        }
+    }
+
+    /// Claim `window` for the GPU device so command buffers can acquire its
+    /// swapchain texture. Any number of windows can be claimed; each gets
+    /// its own swapchain handle, with [`Texture::SWAPCHAIN`] reserved for
+    /// the device's own window.
+    ///
+    /// On Android the device's own window must be claimed again when the
+    /// app enters the foreground (`SDL_EVENT_WILL_ENTER_FOREGROUND`).
+    pub fn claim_window(&self, window: &Window) -> Result<(), &'static str> {
+        let id = window.id();
+        let own = self.window.as_ref().is_some_and(|w| w.id() == id);
+        let slot = {
+            let windows = self.windows.borrow();
+            if windows.contains_key(&id) {
+                return Err("Window is already claimed by this device");
+            }
+            if own {
+                0
+            } else {
+                (1..MAX_CLAIMED_WINDOWS)
+                    .find(|&slot| windows.values().all(|w| w.slot != slot))
+                    .ok_or("Too many windows claimed")?
+            }
+        };
+        if !self.backend.claim_window(window.raw()) {
+            return Err("SDL_ClaimWindowForGPUDevice failed");
+        }
+        self.windows.borrow_mut().insert(id, ClaimedWindow::new(slot, window.raw()));
+        if own && self.swapchain_config.get() != SwapchainConfig::default() {
+            let _ = self.set_swapchain_config(window, &self.swapchain_config.get());
         }
+        Ok(())
     }
 
-    /// Claim the window for the GPU device. Must be called on Android
-    /// when the app enters the foreground (`SDL_EVENT_WILL_ENTER_FOREGROUND`).
-    pub fn claim_window(&self) {
-        if let Some(window) = &self.window {
-            unsafe { gpu::SDL_ClaimWindowForGPUDevice(self.inner, window.raw()); }
+    /// Whether `window` is currently claimed by this device.
+    pub fn is_window_claimed(&self, window: &Window) -> bool {
+        self.windows.borrow().contains_key(&window.id())
+    }
+
+    fn claimed_window_slot(&self, window: &Window) -> Result<i32, &'static str> {
+        self.windows.borrow().get(&window.id())
+            .map(|w| w.slot)
+            .ok_or("Window is not claimed by this device")
+    }
+
+    /// Make swapchain textures capturable with
+    /// [`CommandBuffer::capture_swapchain`].
+    ///
+    /// Swapchain textures can't be read back, so while capture is on the
+    /// swapchain handles render into an intermediate texture per window,
+    /// which is blitted to the real swapchain texture when the command
+    /// buffer is submitted. That costs one extra full-window blit per
+    /// window and frame, so only turn it on when screenshots may be taken.
+    pub fn set_swapchain_capture(&self, enabled: bool) {
+        self.swapchain_capture.set(enabled);
+        if !enabled {
+            for claimed in self.windows.borrow_mut().values_mut() {
+                if claimed.capture.is_valid() {
+                    claimed.capture.destroy(self);
+                }
+            }
         }
     }
 
-    pub fn create_texture(&self, info: &gpu::SDL_GPUTextureCreateInfo) -> Result<Texture, &'static str> {
-        validate_sample_count(info.sample_count)?;
-        unsafe {
-            let raw = gpu::SDL_CreateGPUTexture(self.inner, info);
-            if raw.is_null() {
-                return Err("SDL_CreateGPUTexture failed");
+    pub fn swapchain_capture(&self) -> bool {
+        self.swapchain_capture.get()
+    }
+
+    /// The intermediate texture a window's swapchain handle renders into
+    /// while capture is on, recreated when the swapchain size or format
+    /// changes.
+    fn swapchain_capture_texture(
+        &self,
+        window: &Window,
+        format: SDL_GPUTextureFormat,
+        width: u32,
+        height: u32,
+    ) -> Result<*mut gpu::SDL_GPUTexture, &'static str> {
+        let current = self.windows.borrow().get(&window.id()).map_or(Texture::NONE, |w| w.capture);
+        if current.is_valid() {
+            if self.resources.textures.with(current.0, |slot| slot.res == (width, height) && slot.format == format) {
+                return Ok(self.texture_raw(current));
             }
-            let slot = TextureSlot {
-                inner: raw,
-                res: (info.width, info.height),
-            };
-            let idx = self.textures.insert(slot);
-            Ok(Texture(idx))
+            let mut stale = current;
+            stale.destroy(self);
+        }
+        let usage = SDL_GPUTextureUsageFlags::COLOR_TARGET | SDL_GPUTextureUsageFlags::SAMPLER;
+        let capture = self.create_texture(&TextureDesc::new(format, width, height, usage).to_create_info());
+        if let Some(claimed) = self.windows.borrow_mut().get_mut(&window.id()) {
+            claimed.capture = *capture.as_ref().unwrap_or(&Texture::NONE);
+        }
+        Ok(self.texture_raw(capture?))
+    }
+
+    fn set_swapchain_texture(&self, window: &Window, swapchain: AcquiredSwapchain) {
+        if let Some(claimed) = self.windows.borrow_mut().get_mut(&window.id()) {
+            claimed.swapchain = (!swapchain.texture.is_null()).then_some(swapchain);
+        }
+    }
+
+    /// The texture currently acquired for a swapchain handle, by whichever
+    /// command buffer acquired it.
+    fn acquired_swapchain(&self, handle: Texture) -> Option<AcquiredSwapchain> {
+        let slot = Texture::SWAPCHAIN.0 - handle.0;
+        self.windows.borrow().values()
+            .find(|w| w.slot == slot)
+            .and_then(|w| w.swapchain)
+    }
+
+    fn swapchain_texture(&self, handle: Texture) -> AcquiredSwapchain {
+        self.acquired_swapchain(handle).expect("no swapchain texture acquired")
+    }
+
+    /// Whether the swapchain of `window`, claimed by this device, can use
+    /// `mode`. `VSYNC` always can.
+    pub fn supports_present_mode(&self, window: &Window, mode: SDL_GPUPresentMode) -> bool {
+        self.is_window_claimed(window) && self.backend.window_supports_present_mode(window.raw(), mode)
+    }
+
+    /// Whether the swapchain of `window`, claimed by this device, can use
+    /// `composition`. `SDR` always can.
+    pub fn supports_swapchain_composition(&self, window: &Window, composition: SDL_GPUSwapchainComposition) -> bool {
+        self.is_window_claimed(window) && self.backend.window_supports_swapchain_composition(window.raw(), composition)
+    }
+
+    /// Apply `config` to the swapchain of `window`, claimed by this device,
+    /// falling back where the window doesn't support a setting: `MAILBOX`
+    /// and `IMMEDIATE` fall back to `VSYNC`, an HDR composition to the other
+    /// HDR one and then to `SDR`, `SDR_LINEAR` to `SDR`, and frames in
+    /// flight are clamped to 1..=3. Frames in flight apply to the whole
+    /// device, so to every window.
+    ///
+    /// Returns the configuration actually in use. The swapchain texture
+    /// format may change with the composition; query it again afterwards.
+    pub fn set_swapchain_config(&self, window: &Window, config: &SwapchainConfig) -> Result<SwapchainConfig, &'static str> {
+        self.claimed_window_slot(window)?;
+
+        let present_mode = if self.supports_present_mode(window, config.present_mode) {
+            config.present_mode
+        } else {
+            SDL_GPUPresentMode::VSYNC
+        };
+        let fallbacks: &[SDL_GPUSwapchainComposition] = match config.composition {
+            SDL_GPUSwapchainComposition::HDR10_ST2084 => &[SDL_GPUSwapchainComposition::HDR_EXTENDED_LINEAR],
+            SDL_GPUSwapchainComposition::HDR_EXTENDED_LINEAR => &[SDL_GPUSwapchainComposition::HDR10_ST2084],
+            _ => &[],
+        };
+        let composition = std::iter::once(config.composition)
+            .chain(fallbacks.iter().copied())
+            .find(|&c| self.supports_swapchain_composition(window, c))
+            .unwrap_or(SDL_GPUSwapchainComposition::SDR);
+        let frames_in_flight = config.frames_in_flight.clamp(1, 3);
+
+        if !self.backend.set_swapchain_parameters(window.raw(), composition, present_mode) {
+            return Err("SDL_SetGPUSwapchainParameters failed");
+        }
+        if !self.backend.set_allowed_frames_in_flight(frames_in_flight) {
+            return Err("SDL_SetGPUAllowedFramesInFlight failed");
         }
+        let applied = SwapchainConfig { present_mode, composition, frames_in_flight };
+        if let Some(claimed) = self.windows.borrow_mut().get_mut(&window.id()) {
+            claimed.config = applied;
+        }
+        Ok(applied)
+    }
+
+    /// The configuration last applied to the swapchain of `window` with
+    /// [`set_swapchain_config`](Self::set_swapchain_config), or `None` if
+    /// the window isn't claimed by this device.
+    pub fn swapchain_config(&self, window: &Window) -> Option<SwapchainConfig> {
+        self.windows.borrow().get(&window.id()).map(|w| w.config)
+    }
+
+    /// Create a texture. A name set in `info.props` with
+    /// `SDL_PROP_GPU_TEXTURE_CREATE_NAME_STRING` is kept, see
+    /// [`get_texture_name`](Self::get_texture_name).
+    #[track_caller]
+    pub fn create_texture(&self, info: &gpu::SDL_GPUTextureCreateInfo) -> Result<Texture, &'static str> {
+        self.resources.create_texture(info, Location::caller())
+    }
+
+    /// Create a texture named `name` in graphics debuggers.
+    #[track_caller]
+    pub fn create_texture_named(&self, info: &gpu::SDL_GPUTextureCreateInfo, name: &str) -> Result<Texture, &'static str> {
+        let props = StringProperty::new(info.props, gpu::SDL_PROP_GPU_TEXTURE_CREATE_NAME_STRING, Some(name));
+        self.create_texture(&gpu::SDL_GPUTextureCreateInfo { props: props.id(), ..*info })
+    }
+
+    /// Rename a texture, in graphics debuggers and for
+    /// [`get_texture_name`](Self::get_texture_name).
+    pub fn set_texture_name(&self, texture: Texture, name: &str) {
+        self.backend.set_texture_name(self.texture_raw(texture), &debug_cstring(name));
+        self.resources.textures.with_mut(texture.0, |slot| slot.name = Some(name.to_string()));
+    }
+
+    pub fn get_texture_name(&self, texture: Texture) -> Option<String> {
+        self.resources.textures.with(texture.0, |slot| slot.name.clone())
     }
 
 
     pub(crate) fn texture_raw(&self, handle: Texture) -> *mut gpu::SDL_GPUTexture {
-        if handle == Texture::SWAPCHAIN {
-            let (ptr, _, _) = self.swapchain.get();
-            assert!(!ptr.is_null(), "no swapchain texture acquired");
-            return ptr;
+        if handle.is_swapchain() {
+            return self.swapchain_texture(handle).texture;
         }
-        self.textures.with(handle.0, |slot| slot.inner)
+        self.resources.textures.with(handle.0, |slot| slot.inner)
     }
 
     pub fn get_texture_res(&self, handle: Texture) -> (u32, u32) {
-        if handle == Texture::SWAPCHAIN {
-            let (ptr, w, h) = self.swapchain.get();
-            assert!(!ptr.is_null(), "no swapchain texture acquired");
-            return (w, h);
+        if handle.is_swapchain() {
+            let swapchain = self.swapchain_texture(handle);
+            return (swapchain.width, swapchain.height);
         }
-        self.textures.with(handle.0, |slot| slot.res)
+        self.resources.textures.with(handle.0, |slot| slot.res)
     }
 
-    pub fn create_shader(&self, info: &ShaderCreateInfo) -> Result<Shader, &'static str> {
-        let entrypoint = std::ffi::CString::new(info.entrypoint)
-            .map_err(|_| "entrypoint contains interior nul byte")?;
-        let raw_info = gpu::SDL_GPUShaderCreateInfo {
-            code_size: info.code.len(),
-            code: info.code.as_ptr(),
-            entrypoint: entrypoint.as_ptr(),
-            format: info.format,
-            stage: info.stage,
-            num_samplers: info.num_samplers,
-            num_storage_textures: info.num_storage_textures,
-            num_storage_buffers: info.num_storage_buffers,
-            num_uniform_buffers: info.num_uniform_buffers,
-            props: sys::properties::SDL_PropertiesID(0),
-        };
-        unsafe {
-            let raw = gpu::SDL_CreateGPUShader(self.inner, &raw_info);
-            if raw.is_null() {
-                return Err("SDL_CreateGPUShader failed");
-            }
-            let idx = self.shaders.insert(ShaderSlot { inner: raw });
-            Ok(Shader(idx))
+    pub fn get_texture_format(&self, handle: Texture) -> SDL_GPUTextureFormat {
+        if handle.is_swapchain() {
+            return self.swapchain_texture(handle).format;
         }
+        self.resources.textures.with(handle.0, |slot| slot.format)
     }
 
+    /// Usage flags of a texture. Swapchain textures are color targets.
+    pub fn get_texture_usage(&self, handle: Texture) -> SDL_GPUTextureUsageFlags {
+        if handle.is_swapchain() {
+            return SDL_GPUTextureUsageFlags::COLOR_TARGET;
+        }
+        self.resources.textures.with(handle.0, |slot| slot.usage)
+    }
 
-    #[allow(deprecated)]
-    pub fn create_graphics_pipeline(&self, info: &GraphicsPipelineCreateInfo) -> Result<GraphicsPipeline, &'static str> {
-        validate_sample_count(info.multisample_state.sample_count)?;
-        let vertex_shader_raw = self.shaders.with(info.vertex_shader.0, |s| s.inner);
-        let fragment_shader_raw = self.shaders.with(info.fragment_shader.0, |s| s.inner);
-        let raw_info = gpu::SDL_GPUGraphicsPipelineCreateInfo {
-            vertex_shader: vertex_shader_raw,
-            fragment_shader: fragment_shader_raw,
-            vertex_input_state: gpu::SDL_GPUVertexInputState {
-                vertex_buffer_descriptions: if info.vertex_buffer_descriptions.is_empty() {
-                    std::ptr::null()
-                } else {
-                    info.vertex_buffer_descriptions.as_ptr()
-                },
-                num_vertex_buffers: info.vertex_buffer_descriptions.len() as u32,
-                vertex_attributes: if info.vertex_attributes.is_empty() {
-                    std::ptr::null()
-                } else {
-                    info.vertex_attributes.as_ptr()
-                },
-                num_vertex_attributes: info.vertex_attributes.len() as u32,
-            },
-            primitive_type: info.primitive_type,
-            rasterizer_state: info.rasterizer_state,
-            multisample_state: info.multisample_state,
-            depth_stencil_state: info.depth_stencil_state,
-            target_info: gpu::SDL_GPUGraphicsPipelineTargetInfo {
-                color_target_descriptions: if info.color_target_descriptions.is_empty() {
-                    std::ptr::null()
-                } else {
-                    info.color_target_descriptions.as_ptr()
-                },
-                num_color_targets: info.color_target_descriptions.len() as u32,
-                depth_stencil_format: info.depth_stencil_format,
-                has_depth_stencil_target: info.has_depth_stencil_target,
-                padding1: 0,
-                padding2: 0,
-                padding3: 0,
-            },
-            props: sys::properties::SDL_PropertiesID(0),
-        };
+    fn texture_sample_count(&self, handle: Texture) -> SDL_GPUSampleCount {
+        if handle.is_swapchain() {
+            return SDL_GPUSampleCount::_1;
+        }
+        self.resources.textures.with(handle.0, |slot| slot.sample_count)
+    }
 
-        unsafe {
-            let raw = gpu::SDL_CreateGPUGraphicsPipeline(self.inner, &raw_info);
-            if raw.is_null() {
-                return Err("SDL_CreateGPUGraphicsPipeline failed");
+    fn has_usage(&self, handle: Texture, usage: SDL_GPUTextureUsageFlags) -> bool {
+        self.get_texture_usage(handle).0 & usage.0 == usage.0
+    }
+
+    /// Panic unless every texture in `textures` has `usage`, named
+    /// `usage_name` in the message.
+    fn require_usage(&self, call: &str, textures: impl IntoIterator<Item = Texture>, usage: SDL_GPUTextureUsageFlags, usage_name: &str) {
+        for texture in textures {
+            if !self.has_usage(texture, usage) {
+                match self.get_texture_name(texture) {
+                    Some(name) => panic!("{call}: texture \"{name}\" was not created with {usage_name} usage"),
+                    None => panic!("{call}: texture {} was not created with {usage_name} usage", texture.0),
+                }
             }
-            let idx = self.graphics_pipelines.insert(GraphicsPipelineSlot { inner: raw });
-            Ok(GraphicsPipeline(idx))
         }
     }
 
+    #[track_caller]
+    pub fn create_shader(&self, info: &ShaderCreateInfo) -> Result<Shader, &'static str> {
+        self.resources.create_shader(info, Location::caller())
+    }
+
+    /// Identifies the shader behind a handle: a shader created into a slot
+    /// freed by [`Shader::destroy`] gets a different serial.
+    pub(crate) fn shader_serial(&self, shader: Shader) -> u64 {
+        self.resources.shaders.with(shader.0, |slot| slot.serial)
+    }
+
+    /// Replace the shader behind `shader` with a new one built from `info`,
+    /// keeping the handle, and the name unless `info` has one. On failure
+    /// the existing shader is left untouched.
+    pub fn recreate_shader(&self, shader: Shader, info: &ShaderCreateInfo) -> Result<(), &'static str> {
+        let name = info.name.map(str::to_string).or_else(|| self.resources.shaders.with(shader.0, |slot| slot.name.clone()));
+        let raw = self.resources.build_shader(&ShaderCreateInfo { name: name.as_deref(), ..*info })?;
+        let old = self.resources.shaders.with_mut(shader.0, |slot| {
+            slot.name = name.clone();
+            slot.resources = stage_resources(info);
+            std::mem::replace(&mut slot.inner, raw)
+        });
+        self.backend.release_shader(old);
+        Ok(())
+    }
+
+    #[track_caller]
+    pub fn create_graphics_pipeline(&self, info: &GraphicsPipelineCreateInfo) -> Result<GraphicsPipeline, &'static str> {
+        self.resources.create_graphics_pipeline(info, Location::caller())
+    }
+
+    /// Replace the pipeline behind `pipeline` with a new one built from `info`,
+    /// keeping the handle, and the name unless `info` has one. On failure
+    /// the existing pipeline stays in use.
+    pub fn recreate_graphics_pipeline(&self, pipeline: GraphicsPipeline, info: &GraphicsPipelineCreateInfo) -> Result<(), &'static str> {
+        let name = info.name.clone().or_else(|| self.resources.graphics_pipelines.with(pipeline.0, |slot| slot.name.clone()));
+        let raw = self.resources.build_graphics_pipeline(&GraphicsPipelineCreateInfo { name: name.clone(), ..info.clone() })?;
+        let requirements = self.resources.graphics_requirements(info);
+        let old = self.resources.graphics_pipelines.with_mut(pipeline.0, |slot| {
+            slot.name = name;
+            slot.requirements = requirements;
+            slot.targets = pipeline_targets(info);
+            std::mem::replace(&mut slot.inner, raw)
+        });
+        self.backend.release_graphics_pipeline(old);
+        Ok(())
+    }
 
+    #[track_caller]
     pub fn create_compute_pipeline(&self, info: &ComputePipelineCreateInfo) -> Result<ComputePipeline, &'static str> {
-        let entrypoint = std::ffi::CString::new(info.entrypoint)
-            .map_err(|_| "entrypoint contains interior nul byte")?;
-        let raw_info = gpu::SDL_GPUComputePipelineCreateInfo {
-            code_size: info.code.len(),
-            code: info.code.as_ptr(),
-            entrypoint: entrypoint.as_ptr(),
-            format: info.format,
-            num_samplers: info.num_samplers,
-            num_readonly_storage_textures: info.num_readonly_storage_textures,
-            num_readonly_storage_buffers: info.num_readonly_storage_buffers,
-            num_readwrite_storage_textures: info.num_readwrite_storage_textures,
-            num_readwrite_storage_buffers: info.num_readwrite_storage_buffers,
-            num_uniform_buffers: info.num_uniform_buffers,
-            threadcount_x: info.threadcount_x,
-            threadcount_y: info.threadcount_y,
-            threadcount_z: info.threadcount_z,
-            props: sys::properties::SDL_PropertiesID(0),
-        };
-        unsafe {
-            let raw = gpu::SDL_CreateGPUComputePipeline(self.inner, &raw_info);
-            if raw.is_null() {
-                return Err("SDL_CreateGPUComputePipeline failed");
-            }
-            let idx = self.compute_pipelines.insert(ComputePipelineSlot { inner: raw });
-            Ok(ComputePipeline(idx))
-        }
+        self.resources.create_compute_pipeline(info, Location::caller())
+    }
+
+    /// Replace the pipeline behind `pipeline` with a new one built from `info`,
+    /// keeping the handle, and the name unless `info` has one. On failure
+    /// the existing pipeline stays in use.
+    pub fn recreate_compute_pipeline(&self, pipeline: ComputePipeline, info: &ComputePipelineCreateInfo) -> Result<(), &'static str> {
+        let name = info.name.map(str::to_string).or_else(|| self.resources.compute_pipelines.with(pipeline.0, |slot| slot.name.clone()));
+        let raw = self.resources.build_compute_pipeline(&ComputePipelineCreateInfo { name: name.as_deref(), ..*info })?;
+        let old = self.resources.compute_pipelines.with_mut(pipeline.0, |slot| {
+            slot.name = name.clone();
+            slot.requirements = compute_requirements(info);
+            std::mem::replace(&mut slot.inner, raw)
+        });
+        self.backend.release_compute_pipeline(old);
+        Ok(())
     }
 
 
-    pub fn create_buffer(&self, usage: SDL_GPUBufferUsageFlags, size: u32) -> Result<GPUBuffer, &'static str> {
-        let info = gpu::SDL_GPUBufferCreateInfo {
-            usage,
-            size,
-            props: sys::properties::SDL_PropertiesID(0),
-        };
-        unsafe {
-            let raw = gpu::SDL_CreateGPUBuffer(self.inner, &info);
-            if raw.is_null() {
-                return Err("SDL_CreateGPUBuffer failed");
-            }
-            let idx = self.buffers.insert(BufferSlot { inner: raw, size });
-            Ok(GPUBuffer(idx))
+    /// Pick the format to compile runtime shader sources to: SPIR-V if the
+    /// device takes it, else MSL.
+    #[cfg(feature = "naga")]
+    fn source_target(&self) -> Result<(ShaderTarget, SDL_GPUShaderFormat), ShaderSourceError> {
+        let formats = self.get_shader_formats();
+        if formats.0 & SDL_GPUShaderFormat::SPIRV.0 != 0 {
+            Ok((ShaderTarget::Spirv, SDL_GPUShaderFormat::SPIRV))
+        } else if formats.0 & SDL_GPUShaderFormat::MSL.0 != 0 {
+            Ok((ShaderTarget::Msl, SDL_GPUShaderFormat::MSL))
+        } else {
+            Err(ShaderSourceError::Device("device takes neither SPIR-V nor MSL; runtime shader sources are unsupported"))
         }
     }
 
+    /// Compile GLSL or WGSL in-process and create a shader from it. Resource
+    /// counts come from reflection; see [`crate::shader_compiler`] for how
+    /// resources must be declared.
+    #[cfg(feature = "naga")]
+    #[track_caller]
+    pub fn create_shader_from_source(&self, src: &str, lang: SourceLanguage, stage: ShaderStage) -> Result<Shader, ShaderSourceError> {
+        let sdl_stage = match stage {
+            ShaderStage::Vertex => SDL_GPUShaderStage::VERTEX,
+            ShaderStage::Fragment => SDL_GPUShaderStage::FRAGMENT,
+            ShaderStage::Compute => {
+                return Err(ShaderSourceError::Device("compute shaders are created with create_compute_pipeline_from_source"));
+            }
+        };
+        let (target, format) = self.source_target()?;
+        let compiled = shader_compiler::compile(src, lang, Some(stage), &[], target)?;
+        let reflection = &compiled.reflection;
+        self.create_shader(&ShaderCreateInfo {
+            code: &compiled.code,
+            entrypoint: &compiled.entrypoint,
+            format,
+            stage: sdl_stage,
+            num_samplers: reflection.num_samplers,
+            num_storage_textures: reflection.num_storage_textures(),
+            num_storage_buffers: reflection.num_storage_buffers(),
+            num_uniform_buffers: reflection.num_uniform_buffers,
+            name: None,
+        }).map_err(ShaderSourceError::Device)
+    }
+
+    /// Compile a GLSL or WGSL compute shader in-process and create a compute
+    /// pipeline from it, with resource counts and workgroup size from reflection.
+    #[cfg(feature = "naga")]
+    #[track_caller]
+    pub fn create_compute_pipeline_from_source(&self, src: &str, lang: SourceLanguage) -> Result<ComputePipeline, ShaderSourceError> {
+        let (target, format) = self.source_target()?;
+        let compiled = shader_compiler::compile(src, lang, Some(ShaderStage::Compute), &[], target)?;
+        let reflection = &compiled.reflection;
+        self.create_compute_pipeline(&ComputePipelineCreateInfo {
+            code: &compiled.code,
+            entrypoint: &compiled.entrypoint,
+            format,
+            num_samplers: reflection.num_samplers,
+            num_readonly_storage_textures: reflection.num_readonly_storage_textures,
+            num_readonly_storage_buffers: reflection.num_readonly_storage_buffers,
+            num_readwrite_storage_textures: reflection.num_readwrite_storage_textures,
+            num_readwrite_storage_buffers: reflection.num_readwrite_storage_buffers,
+            num_uniform_buffers: reflection.num_uniform_buffers,
+            threadcount_x: reflection.threadcount[0],
+            threadcount_y: reflection.threadcount[1],
+            threadcount_z: reflection.threadcount[2],
+            name: None,
+        }).map_err(ShaderSourceError::Device)
+    }
+
+    #[track_caller]
+    pub fn create_buffer(&self, usage: SDL_GPUBufferUsageFlags, size: u32) -> Result<GPUBuffer, &'static str> {
+        self.resources.create_buffer(usage, size, None, Location::caller())
+    }
+
+    /// Create a buffer named `name` in graphics debuggers.
+    #[track_caller]
+    pub fn create_buffer_named(&self, usage: SDL_GPUBufferUsageFlags, size: u32, name: &str) -> Result<GPUBuffer, &'static str> {
+        self.resources.create_buffer(usage, size, Some(name), Location::caller())
+    }
+
+    /// Rename a buffer, in graphics debuggers and for
+    /// [`get_buffer_name`](Self::get_buffer_name).
+    pub fn set_buffer_name(&self, buffer: GPUBuffer, name: &str) {
+        self.backend.set_buffer_name(self.buffer_raw(buffer), &debug_cstring(name));
+        self.resources.buffers.with_mut(buffer.0, |slot| slot.name = Some(name.to_string()));
+    }
+
+    pub fn get_buffer_name(&self, buffer: GPUBuffer) -> Option<String> {
+        self.resources.buffers.with(buffer.0, |slot| slot.name.clone())
+    }
+
 
     pub(crate) fn buffer_raw(&self, handle: GPUBuffer) -> *mut gpu::SDL_GPUBuffer {
-        self.buffers.with(handle.0, |slot| slot.inner)
+        self.resources.buffers.with(handle.0, |slot| slot.inner)
     }
 
     pub fn get_buffer_size(&self, handle: GPUBuffer) -> u32 {
-        self.buffers.with(handle.0, |slot| slot.size)
+        self.resources.buffers.with(handle.0, |slot| slot.size)
     }
 
+    /// Create a sampler. A name set in `info.props` with
+    /// `SDL_PROP_GPU_SAMPLER_CREATE_NAME_STRING` is kept.
+    #[track_caller]
     pub fn create_sampler(&self, info: &gpu::SDL_GPUSamplerCreateInfo) -> Result<Sampler, &'static str> {
-        unsafe {
-            let raw = gpu::SDL_CreateGPUSampler(self.inner, info);
-            if raw.is_null() {
-                return Err("SDL_CreateGPUSampler failed");
-            }
-            let idx = self.samplers.insert(SamplerSlot { inner: raw });
-            Ok(Sampler(idx))
-        }
+        self.resources.create_sampler(info, Location::caller())
+    }
+
+    /// Create a sampler named `name` in graphics debuggers.
+    #[allow(deprecated)]
+    #[track_caller]
+    pub fn create_sampler_named(&self, info: &gpu::SDL_GPUSamplerCreateInfo, name: &str) -> Result<Sampler, &'static str> {
+        let props = StringProperty::new(info.props, gpu::SDL_PROP_GPU_SAMPLER_CREATE_NAME_STRING, Some(name));
+        self.create_sampler(&gpu::SDL_GPUSamplerCreateInfo { props: props.id(), ..*info })
     }
 
 
     pub(crate) fn sampler_raw(&self, handle: Sampler) -> *mut gpu::SDL_GPUSampler {
-        self.samplers.with(handle.0, |slot| slot.inner)
+        self.resources.samplers.with(handle.0, |slot| slot.inner)
+    }
+
+    pub fn get_sampler_name(&self, sampler: Sampler) -> Option<String> {
+        self.resources.samplers.with(sampler.0, |slot| slot.name.clone())
     }
 
     /// Ensure the internal upload transfer buffer is at least `size` bytes.
@@ -606,26 +996,25 @@ impl Device {
             size,
             props: sys::properties::SDL_PropertiesID(0),
         };
-        unsafe {
-            let raw = gpu::SDL_CreateGPUTransferBuffer(self.inner, &tb_info);
-            if raw.is_null() {
-                self.upload_transfer_buffer.set((std::ptr::null_mut(), 0));
-                return Err("SDL_CreateGPUTransferBuffer failed");
-            }
-            self.upload_transfer_buffer.set((raw, size));
-            Ok(raw)
+        let raw = self.backend.create_transfer_buffer(&tb_info);
+        if raw.is_null() {
+            self.upload_transfer_buffer.set((std::ptr::null_mut(), 0));
+            return Err("SDL_CreateGPUTransferBuffer failed");
         }
+        self.upload_transfer_buffer.set((raw, size));
+        Ok(raw)
     }
 
     /// Stage upload data into the internal transfer buffer (map, copy, unmap).
     /// Returns the transfer buffer handle.
     fn stage_upload(&self, data: &[u8]) -> Result<*mut gpu::SDL_GPUTransferBuffer, &'static str> {
         let transfer = self.ensure_upload_transfer_buffer(data.len() as u32)?;
+        self.count(|stats| stats.upload_bytes += data.len() as u64);
+        let ptr = self.backend.map_transfer_buffer(transfer, true);
         unsafe {
-            let ptr = gpu::SDL_MapGPUTransferBuffer(self.inner, transfer, true);
-            std::ptr::copy_nonoverlapping(data.as_ptr(), ptr as *mut u8, data.len());
-            gpu::SDL_UnmapGPUTransferBuffer(self.inner, transfer);
+            std::ptr::copy_nonoverlapping(data.as_ptr(), ptr, data.len());
         }
+        self.backend.unmap_transfer_buffer(transfer);
         Ok(transfer)
     }
 
@@ -640,53 +1029,49 @@ impl Device {
             f(pass.inner);
             return Ok(());
         }
-        unsafe {
-            let cmd = gpu::SDL_AcquireGPUCommandBuffer(self.inner);
-            if cmd.is_null() {
-                return Err("SDL_AcquireGPUCommandBuffer failed");
-            }
-            let tmp_pass = gpu::SDL_BeginGPUCopyPass(cmd);
-            if tmp_pass.is_null() {
-                gpu::SDL_CancelGPUCommandBuffer(cmd);
-                return Err("SDL_BeginGPUCopyPass failed");
-            }
-            f(tmp_pass);
-            gpu::SDL_EndGPUCopyPass(tmp_pass);
-            if !gpu::SDL_SubmitGPUCommandBuffer(cmd) {
-                return Err("SDL_SubmitGPUCommandBuffer failed");
-            }
+        let cmd = self.backend.acquire_command_buffer();
+        if cmd.is_null() {
+            return Err("SDL_AcquireGPUCommandBuffer failed");
+        }
+        let tmp_pass = self.backend.begin_copy_pass(cmd);
+        if tmp_pass.is_null() {
+            self.backend.cancel_command_buffer(cmd);
+            return Err("SDL_BeginGPUCopyPass failed");
+        }
+        f(tmp_pass);
+        self.backend.end_copy_pass(tmp_pass);
+        if !self.backend.submit_command_buffer(cmd) {
+            return Err("SDL_SubmitGPUCommandBuffer failed");
         }
         Ok(())
     }
 
     /// Create a download transfer buffer of the given size.
-    fn create_download_transfer_buffer(&self, size: u32) -> Result<*mut gpu::SDL_GPUTransferBuffer, &'static str> {
+    pub(crate) fn create_download_transfer_buffer(&self, size: u32) -> Result<*mut gpu::SDL_GPUTransferBuffer, &'static str> {
         let tb_info = gpu::SDL_GPUTransferBufferCreateInfo {
             usage: gpu::SDL_GPUTransferBufferUsage::DOWNLOAD,
             size,
             props: sys::properties::SDL_PropertiesID(0),
         };
-        unsafe {
-            let transfer = gpu::SDL_CreateGPUTransferBuffer(self.inner, &tb_info);
-            if transfer.is_null() {
-                return Err("SDL_CreateGPUTransferBuffer (download) failed");
-            }
-            Ok(transfer)
+        let transfer = self.backend.create_transfer_buffer(&tb_info);
+        if transfer.is_null() {
+            return Err("SDL_CreateGPUTransferBuffer (download) failed");
         }
+        Ok(transfer)
     }
 
     /// Upload data from a byte slice into a GPU buffer.
     pub fn upload_to_buffer(&self, copy_pass: Option<&CopyPass>, buffer: GPUBuffer, offset: u32, data: &[u8]) -> Result<(), &'static str> {
         let size = data.len() as u32;
-        let buf_size = self.buffers.with(buffer.0, |slot| slot.size);
+        let buf_size = self.resources.buffers.with(buffer.0, |slot| slot.size);
         if offset.saturating_add(size) > buf_size {
             return Err("data exceeds buffer size");
         }
         let transfer = self.stage_upload(data)?;
         let src = gpu::SDL_GPUTransferBufferLocation { transfer_buffer: transfer, offset: 0 };
         let dst = gpu::SDL_GPUBufferRegion { buffer: self.buffer_raw(buffer), offset, size };
-        self.with_copy_pass(copy_pass, |pass| unsafe {
-            gpu::SDL_UploadToGPUBuffer(pass, &src, &dst, true);
+        self.with_copy_pass(copy_pass, |pass| {
+            self.backend.upload_to_buffer(pass, &src, &dst, true);
         })
     }
 
@@ -741,98 +1126,144 @@ impl Device {
             rows_per_layer: 0,
         };
         let dst = region.to_raw(self);
-        self.with_copy_pass(copy_pass, |pass| unsafe {
-            gpu::SDL_UploadToGPUTexture(pass, &src, &dst, true);
+        self.with_copy_pass(copy_pass, |pass| {
+            self.backend.upload_to_texture(pass, &src, &dst, true);
         })
     }
 
     /// Download data from a GPU buffer into a Vec<u8>.
     pub fn download_from_buffer(&self, buffer: GPUBuffer, offset: u32, size: u32) -> Result<Vec<u8>, &'static str> {
-        let buf_size = self.buffers.with(buffer.0, |slot| slot.size);
+        let buf_size = self.resources.buffers.with(buffer.0, |slot| slot.size);
         let size = if size == 0 { buf_size - offset } else { size };
         if offset.saturating_add(size) > buf_size {
             return Err("requested range exceeds buffer size");
         }
-        let transfer = self.create_download_transfer_buffer(size)?;
-        unsafe {
-            let cmd = gpu::SDL_AcquireGPUCommandBuffer(self.inner);
-            if cmd.is_null() {
-                gpu::SDL_ReleaseGPUTransferBuffer(self.inner, transfer);
-                return Err("SDL_AcquireGPUCommandBuffer failed");
-            }
-            let pass = gpu::SDL_BeginGPUCopyPass(cmd);
-            if pass.is_null() {
-                gpu::SDL_CancelGPUCommandBuffer(cmd);
-                gpu::SDL_ReleaseGPUTransferBuffer(self.inner, transfer);
-                return Err("SDL_BeginGPUCopyPass failed");
-            }
-
-            let src = gpu::SDL_GPUBufferRegion { buffer: self.buffer_raw(buffer), offset, size };
+        let src = gpu::SDL_GPUBufferRegion { buffer: self.buffer_raw(buffer), offset, size };
+        self.download_blocking(size, |pass, transfer| {
             let dst = gpu::SDL_GPUTransferBufferLocation { transfer_buffer: transfer, offset: 0 };
-            gpu::SDL_DownloadFromGPUBuffer(pass, &src, &dst);
-            gpu::SDL_EndGPUCopyPass(pass);
+            self.backend.download_from_buffer(pass, &src, &dst);
+        })
+    }
 
-            let fence = gpu::SDL_SubmitGPUCommandBufferAndAcquireFence(cmd);
-            if fence.is_null() {
-                gpu::SDL_ReleaseGPUTransferBuffer(self.inner, transfer);
-                return Err("SDL_SubmitGPUCommandBufferAndAcquireFence failed");
-            }
-            if !gpu::SDL_WaitForGPUFences(self.inner, true, &fence, 1) {
-                gpu::SDL_ReleaseGPUFence(self.inner, fence);
-                gpu::SDL_ReleaseGPUTransferBuffer(self.inner, transfer);
-                return Err("SDL_WaitForGPUFences failed");
-            }
-            gpu::SDL_ReleaseGPUFence(self.inner, fence);
+    /// Download a texture region into a Vec<u8>, tightly packed in the
+    /// texture's format. Waits for the GPU.
+    pub fn download_from_texture(&self, region: &TextureRegion) -> Result<Vec<u8>, &'static str> {
+        let format = self.get_texture_format(region.texture);
+        let size = unsafe { gpu::SDL_CalculateGPUTextureFormatSize(format, region.w, region.h, region.d) };
+        let src = region.to_raw(self);
+        self.download_blocking(size, |pass, transfer| {
+            let dst = gpu::SDL_GPUTextureTransferInfo {
+                transfer_buffer: transfer,
+                offset: 0,
+                pixels_per_row: 0,
+                rows_per_layer: 0,
+            };
+            self.backend.download_from_texture(pass, &src, &dst);
+        })
+    }
 
-            let ptr = gpu::SDL_MapGPUTransferBuffer(self.inner, transfer, false);
-            if ptr.is_null() {
-                gpu::SDL_ReleaseGPUTransferBuffer(self.inner, transfer);
-                return Err("SDL_MapGPUTransferBuffer failed");
-            }
-            let mut data = vec![0u8; size as usize];
+    /// Record a download of `size` bytes with `f` on a temporary command
+    /// buffer, submit it, wait for it and return the downloaded bytes.
+    fn download_blocking(
+        &self,
+        size: u32,
+        f: impl FnOnce(*mut gpu::SDL_GPUCopyPass, *mut gpu::SDL_GPUTransferBuffer),
+    ) -> Result<Vec<u8>, &'static str> {
+        let transfer = self.create_download_transfer_buffer(size)?;
+        self.count(|stats| stats.download_bytes += size as u64);
+        let backend = &self.backend;
+        let cmd = backend.acquire_command_buffer();
+        if cmd.is_null() {
+            backend.release_transfer_buffer(transfer);
+            return Err("SDL_AcquireGPUCommandBuffer failed");
+        }
+        let pass = backend.begin_copy_pass(cmd);
+        if pass.is_null() {
+            backend.cancel_command_buffer(cmd);
+            backend.release_transfer_buffer(transfer);
+            return Err("SDL_BeginGPUCopyPass failed");
+        }
+
+        f(pass, transfer);
+        backend.end_copy_pass(pass);
+
+        let fence = backend.submit_command_buffer_and_acquire_fence(cmd);
+        if fence.is_null() {
+            backend.release_transfer_buffer(transfer);
+            return Err("SDL_SubmitGPUCommandBufferAndAcquireFence failed");
+        }
+        if !backend.wait_for_fence(fence) {
+            backend.release_fence(fence);
+            backend.release_transfer_buffer(transfer);
+            return Err("SDL_WaitForGPUFences failed");
+        }
+        backend.release_fence(fence);
+        let data = self.read_transfer_buffer(transfer, size);
+        self.release_transfer_buffer(transfer);
+        data
+    }
+
+    /// Copy the first `size` bytes out of a download transfer buffer whose
+    /// command buffer has completed.
+    pub(crate) fn read_transfer_buffer(&self, transfer: *mut gpu::SDL_GPUTransferBuffer, size: u32) -> Result<Vec<u8>, &'static str> {
+        let ptr = self.backend.map_transfer_buffer(transfer, false);
+        if ptr.is_null() {
+            return Err("SDL_MapGPUTransferBuffer failed");
+        }
+        let mut data = vec![0u8; size as usize];
+        unsafe {
             std::ptr::copy_nonoverlapping(ptr as *const u8, data.as_mut_ptr(), size as usize);
-            gpu::SDL_UnmapGPUTransferBuffer(self.inner, transfer);
-            gpu::SDL_ReleaseGPUTransferBuffer(self.inner, transfer);
-
-            Ok(data)
         }
+        self.backend.unmap_transfer_buffer(transfer);
+        Ok(data)
+    }
+
+    pub(crate) fn release_transfer_buffer(&self, transfer: *mut gpu::SDL_GPUTransferBuffer) {
+        self.backend.release_transfer_buffer(transfer);
     }
 
     pub fn get_swapchain_texture_format(&self) -> SDL_GPUTextureFormat {
         let window = self.window.as_ref().expect("Device has no window");
-        unsafe { gpu::SDL_GetGPUSwapchainTextureFormat(self.inner, window.raw()) }
+        self.get_window_swapchain_texture_format(window)
+    }
+
+    /// Swapchain texture format of a claimed window.
+    pub fn get_window_swapchain_texture_format(&self, window: &Window) -> SDL_GPUTextureFormat {
+        self.backend.swapchain_texture_format(window.raw())
     }
 
     pub fn get_shader_formats(&self) -> SDL_GPUShaderFormat {
-        unsafe { gpu::SDL_GetGPUShaderFormats(self.inner) }
+        self.backend.shader_formats()
     }
 
     pub fn get_driver_name(&self) -> String
     {
-        unsafe
-        {
-            std::ffi::CStr::from_ptr(sys::gpu::SDL_GetGPUDeviceDriver(self.inner)).to_string_lossy().to_string()
-        }
+        self.backend.driver_name()
     }
 
     /// Get the device properties for this device
     pub fn get_device_properties(&self) -> sys::properties::SDL_PropertiesID {
-        unsafe
-        {
-            sys::gpu::SDL_GetGPUDeviceProperties(self.inner)
-        }
+        self.backend.device_properties()
     }
 
 
     pub fn acquire_command_buffer(&self) -> Result<CommandBuffer<'_>, &'static str> {
-        unsafe {
-            let raw = gpu::SDL_AcquireGPUCommandBuffer(self.inner);
-            if raw.is_null() {
-                return Err("SDL_AcquireGPUCommandBuffer failed");
-            }
-            self.cmd_buf_count.fetch_add(1, Ordering::Relaxed);
-            Ok(CommandBuffer { inner: raw, device: self, submitted: false, pass_active: Cell::new(false) })
-        }
+        let raw = self.backend.acquire_command_buffer();
+        if raw.is_null() {
+            return Err("SDL_AcquireGPUCommandBuffer failed");
+        }
+        if self.cmd_buf_count.fetch_add(1, Ordering::Relaxed) == 0 {
+            self.texture_pool.borrow_mut().begin_frame(self);
+        }
+        let id = self.next_cmd_buf_id.get();
+        self.next_cmd_buf_id.set(id + 1);
+        Ok(CommandBuffer {
+            inner: raw,
+            device: self,
+            id,
+            submitted: false,
+            fence_waiters: RefCell::new(Vec::new()),
+        })
     }
 
     /// Called when a command buffer is submitted or cancelled.
@@ -843,47 +1274,636 @@ impl Device {
         if prev == 1 {
             let mut pending = self.pending_transfer_buffers.borrow_mut();
             for tb in pending.drain(..) {
-                unsafe { gpu::SDL_ReleaseGPUTransferBuffer(self.inner, tb); }
+                self.backend.release_transfer_buffer(tb);
             }
         }
     }
 
-    pub fn wait_for_swapchain(&self) -> Result<(), &'static str> {
-        let window = self.window.as_ref()
-            .ok_or("Device has no window")?;
-        unsafe {
-            if !gpu::SDL_WaitForGPUSwapchain(self.inner, window.raw()) {
-                return Err("SDL_WaitForGPUSwapchain failed");
-            }
+    /// Get a texture matching `desc` from the pool, creating one if none is free.
+    /// Return it with [`release_pooled_texture`](Self::release_pooled_texture)
+    /// instead of destroying it.
+    pub fn acquire_pooled_texture(&self, desc: &TextureDesc) -> Result<Texture, &'static str> {
+        self.texture_pool.borrow_mut().acquire(self, desc)
+    }
+
+    /// Give a pooled texture back. It can be handed out again once the
+    /// command buffers recorded up to now have finished on the GPU.
+    pub fn release_pooled_texture(&self, texture: Texture) {
+        self.texture_pool.borrow_mut().release(texture);
+    }
+
+    /// Destroy free pooled textures after this many frames without use
+    /// (default 3). A frame starts whenever a command buffer is acquired
+    /// while no other is being recorded.
+    pub fn set_texture_pool_max_unused_frames(&self, frames: u32) {
+        self.texture_pool.borrow_mut().set_max_unused_frames(frames);
+    }
+
+    /// Number and memory size of the textures owned by the pool.
+    pub fn texture_pool_stats(&self) -> TexturePoolStats {
+        self.texture_pool.borrow().stats()
+    }
+
+    /// Commands recorded since the last
+    /// [`take_frame_stats`](Self::take_frame_stats), including uploads and
+    /// downloads made without a command buffer open.
+    pub fn frame_stats(&self) -> FrameStats {
+        self.frame_stats.get()
+    }
+
+    /// End the current frame's statistics: returns them and starts counting
+    /// from zero. Call it once per frame, e.g. after submitting the frame's
+    /// last command buffer.
+    pub fn take_frame_stats(&self) -> FrameStats {
+        self.frame_stats.take()
+    }
+
+    fn count(&self, f: impl FnOnce(&mut FrameStats)) {
+        let mut stats = self.frame_stats.get();
+        f(&mut stats);
+        self.frame_stats.set(stats);
+    }
+
+    /// Texture-sampler pairs bind a texture and a sampler each.
+    fn count_sampler_binds(&self, pairs: usize) {
+        self.count(|stats| {
+            stats.texture_binds += pairs as u32;
+            stats.sampler_binds += pairs as u32;
+        });
+    }
+
+    /// List every live texture, buffer, sampler, shader and pipeline with
+    /// its name, size and creation site. Compare inventories over time to
+    /// spot handles that are never destroyed.
+    pub fn resource_inventory(&self) -> ResourceInventory {
+        let mut internal: Vec<Texture> = self.texture_pool.borrow().textures().collect();
+        internal.extend(self.windows.borrow().values().map(|w| w.capture).filter(Texture::is_valid));
+        let mut resources = Vec::new();
+        let mut push = |kind, handle, name: &Option<String>, created_at| {
+            resources.push(LiveResource {
+                kind,
+                handle,
+                name: name.clone(),
+                dimensions: None,
+                bytes: 0,
+                created_at,
+                internal: false,
+            });
+        };
+        self.resources.samplers.for_each(|i, slot| push(ResourceKind::Sampler, i, &slot.name, slot.created_at));
+        self.resources.shaders.for_each(|i, slot| push(ResourceKind::Shader, i, &slot.name, slot.created_at));
+        self.resources.graphics_pipelines.for_each(|i, slot| push(ResourceKind::GraphicsPipeline, i, &slot.name, slot.created_at));
+        self.resources.compute_pipelines.for_each(|i, slot| push(ResourceKind::ComputePipeline, i, &slot.name, slot.created_at));
+        self.resources.textures.for_each(|i, slot| {
+            resources.push(LiveResource {
+                kind: ResourceKind::Texture,
+                handle: i,
+                name: slot.name.clone(),
+                dimensions: Some(slot.res),
+                bytes: slot.bytes,
+                created_at: slot.created_at,
+                internal: internal.contains(&Texture(i)),
+            });
+        });
+        self.resources.buffers.for_each(|i, slot| {
+            resources.push(LiveResource {
+                kind: ResourceKind::Buffer,
+                handle: i,
+                name: slot.name.clone(),
+                dimensions: None,
+                bytes: slot.size as u64,
+                created_at: slot.created_at,
+                internal: false,
+            });
+        });
+        resources.sort_by_key(|r| (r.kind, r.handle));
+        ResourceInventory { resources }
+    }
+
+    /// A handle to this device's resources that can be sent to other
+    /// threads, to create resources and upload data there while this
+    /// thread keeps recording.
+    pub fn shared(&self) -> SharedDevice {
+        SharedDevice::new(self.resources.clone())
+    }
+
+    /// Print the resources the caller never destroyed to stderr when the
+    /// device is dropped (default off). Resources the device owns itself,
+    /// such as pooled textures, are left out.
+    pub fn set_leak_report(&self, enabled: bool) {
+        self.leak_report.set(enabled);
+    }
+
+    /// Check before every draw and dispatch that a pipeline is bound and
+    /// that every vertex buffer, index buffer, sampler and storage slot its
+    /// shaders declare has been bound in the pass, panicking with the call,
+    /// pipeline name and slot otherwise (default off). Binds are checked
+    /// too: pipelines against the pass's target formats and sample count,
+    /// textures for the usage the binding needs. Applies to passes begun
+    /// after the call.
+    pub fn set_draw_validation(&self, enabled: bool) {
+        self.draw_validation.set(enabled);
+    }
+
+    pub fn draw_validation(&self) -> bool {
+        self.draw_validation.get()
+    }
+
+    pub fn wait_for_swapchain(&self) -> Result<(), &'static str> {
+        let window = self.window.as_ref()
+            .ok_or("Device has no window")?;
+        if !self.backend.wait_for_swapchain(window.raw()) {
+            return Err("SDL_WaitForGPUSwapchain failed");
         }
         Ok(())
     }
 }
 
-struct TextureSlot {
+/// Most windows a device can have claimed at once.
+const MAX_CLAIMED_WINDOWS: i32 = 64;
+
+/// Swapchain state of a window claimed by a device.
+struct ClaimedWindow {
+    /// Index of the window's swapchain handle, see [`Texture::swapchain`].
+    slot: i32,
+    raw: *mut video::SDL_Window,
+    /// The texture acquired this frame, until its command buffer is
+    /// submitted or dropped.
+    swapchain: Option<AcquiredSwapchain>,
+    /// Intermediate texture rendered into while swapchain capture is on.
+    capture: Texture,
+    /// Configuration last applied to the window's swapchain.
+    config: SwapchainConfig,
+}
+
+impl ClaimedWindow {
+    fn new(slot: i32, raw: *mut video::SDL_Window) -> Self {
+        Self { slot, raw, swapchain: None, capture: Texture::NONE, config: SwapchainConfig::default() }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct AcquiredSwapchain {
+    /// Id of the command buffer that acquired the texture.
+    owner: u64,
+    /// What the swapchain handle resolves to: the swapchain texture, or
+    /// the window's capture texture while capture is on.
+    texture: *mut gpu::SDL_GPUTexture,
+    /// The swapchain texture presented at submit.
+    present: *mut gpu::SDL_GPUTexture,
+    width: u32,
+    height: u32,
+    format: SDL_GPUTextureFormat,
+}
+
+/// A submitted command buffer's fence, released with the last reference.
+pub(crate) struct GpuFence {
+    backend: Arc<dyn Backend>,
+    inner: *mut gpu::SDL_GPUFence,
+}
+
+impl GpuFence {
+    pub fn is_signaled(&self) -> bool {
+        self.backend.query_fence(self.inner)
+    }
+
+    pub fn wait(&self) -> Result<(), &'static str> {
+        if self.backend.wait_for_fence(self.inner) {
+            Ok(())
+        } else {
+            Err("SDL_WaitForGPUFences failed")
+        }
+    }
+}
+
+impl Drop for GpuFence {
+    fn drop(&mut self) {
+        self.backend.release_fence(self.inner);
+    }
+}
+
+/// A whole-texture download recorded with
+/// [`CommandBuffer::record_texture_download`].
+#[derive(Clone, Copy)]
+pub(crate) struct TextureDownload {
+    raw: *mut gpu::SDL_GPUTexture,
+    pub format: SDL_GPUTextureFormat,
+    pub width: u32,
+    pub height: u32,
+    /// Bytes the download writes, tightly packed.
+    pub size: u32,
+}
+
+/// Receives the fence of a command buffer once it is submitted. Stays
+/// empty if the command buffer is dropped without submitting.
+pub(crate) type FenceWaiter = Rc<OnceCell<Rc<GpuFence>>>;
+
+/// A window's swapchain texture, acquired by a [`CommandBuffer`] and only
+/// valid in that command buffer.
+///
+/// Nothing ties it to the command buffer at compile time. Instead the
+/// device remembers which command buffer acquired each window's texture,
+/// and using the handle in another command buffer, or after this one is
+/// submitted, is rejected when the pass or copy is recorded.
+#[derive(Clone, Copy)]
+pub struct SwapchainTexture {
+    texture: Texture,
+    width: u32,
+    height: u32,
+}
+
+impl SwapchainTexture {
+    /// Handle to use in color targets and blits of the acquiring command buffer.
+    pub fn texture(&self) -> Texture {
+        self.texture
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+}
+
+/// The slot maps behind resource handles, shared by a [`Device`] and its
+/// [`SharedDevice`](crate::shared_device::SharedDevice)s. What is left in
+/// them is released when the last of those is dropped.
+pub(crate) struct Resources {
+    pub(crate) backend: Arc<dyn Backend>,
+    pub(crate) textures: SlotMapMutex<TextureSlot>,
+    pub(crate) shaders: SlotMapMutex<ShaderSlot>,
+    pub(crate) graphics_pipelines: SlotMapMutex<GraphicsPipelineSlot>,
+    pub(crate) compute_pipelines: SlotMapMutex<ComputePipelineSlot>,
+    pub(crate) buffers: SlotMapMutex<BufferSlot>,
+    pub(crate) samplers: SlotMapMutex<SamplerSlot>,
+    /// Source of [`ShaderSlot::serial`].
+    next_shader_serial: AtomicU64,
+}
+
+impl Resources {
+    fn new(backend: Arc<dyn Backend>) -> Self {
+        Self {
+            backend,
+            textures: SlotMapMutex::default(),
+            shaders: SlotMapMutex::default(),
+            graphics_pipelines: SlotMapMutex::default(),
+            compute_pipelines: SlotMapMutex::default(),
+            buffers: SlotMapMutex::default(),
+            samplers: SlotMapMutex::default(),
+            next_shader_serial: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn create_texture(&self, info: &gpu::SDL_GPUTextureCreateInfo, created_at: &'static Location<'static>) -> Result<Texture, &'static str> {
+        validate_sample_count(info.sample_count)?;
+        let raw = self.backend.create_texture(info);
+        if raw.is_null() {
+            return Err("SDL_CreateGPUTexture failed");
+        }
+        let slot = TextureSlot {
+            inner: raw,
+            res: (info.width, info.height),
+            format: info.format,
+            usage: info.usage,
+            sample_count: info.sample_count,
+            name: read_string_property(info.props, gpu::SDL_PROP_GPU_TEXTURE_CREATE_NAME_STRING),
+            bytes: inventory::texture_bytes(info),
+            created_at,
+        };
+        let idx = self.textures.insert(slot);
+        Ok(Texture(idx))
+    }
+
+    pub(crate) fn build_shader(&self, info: &ShaderCreateInfo) -> Result<*mut gpu::SDL_GPUShader, &'static str> {
+        let entrypoint = std::ffi::CString::new(info.entrypoint)
+            .map_err(|_| "entrypoint contains interior nul byte")?;
+        let name = StringProperty::new(sys::properties::SDL_PropertiesID(0), gpu::SDL_PROP_GPU_SHADER_CREATE_NAME_STRING, info.name);
+        let raw_info = gpu::SDL_GPUShaderCreateInfo {
+            code_size: info.code.len(),
+            code: info.code.as_ptr(),
+            entrypoint: entrypoint.as_ptr(),
+            format: info.format,
+            stage: info.stage,
+            num_samplers: info.num_samplers,
+            num_storage_textures: info.num_storage_textures,
+            num_storage_buffers: info.num_storage_buffers,
+            num_uniform_buffers: info.num_uniform_buffers,
+            props: name.id(),
+        };
+        let raw = self.backend.create_shader(&raw_info);
+        if raw.is_null() {
+            return Err("SDL_CreateGPUShader failed");
+        }
+        Ok(raw)
+    }
+
+    pub(crate) fn create_shader(&self, info: &ShaderCreateInfo, created_at: &'static Location<'static>) -> Result<Shader, &'static str> {
+        let raw = self.build_shader(info)?;
+        let idx = self.shaders.insert(ShaderSlot {
+            inner: raw,
+            name: info.name.map(str::to_string),
+            resources: stage_resources(info),
+            serial: self.next_shader_serial.fetch_add(1, Ordering::Relaxed),
+            created_at,
+        });
+        Ok(Shader(idx))
+    }
+
+    #[allow(deprecated)]
+    pub(crate) fn build_graphics_pipeline(&self, info: &GraphicsPipelineCreateInfo) -> Result<*mut gpu::SDL_GPUGraphicsPipeline, &'static str> {
+        validate_sample_count(info.multisample_state.sample_count)?;
+        let name = StringProperty::new(
+            sys::properties::SDL_PropertiesID(0),
+            gpu::SDL_PROP_GPU_GRAPHICSPIPELINE_CREATE_NAME_STRING,
+            info.name.as_deref(),
+        );
+        let vertex_shader_raw = self.shaders.with(info.vertex_shader.0, |s| s.inner);
+        let fragment_shader_raw = self.shaders.with(info.fragment_shader.0, |s| s.inner);
+        let raw_info = gpu::SDL_GPUGraphicsPipelineCreateInfo {
+            vertex_shader: vertex_shader_raw,
+            fragment_shader: fragment_shader_raw,
+            vertex_input_state: gpu::SDL_GPUVertexInputState {
+                vertex_buffer_descriptions: if info.vertex_buffer_descriptions.is_empty() {
+                    std::ptr::null()
+                } else {
+                    info.vertex_buffer_descriptions.as_ptr()
+                },
+                num_vertex_buffers: info.vertex_buffer_descriptions.len() as u32,
+                vertex_attributes: if info.vertex_attributes.is_empty() {
+                    std::ptr::null()
+                } else {
+                    info.vertex_attributes.as_ptr()
+                },
+                num_vertex_attributes: info.vertex_attributes.len() as u32,
+            },
+            primitive_type: info.primitive_type,
+            rasterizer_state: info.rasterizer_state,
+            multisample_state: info.multisample_state,
+            depth_stencil_state: info.depth_stencil_state,
+            target_info: gpu::SDL_GPUGraphicsPipelineTargetInfo {
+                color_target_descriptions: if info.color_target_descriptions.is_empty() {
+                    std::ptr::null()
+                } else {
+                    info.color_target_descriptions.as_ptr()
+                },
+                num_color_targets: info.color_target_descriptions.len() as u32,
+                depth_stencil_format: info.depth_stencil_format,
+                has_depth_stencil_target: info.has_depth_stencil_target,
+                padding1: 0,
+                padding2: 0,
+                padding3: 0,
+            },
+            props: name.id(),
+        };
+
+        let raw = self.backend.create_graphics_pipeline(&raw_info);
+        if raw.is_null() {
+            return Err("SDL_CreateGPUGraphicsPipeline failed");
+        }
+        Ok(raw)
+    }
+
+    pub(crate) fn create_graphics_pipeline(&self, info: &GraphicsPipelineCreateInfo, created_at: &'static Location<'static>) -> Result<GraphicsPipeline, &'static str> {
+        let raw = self.build_graphics_pipeline(info)?;
+        let idx = self.graphics_pipelines.insert(GraphicsPipelineSlot {
+            inner: raw,
+            name: info.name.clone(),
+            requirements: self.graphics_requirements(info),
+            targets: pipeline_targets(info),
+            created_at,
+        });
+        Ok(GraphicsPipeline(idx))
+    }
+
+    pub(crate) fn graphics_requirements(&self, info: &GraphicsPipelineCreateInfo) -> GraphicsRequirements {
+        GraphicsRequirements {
+            vertex_buffers: info.vertex_buffer_descriptions.iter().fold(0, |mask, desc| mask | validation::slots(desc.slot, 1)),
+            vertex: self.shaders.with(info.vertex_shader.0, |slot| slot.resources),
+            fragment: self.shaders.with(info.fragment_shader.0, |slot| slot.resources),
+        }
+    }
+
+    pub(crate) fn build_compute_pipeline(&self, info: &ComputePipelineCreateInfo) -> Result<*mut gpu::SDL_GPUComputePipeline, &'static str> {
+        let entrypoint = std::ffi::CString::new(info.entrypoint)
+            .map_err(|_| "entrypoint contains interior nul byte")?;
+        let name = StringProperty::new(
+            sys::properties::SDL_PropertiesID(0),
+            gpu::SDL_PROP_GPU_COMPUTEPIPELINE_CREATE_NAME_STRING,
+            info.name,
+        );
+        let raw_info = gpu::SDL_GPUComputePipelineCreateInfo {
+            code_size: info.code.len(),
+            code: info.code.as_ptr(),
+            entrypoint: entrypoint.as_ptr(),
+            format: info.format,
+            num_samplers: info.num_samplers,
+            num_readonly_storage_textures: info.num_readonly_storage_textures,
+            num_readonly_storage_buffers: info.num_readonly_storage_buffers,
+            num_readwrite_storage_textures: info.num_readwrite_storage_textures,
+            num_readwrite_storage_buffers: info.num_readwrite_storage_buffers,
+            num_uniform_buffers: info.num_uniform_buffers,
+            threadcount_x: info.threadcount_x,
+            threadcount_y: info.threadcount_y,
+            threadcount_z: info.threadcount_z,
+            props: name.id(),
+        };
+        let raw = self.backend.create_compute_pipeline(&raw_info);
+        if raw.is_null() {
+            return Err("SDL_CreateGPUComputePipeline failed");
+        }
+        Ok(raw)
+    }
+
+    pub(crate) fn create_compute_pipeline(&self, info: &ComputePipelineCreateInfo, created_at: &'static Location<'static>) -> Result<ComputePipeline, &'static str> {
+        let raw = self.build_compute_pipeline(info)?;
+        let idx = self.compute_pipelines.insert(ComputePipelineSlot {
+            inner: raw,
+            name: info.name.map(str::to_string),
+            requirements: compute_requirements(info),
+            created_at,
+        });
+        Ok(ComputePipeline(idx))
+    }
+
+    pub(crate) fn create_buffer(
+        &self,
+        usage: SDL_GPUBufferUsageFlags,
+        size: u32,
+        name: Option<&str>,
+        created_at: &'static Location<'static>,
+    ) -> Result<GPUBuffer, &'static str> {
+        let props = StringProperty::new(sys::properties::SDL_PropertiesID(0), gpu::SDL_PROP_GPU_BUFFER_CREATE_NAME_STRING, name);
+        let info = gpu::SDL_GPUBufferCreateInfo {
+            usage,
+            size,
+            props: props.id(),
+        };
+        let raw = self.backend.create_buffer(&info);
+        if raw.is_null() {
+            return Err("SDL_CreateGPUBuffer failed");
+        }
+        let idx = self.buffers.insert(BufferSlot {
+            inner: raw,
+            size,
+            name: name.map(str::to_string),
+            created_at,
+        });
+        Ok(GPUBuffer(idx))
+    }
+
+    pub(crate) fn create_sampler(&self, info: &gpu::SDL_GPUSamplerCreateInfo, created_at: &'static Location<'static>) -> Result<Sampler, &'static str> {
+        let raw = self.backend.create_sampler(info);
+        if raw.is_null() {
+            return Err("SDL_CreateGPUSampler failed");
+        }
+        let name = read_string_property(info.props, gpu::SDL_PROP_GPU_SAMPLER_CREATE_NAME_STRING);
+        let idx = self.samplers.insert(SamplerSlot { inner: raw, name, created_at });
+        Ok(Sampler(idx))
+    }
+
+    pub(crate) fn destroy_texture(&self, handle: Texture) {
+        let slot = self.textures.remove(handle.0);
+        self.backend.release_texture(slot.inner);
+    }
+
+    pub(crate) fn destroy_shader(&self, handle: Shader) {
+        let slot = self.shaders.remove(handle.0);
+        self.backend.release_shader(slot.inner);
+    }
+
+    pub(crate) fn destroy_graphics_pipeline(&self, handle: GraphicsPipeline) {
+        let slot = self.graphics_pipelines.remove(handle.0);
+        self.backend.release_graphics_pipeline(slot.inner);
+    }
+
+    pub(crate) fn destroy_compute_pipeline(&self, handle: ComputePipeline) {
+        let slot = self.compute_pipelines.remove(handle.0);
+        self.backend.release_compute_pipeline(slot.inner);
+    }
+
+    pub(crate) fn destroy_buffer(&self, handle: GPUBuffer) {
+        let slot = self.buffers.remove(handle.0);
+        self.backend.release_buffer(slot.inner);
+    }
+
+    pub(crate) fn destroy_sampler(&self, handle: Sampler) {
+        let slot = self.samplers.remove(handle.0);
+        self.backend.release_sampler(slot.inner);
+    }
+
+    /// Raw texture of a handle that is not a swapchain handle.
+    pub(crate) fn texture_raw(&self, handle: Texture) -> *mut gpu::SDL_GPUTexture {
+        self.textures.with(handle.0, |slot| slot.inner)
+    }
+
+    pub(crate) fn texture_res(&self, handle: Texture) -> (u32, u32) {
+        self.textures.with(handle.0, |slot| slot.res)
+    }
+
+    pub(crate) fn buffer_raw(&self, handle: GPUBuffer) -> *mut gpu::SDL_GPUBuffer {
+        self.buffers.with(handle.0, |slot| slot.inner)
+    }
+
+    pub(crate) fn buffer_size(&self, handle: GPUBuffer) -> u32 {
+        self.buffers.with(handle.0, |slot| slot.size)
+    }
+}
+
+impl Drop for Resources {
+    fn drop(&mut self) {
+        let backend = &self.backend;
+        self.buffers.for_each(|_, slot| backend.release_buffer(slot.inner));
+        self.graphics_pipelines.for_each(|_, slot| backend.release_graphics_pipeline(slot.inner));
+        self.compute_pipelines.for_each(|_, slot| backend.release_compute_pipeline(slot.inner));
+        self.shaders.for_each(|_, slot| backend.release_shader(slot.inner));
+        self.samplers.for_each(|_, slot| backend.release_sampler(slot.inner));
+        self.textures.for_each(|_, slot| backend.release_texture(slot.inner));
+    }
+}
+
+pub(crate) struct TextureSlot {
     inner: *mut gpu::SDL_GPUTexture,
     res: (u32, u32),
+    format: SDL_GPUTextureFormat,
+    usage: SDL_GPUTextureUsageFlags,
+    sample_count: SDL_GPUSampleCount,
+    name: Option<String>,
+    bytes: u64,
+    created_at: &'static Location<'static>,
 }
 
-struct ShaderSlot {
+pub(crate) struct ShaderSlot {
     inner: *mut gpu::SDL_GPUShader,
+    name: Option<String>,
+    resources: StageResources,
+    /// Unique per created shader, unlike the slot index, which is reused
+    /// once the shader is destroyed. Kept by `recreate_shader`.
+    serial: u64,
+    created_at: &'static Location<'static>,
 }
 
-struct GraphicsPipelineSlot {
+pub(crate) struct GraphicsPipelineSlot {
     inner: *mut gpu::SDL_GPUGraphicsPipeline,
+    name: Option<String>,
+    requirements: GraphicsRequirements,
+    targets: TargetFormats,
+    created_at: &'static Location<'static>,
 }
 
-struct ComputePipelineSlot {
+pub(crate) struct ComputePipelineSlot {
     inner: *mut gpu::SDL_GPUComputePipeline,
+    name: Option<String>,
+    requirements: ComputeRequirements,
+    created_at: &'static Location<'static>,
 }
 
-struct BufferSlot {
+pub(crate) struct BufferSlot {
     inner: *mut gpu::SDL_GPUBuffer,
     size: u32,
+    name: Option<String>,
+    created_at: &'static Location<'static>,
 }
 
-struct SamplerSlot {
+pub(crate) struct SamplerSlot {
     inner: *mut gpu::SDL_GPUSampler,
+    name: Option<String>,
+    created_at: &'static Location<'static>,
+}
+
+fn stage_resources(info: &ShaderCreateInfo) -> StageResources {
+    StageResources {
+        samplers: info.num_samplers,
+        storage_textures: info.num_storage_textures,
+        storage_buffers: info.num_storage_buffers,
+    }
+}
+
+fn pipeline_targets(info: &GraphicsPipelineCreateInfo) -> TargetFormats {
+    TargetFormats {
+        colors: info.color_target_descriptions.iter().map(|desc| desc.format).collect(),
+        depth_stencil: info.has_depth_stencil_target.then_some(info.depth_stencil_format),
+        sample_count: info.multisample_state.sample_count,
+    }
+}
+
+fn compute_requirements(info: &ComputePipelineCreateInfo) -> ComputeRequirements {
+    ComputeRequirements {
+        samplers: info.num_samplers,
+        readonly_storage_textures: info.num_readonly_storage_textures,
+        readonly_storage_buffers: info.num_readonly_storage_buffers,
+    }
+}
+
+// SAFETY: the raw pointers are SDL GPU objects, which may be used and
+// released from any thread.
+unsafe impl Send for TextureSlot {}
+unsafe impl Send for ShaderSlot {}
+unsafe impl Send for GraphicsPipelineSlot {}
+unsafe impl Send for ComputePipelineSlot {}
+unsafe impl Send for BufferSlot {}
+unsafe impl Send for SamplerSlot {}
+
+/// `text` for SDL's debug name and label calls, without nul bytes.
+fn debug_cstring(text: &str) -> std::ffi::CString {
+    std::ffi::CString::new(text.replace('\0', "")).unwrap_or_default()
 }
 
 /// Handle to a texture stored in a `Device`.
@@ -898,15 +1918,24 @@ impl Default for Texture
 }
 
 impl Texture {
-    /// Reserved handle for the current swapchain texture.
+    /// Reserved handle for the swapchain texture of the device's own
+    /// window, valid in the command buffer that acquired it.
     pub const SWAPCHAIN: Texture = Texture(-7777);
     pub const NONE: Texture = Texture(-1);
 
+    /// Reserved handle for the swapchain texture of the claimed window in
+    /// `slot`. Slot 0 is [`Texture::SWAPCHAIN`].
+    pub(crate) const fn swapchain(slot: i32) -> Texture {
+        Texture(Texture::SWAPCHAIN.0 - slot)
+    }
+
+    /// Whether this is the handle of a window's swapchain texture.
+    pub fn is_swapchain(&self) -> bool {
+        (Texture::SWAPCHAIN.0 - (MAX_CLAIMED_WINDOWS - 1)..=Texture::SWAPCHAIN.0).contains(&self.0)
+    }
+
     pub fn destroy(&mut self, device: &Device) {
-        let slot = device.textures.remove(self.0);
-        unsafe {
-            gpu::SDL_ReleaseGPUTexture(device.inner, slot.inner);
-        }
+        device.resources.destroy_texture(*self);
         self.0 = -1;
     }
 
@@ -922,10 +1951,7 @@ pub struct Shader(pub i32);
 
 impl Shader {
     pub fn destroy(&mut self, device: &Device) {
-        let slot = device.shaders.remove(self.0);
-        unsafe {
-            gpu::SDL_ReleaseGPUShader(device.inner, slot.inner);
-        }
+        device.resources.destroy_shader(*self);
         self.0 = -1;
     }
 }
@@ -936,10 +1962,7 @@ pub struct GraphicsPipeline(pub i32);
 
 impl GraphicsPipeline {
     pub fn destroy(&mut self, device: &Device) {
-        let slot = device.graphics_pipelines.remove(self.0);
-        unsafe {
-            gpu::SDL_ReleaseGPUGraphicsPipeline(device.inner, slot.inner);
-        }
+        device.resources.destroy_graphics_pipeline(*self);
         self.0 = -1;
     }
 }
@@ -950,10 +1973,7 @@ pub struct ComputePipeline(pub i32);
 
 impl ComputePipeline {
     pub fn destroy(&mut self, device: &Device) {
-        let slot = device.compute_pipelines.remove(self.0);
-        unsafe {
-            gpu::SDL_ReleaseGPUComputePipeline(device.inner, slot.inner);
-        }
+        device.resources.destroy_compute_pipeline(*self);
         self.0 = -1;
     }
 }
@@ -964,10 +1984,7 @@ pub struct GPUBuffer(pub i32);
 
 impl GPUBuffer {
     pub fn destroy(&mut self, device: &Device) {
-        let slot = device.buffers.remove(self.0);
-        unsafe {
-            gpu::SDL_ReleaseGPUBuffer(device.inner, slot.inner);
-        }
+        device.resources.destroy_buffer(*self);
         self.0 = -1;
     }
     pub fn is_valid(&self) -> bool 
@@ -978,11 +1995,8 @@ impl GPUBuffer {
 
 impl Default for GPUBuffer {
     fn default() -> Self {
-        Self
-        {
-            0: -1
-        }
-    }    
+        Self(-1)
+    }
 }
 
 /// Handle to a GPU sampler stored in a `Device`.
@@ -1001,10 +2015,7 @@ impl Sampler {
     }
 
     pub fn destroy(&mut self, device: &Device) {
-        let slot = device.samplers.remove(self.0);
-        unsafe {
-            gpu::SDL_ReleaseGPUSampler(device.inner, slot.inner);
-        }
+        device.resources.destroy_sampler(*self);
         self.0 = -1;
     }
 }
@@ -1023,6 +2034,7 @@ pub struct GPUBufferBinding {
 }
 
 
+#[derive(Clone)]
 pub struct GraphicsPipelineCreateInfo {
     /// The vertex shader used by the graphics pipeline.
     pub vertex_shader: Shader,
@@ -1046,6 +2058,65 @@ pub struct GraphicsPipelineCreateInfo {
     pub depth_stencil_format: SDL_GPUTextureFormat,
     /// Whether the pipeline uses a depth-stencil target.
     pub has_depth_stencil_target: bool,
+    /// Name shown in graphics debuggers. Not compared or hashed, so a
+    /// [`PipelineCache`](crate::pipeline_cache::PipelineCache) hit keeps the
+    /// name the pipeline was first created with.
+    pub name: Option<String>,
+}
+
+impl GraphicsPipelineCreateInfo {
+    /// The rasterizer state in a comparable form; its float fields rule out
+    /// deriving `Eq` and `Hash`.
+    #[allow(clippy::type_complexity)]
+    fn rasterizer_key(&self) -> (SDL_GPUFillMode, SDL_GPUCullMode, SDL_GPUFrontFace, [u32; 3], bool, bool) {
+        let r = &self.rasterizer_state;
+        (
+            r.fill_mode,
+            r.cull_mode,
+            r.front_face,
+            [
+                r.depth_bias_constant_factor.to_bits(),
+                r.depth_bias_clamp.to_bits(),
+                r.depth_bias_slope_factor.to_bits(),
+            ],
+            r.enable_depth_bias,
+            r.enable_depth_clip,
+        )
+    }
+}
+
+impl PartialEq for GraphicsPipelineCreateInfo {
+    fn eq(&self, other: &Self) -> bool {
+        self.vertex_shader == other.vertex_shader
+            && self.fragment_shader == other.fragment_shader
+            && self.vertex_attributes == other.vertex_attributes
+            && self.vertex_buffer_descriptions == other.vertex_buffer_descriptions
+            && self.primitive_type == other.primitive_type
+            && self.rasterizer_key() == other.rasterizer_key()
+            && self.multisample_state == other.multisample_state
+            && self.depth_stencil_state == other.depth_stencil_state
+            && self.color_target_descriptions == other.color_target_descriptions
+            && self.depth_stencil_format == other.depth_stencil_format
+            && self.has_depth_stencil_target == other.has_depth_stencil_target
+    }
+}
+
+impl Eq for GraphicsPipelineCreateInfo {}
+
+impl std::hash::Hash for GraphicsPipelineCreateInfo {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.vertex_shader.hash(state);
+        self.fragment_shader.hash(state);
+        self.vertex_attributes.hash(state);
+        self.vertex_buffer_descriptions.hash(state);
+        self.primitive_type.hash(state);
+        self.rasterizer_key().hash(state);
+        self.multisample_state.hash(state);
+        self.depth_stencil_state.hash(state);
+        self.color_target_descriptions.hash(state);
+        self.depth_stencil_format.hash(state);
+        self.has_depth_stencil_target.hash(state);
+    }
 }
 
 pub struct ComputePipelineCreateInfo<'a> {
@@ -1073,6 +2144,8 @@ pub struct ComputePipelineCreateInfo<'a> {
     pub threadcount_y: u32,
     /// The number of threads in the Z dimension of the workgroup.
     pub threadcount_z: u32,
+    /// Name shown in graphics debuggers.
+    pub name: Option<&'a str>,
 }
 
 /// A read-write storage buffer binding for a compute pass.
@@ -1089,11 +2162,64 @@ pub struct StorageTextureReadWriteBinding {
     pub cycle: bool,
 }
 
+/// A debug group opened with [`CommandBuffer::debug_group`], closed on
+/// drop. Commands are recorded into the group through it, as it derefs to
+/// the command buffer, which stays borrowed until the group closes:
+///
+/// ```compile_fail
+/// # fn f(device: &sdl3_gs::device::Device) -> Result<(), &'static str> {
+/// let mut cmd = device.acquire_command_buffer()?;
+/// let group = cmd.debug_group("shadows");
+/// cmd.submit()?; // error: `cmd` is borrowed by `group`
+/// # drop(group);
+/// # Ok(())
+/// # }
+/// ```
+pub struct DebugGroup<'b, 'a> {
+    cmd: &'b mut CommandBuffer<'a>,
+}
+
+impl<'a> std::ops::Deref for DebugGroup<'_, 'a> {
+    type Target = CommandBuffer<'a>;
+
+    fn deref(&self) -> &CommandBuffer<'a> {
+        self.cmd
+    }
+}
+
+impl<'a> std::ops::DerefMut for DebugGroup<'_, 'a> {
+    fn deref_mut(&mut self) -> &mut CommandBuffer<'a> {
+        self.cmd
+    }
+}
+
+impl Drop for DebugGroup<'_, '_> {
+    fn drop(&mut self) {
+        self.cmd.device.backend.pop_debug_group(self.cmd.inner);
+    }
+}
+
+/// A command buffer being recorded.
+///
+/// Beginning a pass borrows the command buffer mutably until the pass is
+/// dropped, so blits, swapchain acquisition, captures and submit can't be
+/// called while a pass is open, and only one pass is open at a time:
+///
+/// ```compile_fail
+/// # fn f(device: &sdl3_gs::device::Device) -> Result<(), &'static str> {
+/// let mut cmd = device.acquire_command_buffer()?;
+/// let pass = cmd.begin_copy_pass()?;
+/// cmd.submit()?; // error: `cmd` is borrowed by `pass`
+/// # drop(pass);
+/// # Ok(())
+/// # }
+/// ```
 pub struct CommandBuffer<'a> {
     inner: *mut gpu::SDL_GPUCommandBuffer,
     device: &'a Device,
+    id: u64,
     submitted: bool,
-    pass_active: Cell<bool>,
+    fence_waiters: RefCell<Vec<FenceWaiter>>,
 }
 
 impl<'a> CommandBuffer<'a> {
@@ -1101,79 +2227,240 @@ impl<'a> CommandBuffer<'a> {
         self.inner
     }
 
-    pub fn device(&self) -> &Device {
+    pub fn device(&self) -> &'a Device {
         self.device
     }
 
+    /// Open a named group of commands, shown in graphics debuggers such as
+    /// RenderDoc, until the returned guard is dropped. Record the group's
+    /// commands through the guard. Groups nest.
+    pub fn debug_group(&mut self, name: &str) -> DebugGroup<'_, 'a> {
+        self.device.backend.push_debug_group(self.inner, &debug_cstring(name));
+        DebugGroup { cmd: self }
+    }
+
+    /// Mark the current point in the command stream for graphics debuggers.
+    pub fn insert_debug_label(&self, text: &str) {
+        self.device.backend.insert_debug_label(self.inner, &debug_cstring(text));
+    }
+
+    /// Acquire the swapchain texture of a claimed window. Each window has
+    /// its own handle, so one command buffer can render to several windows.
+    /// Returns `None` when the window has no texture to render to, e.g.
+    /// while minimized.
     pub fn acquire_swapchain_texture(
         &self,
-    ) -> Result<Option<Texture>, &'static str> {
-        let window = self.device.window.as_ref()
-            .ok_or("Device has no window")?;
+        window: &Window,
+    ) -> Result<Option<SwapchainTexture>, &'static str> {
+        let slot = self.device.claimed_window_slot(window)?;
+        let (texture, width, height) = self.device.backend
+            .acquire_swapchain_texture(self.inner, window.raw(), false)
+            .ok_or("SDL_AcquireGPUSwapchainTexture failed")?;
+        self.finish_acquire(window, slot, texture, width, height)
+    }
+
+    /// Like [`acquire_swapchain_texture`](Self::acquire_swapchain_texture),
+    /// but waits for a swapchain texture to become available. Still returns
+    /// `None` when the window has nothing to render to, e.g. while minimized.
+    pub fn wait_and_acquire_swapchain_texture(
+        &self,
+        window: &Window,
+    ) -> Result<Option<SwapchainTexture>, &'static str> {
+        let slot = self.device.claimed_window_slot(window)?;
+        let (texture, width, height) = self.device.backend
+            .acquire_swapchain_texture(self.inner, window.raw(), true)
+            .ok_or("SDL_WaitAndAcquireGPUSwapchainTexture failed")?;
+        self.finish_acquire(window, slot, texture, width, height)
+    }
+
+    fn finish_acquire(
+        &self,
+        window: &Window,
+        slot: i32,
+        present: *mut gpu::SDL_GPUTexture,
+        width: u32,
+        height: u32,
+    ) -> Result<Option<SwapchainTexture>, &'static str> {
+        let format = self.device.get_window_swapchain_texture_format(window);
+        let texture = if !present.is_null() && self.device.swapchain_capture.get() {
+            self.device.swapchain_capture_texture(window, format, width, height)?
+        } else {
+            present
+        };
+        self.device.set_swapchain_texture(window, AcquiredSwapchain { owner: self.id, texture, present, width, height, format });
+        if present.is_null() {
+            Ok(None)
+        } else {
+            Ok(Some(SwapchainTexture { texture: Texture::swapchain(slot), width, height }))
+        }
+    }
 
-        let mut texture: *mut gpu::SDL_GPUTexture = std::ptr::null_mut();
-        let mut width: u32 = 0;
-        let mut height: u32 = 0;
+    /// Download the swapchain texture of the device's own window, as
+    /// rendered by the passes recorded so far. The screenshot is ready once
+    /// this command buffer completes on the GPU.
+    ///
+    /// Needs [`Device::set_swapchain_capture`] to be on when the texture
+    /// is acquired. If it is off, this turns it on and returns an error:
+    /// the screenshot can be taken from the next acquired texture on.
+    pub fn capture_swapchain(&self) -> Result<PendingScreenshot<'a>, &'static str> {
+        self.capture_swapchain_handle(Texture::SWAPCHAIN)
+    }
 
-        
+    /// Like [`capture_swapchain`](Self::capture_swapchain), for any claimed window.
+    pub fn capture_window_swapchain(&self, window: &Window) -> Result<PendingScreenshot<'a>, &'static str> {
+        self.capture_swapchain_handle(Texture::swapchain(self.device.claimed_window_slot(window)?))
+    }
 
-        unsafe {
-            let ok = gpu::SDL_AcquireGPUSwapchainTexture(
-                self.inner,
-                window.raw(),
-                &mut texture,
-                &mut width,
-                &mut height,
-            );
-            if !ok {
-                return Err("SDL_AcquireGPUSwapchainTexture failed");
+    fn capture_swapchain_handle(&self, handle: Texture) -> Result<PendingScreenshot<'a>, &'static str> {
+        let download = self.texture_download(handle)?;
+        let transfer = self.device.create_download_transfer_buffer(download.size)?;
+        match self.record_texture_download(handle, transfer) {
+            Ok(fence) => Ok(PendingScreenshot::new(self.device, transfer, download, fence)),
+            Err(e) => {
+                self.device.release_transfer_buffer(transfer);
+                Err(e)
             }
         }
+    }
 
-        self.device.swapchain.set((texture, width, height));
-        if texture.is_null() {
-            Ok(None)
+    /// Where a download of `texture` reads from. A swapchain handle must be
+    /// acquired by this command buffer with capture on; if capture is off,
+    /// it is turned on for the next acquire.
+    pub(crate) fn texture_download(&self, texture: Texture) -> Result<TextureDownload, &'static str> {
+        let (raw, format, width, height) = if texture.is_swapchain() {
+            self.check_swapchain_owner(texture)?;
+            let swapchain = self.device.swapchain_texture(texture);
+            if swapchain.texture == swapchain.present {
+                self.device.set_swapchain_capture(true);
+                return Err("swapchain capture was off, it is on from the next acquired swapchain texture");
+            }
+            (swapchain.texture, swapchain.format, swapchain.width, swapchain.height)
         } else {
-            Ok(Some(Texture::SWAPCHAIN))
-        }
+            let (width, height) = self.device.get_texture_res(texture);
+            (self.device.texture_raw(texture), self.device.get_texture_format(texture), width, height)
+        };
+        let size = unsafe { gpu::SDL_CalculateGPUTextureFormatSize(format, width, height, 1) };
+        Ok(TextureDownload { raw, format, width, height, size })
     }
 
-    pub fn wait_and_acquire_swapchain_texture(
+    /// Record a copy pass downloading all of `texture` into `transfer`,
+    /// which must hold at least [`TextureDownload::size`] bytes. Returns
+    /// the fence to wait on before reading `transfer`.
+    pub(crate) fn record_texture_download(
         &self,
-    ) -> Result<Texture, &'static str> {
-        let window = self.device.window.as_ref()
-            .ok_or("Device has no window")?;
+        texture: Texture,
+        transfer: *mut gpu::SDL_GPUTransferBuffer,
+    ) -> Result<FenceWaiter, &'static str> {
+        let download = self.texture_download(texture)?;
+        let backend = &self.device.backend;
+        let pass = backend.begin_copy_pass(self.inner);
+        if pass.is_null() {
+            return Err("SDL_BeginGPUCopyPass failed");
+        }
+        let src = gpu::SDL_GPUTextureRegion {
+            texture: download.raw,
+            mip_level: 0,
+            layer: 0,
+            x: 0,
+            y: 0,
+            z: 0,
+            w: download.width,
+            h: download.height,
+            d: 1,
+        };
+        let dst = gpu::SDL_GPUTextureTransferInfo {
+            transfer_buffer: transfer,
+            offset: 0,
+            pixels_per_row: 0,
+            rows_per_layer: 0,
+        };
+        backend.download_from_texture(pass, &src, &dst);
+        backend.end_copy_pass(pass);
+        self.device.count(|stats| stats.download_bytes += download.size as u64);
+        Ok(self.fence_on_submit())
+    }
 
-        let mut texture: *mut gpu::SDL_GPUTexture = std::ptr::null_mut();
-        let mut width: u32 = 0;
-        let mut height: u32 = 0;
+    /// Ask for this command buffer's fence, filled in when it is submitted.
+    pub(crate) fn fence_on_submit(&self) -> FenceWaiter {
+        let waiter = FenceWaiter::default();
+        self.fence_waiters.borrow_mut().push(waiter.clone());
+        waiter
+    }
 
-        unsafe {
-            let ok = gpu::SDL_WaitAndAcquireGPUSwapchainTexture(
-                self.inner,
-                window.raw(),
-                &mut texture,
-                &mut width,
-                &mut height,
-            );
-            if !ok {
-                return Err("SDL_WaitAndAcquireGPUSwapchainTexture failed");
-            }
+    /// Blit the capture textures rendered into by this command buffer to
+    /// the swapchain textures they stand in for.
+    #[allow(deprecated)]
+    fn present_captured_swapchains(&self) {
+        let windows = self.device.windows.borrow();
+        let captured = windows.values()
+            .filter_map(|w| w.swapchain)
+            .filter(|s| s.owner == self.id && s.texture != s.present);
+        for swapchain in captured {
+            let region = |texture| gpu::SDL_GPUBlitRegion {
+                texture,
+                mip_level: 0,
+                layer_or_depth_plane: 0,
+                x: 0,
+                y: 0,
+                w: swapchain.width,
+                h: swapchain.height,
+            };
+            let info = gpu::SDL_GPUBlitInfo {
+                source: region(swapchain.texture),
+                destination: region(swapchain.present),
+                load_op: SDL_GPULoadOp::DONT_CARE,
+                clear_color: SDL_FColor { r: 0.0, g: 0.0, b: 0.0, a: 1.0 },
+                flip_mode: SDL_FlipMode::NONE,
+                filter: SDL_GPUFilter::NEAREST,
+                cycle: false,
+                padding1: 0,
+                padding2: 0,
+                padding3: 0,
+            };
+            self.device.backend.blit_texture(self.inner, &info);
         }
+    }
+
 
-        self.device.swapchain.set((texture, width, height));
-        Ok(Texture::SWAPCHAIN)
+    /// Check that `texture`, if it is a swapchain handle, was acquired by
+    /// this command buffer.
+    fn check_swapchain_owner(&self, texture: Texture) -> Result<(), &'static str> {
+        if !texture.is_swapchain() {
+            return Ok(());
+        }
+        match self.device.acquired_swapchain(texture) {
+            Some(swapchain) if swapchain.owner == self.id => Ok(()),
+            Some(_) => Err("swapchain texture was acquired by another command buffer"),
+            None => Err("no swapchain texture acquired"),
+        }
     }
 
     pub fn submit(mut self) -> Result<(), &'static str> {
         // Mark submitted before the call — SDL consumes the command buffer
         // regardless of success/failure, so Drop must not cancel it.
         self.submitted = true;
+        self.present_captured_swapchains();
+        // Pooled textures released while recording are recycled once the
+        // last command buffer in flight completes.
+        let retire_textures = self.device.cmd_buf_count.load(Ordering::Relaxed) == 1
+            && self.device.texture_pool.borrow().has_pending();
+        let fence_waiters = self.fence_waiters.take();
         self.device.on_command_buffer_done();
-        unsafe {
-            if !gpu::SDL_SubmitGPUCommandBuffer(self.inner) {
-                return Err("SDL_SubmitGPUCommandBuffer failed");
+        let backend = &self.device.backend;
+        if retire_textures || !fence_waiters.is_empty() {
+            let fence = backend.submit_command_buffer_and_acquire_fence(self.inner);
+            if fence.is_null() {
+                return Err("SDL_SubmitGPUCommandBufferAndAcquireFence failed");
+            }
+            let fence = Rc::new(GpuFence { backend: backend.clone(), inner: fence });
+            if retire_textures {
+                self.device.texture_pool.borrow_mut().retire_pending(fence.clone());
             }
+            for waiter in fence_waiters {
+                let _ = waiter.set(fence.clone());
+            }
+        } else if !backend.submit_command_buffer(self.inner) {
+            return Err("SDL_SubmitGPUCommandBuffer failed");
         }
         Ok(())
     }
@@ -1181,66 +2468,89 @@ impl<'a> CommandBuffer<'a> {
 
 impl<'a> CommandBuffer<'a> {
     /// Blit from a source texture region to a destination texture region.
-    /// Must not be called inside any pass.
+    /// Panics if a swapchain texture in `info` was not acquired by this
+    /// command buffer.
     pub fn blit_texture(&mut self, info: &BlitInfo) {
-        let raw = info.to_raw(self.device);
-        unsafe {
-            gpu::SDL_BlitGPUTexture(self.inner, &raw);
+        for texture in [info.source.texture, info.destination.texture] {
+            if let Err(e) = self.check_swapchain_owner(texture) {
+                panic!("{e}");
+            }
         }
+        let raw = info.to_raw(self.device);
+        self.device.backend.blit_texture(self.inner, &raw);
     }
 }
 
 impl<'a> CommandBuffer<'a> {
-    pub fn begin_copy_pass<'b>(&'b self) -> Result<CopyPass<'b>, &'static str> {
-        assert!(!self.pass_active.get(), "a pass is already active on this command buffer");
-        unsafe {
-            let raw = gpu::SDL_BeginGPUCopyPass(self.inner);
-            if raw.is_null() {
-                return Err("SDL_BeginGPUCopyPass failed");
-            }
-            self.pass_active.set(true);
-            Ok(CopyPass { inner: raw, device: self.device, pass_active: &self.pass_active })
+    pub fn begin_copy_pass<'b>(&'b mut self) -> Result<CopyPass<'b>, &'static str> {
+        let raw = self.device.backend.begin_copy_pass(self.inner);
+        if raw.is_null() {
+            return Err("SDL_BeginGPUCopyPass failed");
         }
+        Ok(CopyPass { inner: raw, device: self.device, _cmd: PhantomData })
     }
+
+    /// Fails if a target texture lacks COLOR_TARGET or DEPTH_STENCIL_TARGET
+    /// usage. Pipelines bound in the pass must match its target formats.
     pub fn begin_render_pass<'b>(
-        &'b self,
+        &'b mut self,
         color_targets: &[ColorTargetInfo],
         depth_stencil_target: Option<&DepthStencilTargetInfo>,
     ) -> Result<RenderPass<'b>, &'static str> {
-        assert!(!self.pass_active.get(), "a pass is already active on this command buffer");
+        let device = self.device;
+        for target in color_targets {
+            self.check_swapchain_owner(target.texture)?;
+            if !device.has_usage(target.texture, SDL_GPUTextureUsageFlags::COLOR_TARGET) {
+                return Err("color target texture was not created with COLOR_TARGET usage");
+            }
+            if let Some(resolve) = target.resolve_texture {
+                self.check_swapchain_owner(resolve)?;
+                if !device.has_usage(resolve, SDL_GPUTextureUsageFlags::COLOR_TARGET) {
+                    return Err("resolve texture was not created with COLOR_TARGET usage");
+                }
+            }
+        }
+        if let Some(ds) = depth_stencil_target
+            && !device.has_usage(ds.texture, SDL_GPUTextureUsageFlags::DEPTH_STENCIL_TARGET)
+        {
+            return Err("depth-stencil target texture was not created with DEPTH_STENCIL_TARGET usage");
+        }
+        let first_target = color_targets.first().map(|ct| ct.texture).or(depth_stencil_target.map(|ds| ds.texture));
+        let targets = TargetFormats {
+            colors: color_targets.iter().map(|ct| device.get_texture_format(ct.texture)).collect(),
+            depth_stencil: depth_stencil_target.map(|ds| device.get_texture_format(ds.texture)),
+            sample_count: first_target.map_or(SDL_GPUSampleCount::_1, |t| device.texture_sample_count(t)),
+        };
         let raw_targets: Vec<gpu::SDL_GPUColorTargetInfo> = color_targets
             .iter()
             .map(|ct| ct.to_raw(self.device))
             .collect();
 
         let raw_ds = depth_stencil_target.map(|ds| ds.to_raw(self.device));
-        let ds_ptr = raw_ds
-            .as_ref()
-            .map(|ds| ds as *const gpu::SDL_GPUDepthStencilTargetInfo)
-            .unwrap_or(std::ptr::null());
 
-        unsafe {
-            let raw = gpu::SDL_BeginGPURenderPass(
-                self.inner,
-                raw_targets.as_ptr(),
-                raw_targets.len() as u32,
-                ds_ptr,
-            );
-            if raw.is_null() {
-                return Err("SDL_BeginGPURenderPass failed");
-            }
-            self.pass_active.set(true);
-            Ok(RenderPass { inner: raw, cmd_buf: self.inner, device: self.device, pass_active: &self.pass_active })
-        }
+        let raw = self.device.backend.begin_render_pass(self.inner, &raw_targets, raw_ds.as_ref());
+        if raw.is_null() {
+            return Err("SDL_BeginGPURenderPass failed");
+        }
+        Ok(RenderPass {
+            inner: raw,
+            cmd_buf: self.inner,
+            device: self.device,
+            _cmd: PhantomData,
+            targets,
+            validation: self.device.draw_validation.get().then(RefCell::default),
+        })
     }
 
     #[allow(deprecated)]
     pub fn begin_compute_pass<'b>(
-        &'b self,
+        &'b mut self,
         storage_texture_bindings: &[StorageTextureReadWriteBinding],
         storage_buffer_bindings: &[StorageBufferReadWriteBinding],
     ) -> Result<ComputePass<'b>, &'static str> {
-        assert!(!self.pass_active.get(), "a pass is already active on this command buffer");
+        if storage_texture_bindings.iter().any(|b| !self.device.has_usage(b.texture, SDL_GPUTextureUsageFlags::COMPUTE_STORAGE_WRITE)) {
+            return Err("read-write storage texture was not created with COMPUTE_STORAGE_WRITE usage");
+        }
         let raw_tex_bindings: Vec<gpu::SDL_GPUStorageTextureReadWriteBinding> = storage_texture_bindings
             .iter()
             .map(|b| gpu::SDL_GPUStorageTextureReadWriteBinding {
@@ -1263,20 +2573,17 @@ impl<'a> CommandBuffer<'a> {
                 padding3: 0,
             })
             .collect();
-        unsafe {
-            let raw = gpu::SDL_BeginGPUComputePass(
-                self.inner,
-                if raw_tex_bindings.is_empty() { std::ptr::null() } else { raw_tex_bindings.as_ptr() },
-                raw_tex_bindings.len() as u32,
-                if raw_buf_bindings.is_empty() { std::ptr::null() } else { raw_buf_bindings.as_ptr() },
-                raw_buf_bindings.len() as u32,
-            );
-            if raw.is_null() {
-                return Err("SDL_BeginGPUComputePass failed");
-            }
-            self.pass_active.set(true);
-            Ok(ComputePass { inner: raw, cmd_buf: self.inner, device: self.device, pass_active: &self.pass_active })
-        }
+        let raw = self.device.backend.begin_compute_pass(self.inner, &raw_tex_bindings, &raw_buf_bindings);
+        if raw.is_null() {
+            return Err("SDL_BeginGPUComputePass failed");
+        }
+        Ok(ComputePass {
+            inner: raw,
+            cmd_buf: self.inner,
+            device: self.device,
+            _cmd: PhantomData,
+            validation: self.device.draw_validation.get().then(RefCell::default),
+        })
     }
 }
 
@@ -1284,10 +2591,40 @@ pub struct RenderPass<'b> {
     inner: *mut gpu::SDL_GPURenderPass,
     cmd_buf: *mut gpu::SDL_GPUCommandBuffer,
     pub device: &'b Device,
-    pass_active: &'b Cell<bool>,
+    /// Holds the command buffer's mutable borrow.
+    _cmd: PhantomData<&'b mut ()>,
+    /// Formats of the pass's targets, checked against bound pipelines with
+    /// draw validation on.
+    targets: TargetFormats,
+    /// Bindings so far, with draw validation on.
+    validation: Option<RefCell<RenderPassState>>,
 }
 
 impl RenderPass<'_> {
+    fn track(&self, f: impl FnOnce(&mut RenderPassState)) {
+        if let Some(state) = &self.validation {
+            f(&mut state.borrow_mut());
+        }
+    }
+
+    fn check_draw(&self, call: &str, indexed: bool) {
+        if let Some(state) = &self.validation {
+            state.borrow().check_draw(call, indexed);
+        }
+    }
+
+    /// [`Device::require_usage`], with draw validation on.
+    fn require_usage(&self, call: &str, textures: impl IntoIterator<Item = Texture>, usage: SDL_GPUTextureUsageFlags, usage_name: &str) {
+        if self.validation.is_some() {
+            self.device.require_usage(call, textures, usage, usage_name);
+        }
+    }
+
+    /// Mark the current point in the pass for graphics debuggers.
+    pub fn insert_debug_label(&self, text: &str) {
+        self.device.backend.insert_debug_label(self.cmd_buf, &debug_cstring(text));
+    }
+
     pub fn bind_vertex_buffers(&self, first_slot: u32, bindings: &[GPUBufferBinding]) {
         let raw_bindings: Vec<gpu::SDL_GPUBufferBinding> = bindings
             .iter()
@@ -1296,50 +2633,67 @@ impl RenderPass<'_> {
                 offset: b.offset,
             })
             .collect();
-        unsafe {
-            gpu::SDL_BindGPUVertexBuffers(
-                self.inner,
-                first_slot,
-                raw_bindings.as_ptr(),
-                raw_bindings.len() as u32,
-            );
-        }
+        self.device.backend.bind_vertex_buffers(self.inner, first_slot, &raw_bindings);
+        self.track(|state| state.vertex_buffers |= validation::slots(first_slot, bindings.len()));
     }
 
+    /// With draw validation on, panics if the pipeline was created for
+    /// target formats or a sample count other than this pass's.
     pub fn bind_graphics_pipeline(&self, pipeline: GraphicsPipeline) {
-        unsafe {
-            gpu::SDL_BindGPUGraphicsPipeline(
-                self.inner,
-                self.device.graphics_pipelines.with(pipeline.0, |slot| slot.inner),
-            );
+        let (raw, mismatch) = self.device.resources.graphics_pipelines.with(pipeline.0, |slot| {
+            let mismatch = self.validation.as_ref().and_then(|_| slot.targets.mismatch(&self.targets));
+            (slot.inner, mismatch.map(|m| (m, slot.name.clone())))
+        });
+        if let Some((mismatch, name)) = mismatch {
+            match name {
+                Some(name) => panic!("bind_graphics_pipeline: pipeline \"{name}\" doesn't match the pass targets: {mismatch}"),
+                None => panic!("bind_graphics_pipeline: pipeline {} doesn't match the pass targets: {mismatch}", pipeline.0),
+            }
         }
+        self.device.backend.bind_graphics_pipeline(self.inner, raw);
+        self.track(|state| {
+            state.pipeline = Some(self.device.resources.graphics_pipelines.with(pipeline.0, |slot| (slot.requirements, slot.name.clone())));
+        });
+        self.device.count(|stats| stats.pipeline_binds += 1);
     }
 
     pub fn draw_primitives(&self, num_vertices: u32, num_instances: u32, first_vertex: u32, first_instance: u32) {
-        unsafe {
-            gpu::SDL_DrawGPUPrimitives(self.inner, num_vertices, num_instances, first_vertex, first_instance);
-        }
+        self.check_draw("draw_primitives", false);
+        self.device.backend.draw_primitives(self.inner, num_vertices, num_instances, first_vertex, first_instance);
+        self.device.count(|stats| {
+            stats.draws += 1;
+            stats.instances += num_instances as u64;
+        });
     }
 
     pub fn draw_indexed_primitives(&self, num_indices: u32, num_instances: u32, first_index: u32, vertex_offset: i32, first_instance: u32) {
-        unsafe {
-            gpu::SDL_DrawGPUIndexedPrimitives(self.inner, num_indices, num_instances, first_index, vertex_offset, first_instance);
-        }
+        self.check_draw("draw_indexed_primitives", true);
+        self.device.backend.draw_indexed_primitives(self.inner, num_indices, num_instances, first_index, vertex_offset, first_instance);
+        self.device.count(|stats| {
+            stats.draws += 1;
+            stats.indexed_draws += 1;
+            stats.instances += num_instances as u64;
+        });
     }
 
     pub fn draw_primitives_indirect(&self, buffer: GPUBuffer, offset: u32, draw_count: u32) {
-        unsafe {
-            gpu::SDL_DrawGPUPrimitivesIndirect(self.inner, self.device.buffer_raw(buffer), offset, draw_count);
-        }
+        self.check_draw("draw_primitives_indirect", false);
+        self.device.backend.draw_primitives_indirect(self.inner, self.device.buffer_raw(buffer), offset, draw_count);
+        self.device.count(|stats| stats.draws += draw_count);
     }
 
     pub fn draw_indexed_primitives_indirect(&self, buffer: GPUBuffer, offset: u32, draw_count: u32) {
-        unsafe {
-            gpu::SDL_DrawGPUIndexedPrimitivesIndirect(self.inner, self.device.buffer_raw(buffer), offset, draw_count);
-        }
+        self.check_draw("draw_indexed_primitives_indirect", true);
+        self.device.backend.draw_indexed_primitives_indirect(self.inner, self.device.buffer_raw(buffer), offset, draw_count);
+        self.device.count(|stats| {
+            stats.draws += draw_count;
+            stats.indexed_draws += draw_count;
+        });
     }
 
+    /// With draw validation on, panics if a texture lacks SAMPLER usage.
     pub fn bind_fragment_samplers(&self, first_slot: u32, bindings: &[TextureSamplerBinding]) {
+        self.require_usage("bind_fragment_samplers", bindings.iter().map(|b| b.texture), SDL_GPUTextureUsageFlags::SAMPLER, "SAMPLER");
         let raw_bindings: Vec<gpu::SDL_GPUTextureSamplerBinding> = bindings
             .iter()
             .map(|b| gpu::SDL_GPUTextureSamplerBinding {
@@ -1347,36 +2701,19 @@ impl RenderPass<'_> {
                 sampler: self.device.sampler_raw(b.sampler),
             })
             .collect();
-        unsafe {
-            gpu::SDL_BindGPUFragmentSamplers(
-                self.inner,
-                first_slot,
-                raw_bindings.as_ptr(),
-                raw_bindings.len() as u32,
-            );
-        }
+        self.device.backend.bind_fragment_samplers(self.inner, first_slot, &raw_bindings);
+        self.track(|state| state.fragment_samplers |= validation::slots(first_slot, bindings.len()));
+        self.device.count_sampler_binds(bindings.len());
     }
 
     pub fn push_vertex_uniform_data(&self, slot_index: u32, data: &[u8]) {
-        unsafe {
-            gpu::SDL_PushGPUVertexUniformData(
-                self.cmd_buf,
-                slot_index,
-                data.as_ptr() as *const std::ffi::c_void,
-                data.len() as u32,
-            );
-        }
+        self.device.backend.push_vertex_uniform_data(self.cmd_buf, slot_index, data);
+        self.device.count(|stats| stats.uniform_bytes += data.len() as u64);
     }
 
     pub fn push_fragment_uniform_data(&self, slot_index: u32, data: &[u8]) {
-        unsafe {
-            gpu::SDL_PushGPUFragmentUniformData(
-                self.cmd_buf,
-                slot_index,
-                data.as_ptr() as *const std::ffi::c_void,
-                data.len() as u32,
-            );
-        }
+        self.device.backend.push_fragment_uniform_data(self.cmd_buf, slot_index, data);
+        self.device.count(|stats| stats.uniform_bytes += data.len() as u64);
     }
 
     pub fn bind_index_buffer(&self, binding: &GPUBufferBinding, index_element_size: SDL_GPUIndexElementSize) {
@@ -1385,48 +2722,41 @@ impl RenderPass<'_> {
             buffer: self.device.buffer_raw(binding.buffer),
             offset: binding.offset,
         };
-        unsafe {
-            gpu::SDL_BindGPUIndexBuffer(self.inner, &raw, index_element_size);
-        }
+        self.device.backend.bind_index_buffer(self.inner, &raw, index_element_size);
+        self.track(|state| state.index_buffer = true);
     }
 
     pub fn set_viewport(&self, viewport: &SDL_GPUViewport) {
-        unsafe {
-            gpu::SDL_SetGPUViewport(self.inner, viewport);
-        }
+        self.device.backend.set_viewport(self.inner, viewport);
     }
 
     pub fn set_scissor(&self, rect: &SDL_Rect) {
-        unsafe {
-            gpu::SDL_SetGPUScissor(self.inner, rect);
-        }
+        self.device.backend.set_scissor(self.inner, rect);
     }
 
     pub fn set_stencil_reference(&self, reference: u8) {
-        unsafe {
-            gpu::SDL_SetGPUStencilReference(self.inner, reference);
-        }
+        self.device.backend.set_stencil_reference(self.inner, reference);
     }
 
     pub fn set_blend_constants(&self, blend_constants: SDL_FColor) {
-        unsafe {
-            gpu::SDL_SetGPUBlendConstants(self.inner, blend_constants);
-        }
+        self.device.backend.set_blend_constants(self.inner, blend_constants);
     }
 
+    /// With draw validation on, panics if a texture lacks GRAPHICS_STORAGE_READ usage.
     pub fn bind_fragment_storage_textures(&self, first_slot: u32, textures: &[Texture]) {
+        self.require_usage(
+            "bind_fragment_storage_textures",
+            textures.iter().copied(),
+            SDL_GPUTextureUsageFlags::GRAPHICS_STORAGE_READ,
+            "GRAPHICS_STORAGE_READ",
+        );
         let raw: Vec<*mut gpu::SDL_GPUTexture> = textures
             .iter()
             .map(|t| self.device.texture_raw(*t))
             .collect();
-        unsafe {
-            gpu::SDL_BindGPUFragmentStorageTextures(
-                self.inner,
-                first_slot,
-                raw.as_ptr(),
-                raw.len() as u32,
-            );
-        }
+        self.device.backend.bind_fragment_storage_textures(self.inner, first_slot, &raw);
+        self.track(|state| state.fragment_storage_textures |= validation::slots(first_slot, textures.len()));
+        self.device.count(|stats| stats.texture_binds += textures.len() as u32);
     }
 
     pub fn bind_fragment_storage_buffers(&self, first_slot: u32, buffers: &[GPUBuffer]) {
@@ -1434,14 +2764,8 @@ impl RenderPass<'_> {
             .iter()
             .map(|b| self.device.buffer_raw(*b))
             .collect();
-        unsafe {
-            gpu::SDL_BindGPUFragmentStorageBuffers(
-                self.inner,
-                first_slot,
-                raw.as_ptr(),
-                raw.len() as u32,
-            );
-        }
+        self.device.backend.bind_fragment_storage_buffers(self.inner, first_slot, &raw);
+        self.track(|state| state.fragment_storage_buffers |= validation::slots(first_slot, buffers.len()));
     }
 
     pub fn bind_vertex_storage_buffers(&self, first_slot: u32, buffers: &[GPUBuffer]) {
@@ -1449,30 +2773,53 @@ impl RenderPass<'_> {
             .iter()
             .map(|b| self.device.buffer_raw(*b))
             .collect();
-        unsafe {
-            gpu::SDL_BindGPUVertexStorageBuffers(
-                self.inner,
-                first_slot,
-                raw.as_ptr(),
-                raw.len() as u32,
-            );
-        }
+        self.device.backend.bind_vertex_storage_buffers(self.inner, first_slot, &raw);
+        self.track(|state| state.vertex_storage_buffers |= validation::slots(first_slot, buffers.len()));
+    }
+
+    /// With draw validation on, panics if a texture lacks SAMPLER usage.
+    pub fn bind_vertex_samplers(&self, first_slot: u32, bindings: &[TextureSamplerBinding]) {
+        self.require_usage("bind_vertex_samplers", bindings.iter().map(|b| b.texture), SDL_GPUTextureUsageFlags::SAMPLER, "SAMPLER");
+        let raw_bindings: Vec<gpu::SDL_GPUTextureSamplerBinding> = bindings
+            .iter()
+            .map(|b| gpu::SDL_GPUTextureSamplerBinding {
+                texture: self.device.texture_raw(b.texture),
+                sampler: self.device.sampler_raw(b.sampler),
+            })
+            .collect();
+        self.device.backend.bind_vertex_samplers(self.inner, first_slot, &raw_bindings);
+        self.track(|state| state.vertex_samplers |= validation::slots(first_slot, bindings.len()));
+        self.device.count_sampler_binds(bindings.len());
+    }
+
+    /// With draw validation on, panics if a texture lacks GRAPHICS_STORAGE_READ usage.
+    pub fn bind_vertex_storage_textures(&self, first_slot: u32, textures: &[Texture]) {
+        self.require_usage(
+            "bind_vertex_storage_textures",
+            textures.iter().copied(),
+            SDL_GPUTextureUsageFlags::GRAPHICS_STORAGE_READ,
+            "GRAPHICS_STORAGE_READ",
+        );
+        let raw: Vec<*mut gpu::SDL_GPUTexture> = textures
+            .iter()
+            .map(|t| self.device.texture_raw(*t))
+            .collect();
+        self.device.backend.bind_vertex_storage_textures(self.inner, first_slot, &raw);
+        self.track(|state| state.vertex_storage_textures |= validation::slots(first_slot, textures.len()));
+        self.device.count(|stats| stats.texture_binds += textures.len() as u32);
     }
 }
 
 impl Drop for RenderPass<'_> {
     fn drop(&mut self) {
-        unsafe {
-            gpu::SDL_EndGPURenderPass(self.inner);
-        }
-        self.pass_active.set(false);
+        self.device.backend.end_render_pass(self.inner);
     }
 }
 
 pub struct CopyPass<'b> {
     pub(crate) inner: *mut gpu::SDL_GPUCopyPass,
     device: &'b Device,
-    pass_active: &'b Cell<bool>,
+    _cmd: PhantomData<&'b mut ()>,
 }
 
 impl CopyPass<'_> {
@@ -1493,18 +2840,13 @@ impl CopyPass<'_> {
             buffer: self.device.buffer_raw(destination),
             offset: destination_offset,
         };
-        unsafe {
-            gpu::SDL_CopyGPUBufferToBuffer(self.inner, &src, &dst, size, cycle);
-        }
+        self.device.backend.copy_buffer_to_buffer(self.inner, &src, &dst, size, cycle);
     }
 }
 
 impl Drop for CopyPass<'_> {
     fn drop(&mut self) {
-        unsafe {
-            gpu::SDL_EndGPUCopyPass(self.inner);
-        }
-        self.pass_active.set(false);
+        self.device.backend.end_copy_pass(self.inner);
     }
 }
 
@@ -1512,32 +2854,60 @@ pub struct ComputePass<'b> {
     inner: *mut gpu::SDL_GPUComputePass,
     cmd_buf: *mut gpu::SDL_GPUCommandBuffer,
     device: &'b Device,
-    pass_active: &'b Cell<bool>,
+    _cmd: PhantomData<&'b mut ()>,
+    /// Bindings so far, with draw validation on.
+    validation: Option<RefCell<ComputePassState>>,
 }
 
 impl ComputePass<'_> {
-    pub fn bind_compute_pipeline(&self, pipeline: ComputePipeline) {
-        unsafe {
-            gpu::SDL_BindGPUComputePipeline(
-                self.inner,
-                self.device.compute_pipelines.with(pipeline.0, |slot| slot.inner),
-            );
+    fn track(&self, f: impl FnOnce(&mut ComputePassState)) {
+        if let Some(state) = &self.validation {
+            f(&mut state.borrow_mut());
         }
     }
 
+    fn check_dispatch(&self, call: &str) {
+        if let Some(state) = &self.validation {
+            state.borrow().check_dispatch(call);
+        }
+    }
+
+    /// [`Device::require_usage`], with draw validation on.
+    fn require_usage(&self, call: &str, textures: impl IntoIterator<Item = Texture>, usage: SDL_GPUTextureUsageFlags, usage_name: &str) {
+        if self.validation.is_some() {
+            self.device.require_usage(call, textures, usage, usage_name);
+        }
+    }
+
+    /// Mark the current point in the pass for graphics debuggers.
+    pub fn insert_debug_label(&self, text: &str) {
+        self.device.backend.insert_debug_label(self.cmd_buf, &debug_cstring(text));
+    }
+
+    pub fn bind_compute_pipeline(&self, pipeline: ComputePipeline) {
+        let raw = self.device.resources.compute_pipelines.with(pipeline.0, |slot| slot.inner);
+        self.device.backend.bind_compute_pipeline(self.inner, raw);
+        self.track(|state| {
+            state.pipeline = Some(self.device.resources.compute_pipelines.with(pipeline.0, |slot| (slot.requirements, slot.name.clone())));
+        });
+        self.device.count(|stats| stats.pipeline_binds += 1);
+    }
+
+    /// With draw validation on, panics if a texture lacks COMPUTE_STORAGE_READ usage.
     pub fn bind_storage_textures(&self, first_slot: u32, textures: &[Texture]) {
+        self.require_usage(
+            "bind_storage_textures",
+            textures.iter().copied(),
+            SDL_GPUTextureUsageFlags::COMPUTE_STORAGE_READ,
+            "COMPUTE_STORAGE_READ",
+        );
         let raw: Vec<*mut gpu::SDL_GPUTexture> = textures
             .iter()
             .map(|t| self.device.texture_raw(*t))
             .collect();
-        unsafe {
-            gpu::SDL_BindGPUComputeStorageTextures(
-                self.inner,
-                first_slot,
-                raw.as_ptr(),
-                raw.len() as u32,
-            );
-        }
+        self.device.backend.bind_compute_storage_textures(self.inner, first_slot, &raw);
+        self.track(|state| state.storage_textures |= validation::slots(first_slot, textures.len()));
+        self.device.count(|stats| stats.texture_binds += textures.len() as u32);
     }
 
     pub fn bind_storage_buffers(&self, first_slot: u32, buffers: &[GPUBuffer]) {
@@ -1545,17 +2915,13 @@ impl ComputePass<'_> {
             .iter()
             .map(|b| self.device.buffer_raw(*b))
             .collect();
-        unsafe {
-            gpu::SDL_BindGPUComputeStorageBuffers(
-                self.inner,
-                first_slot,
-                raw.as_ptr(),
-                raw.len() as u32,
-            );
-        }
+        self.device.backend.bind_compute_storage_buffers(self.inner, first_slot, &raw);
+        self.track(|state| state.storage_buffers |= validation::slots(first_slot, buffers.len()));
     }
 
+    /// With draw validation on, panics if a texture lacks SAMPLER usage.
     pub fn bind_samplers(&self, first_slot: u32, bindings: &[TextureSamplerBinding]) {
+        self.require_usage("bind_samplers", bindings.iter().map(|b| b.texture), SDL_GPUTextureUsageFlags::SAMPLER, "SAMPLER");
         let raw_bindings: Vec<gpu::SDL_GPUTextureSamplerBinding> = bindings
             .iter()
             .map(|b| gpu::SDL_GPUTextureSamplerBinding {
@@ -1563,56 +2929,44 @@ impl ComputePass<'_> {
                 sampler: self.device.sampler_raw(b.sampler),
             })
             .collect();
-        unsafe {
-            gpu::SDL_BindGPUComputeSamplers(
-                self.inner,
-                first_slot,
-                raw_bindings.as_ptr(),
-                raw_bindings.len() as u32,
-            );
-        }
+        self.device.backend.bind_compute_samplers(self.inner, first_slot, &raw_bindings);
+        self.track(|state| state.samplers |= validation::slots(first_slot, bindings.len()));
+        self.device.count_sampler_binds(bindings.len());
     }
 
     pub fn push_compute_uniform_data(&self, slot_index: u32, data: &[u8]) {
-        unsafe {
-            gpu::SDL_PushGPUComputeUniformData(
-                self.cmd_buf,
-                slot_index,
-                data.as_ptr() as *const std::ffi::c_void,
-                data.len() as u32,
-            );
-        }
+        self.device.backend.push_compute_uniform_data(self.cmd_buf, slot_index, data);
+        self.device.count(|stats| stats.uniform_bytes += data.len() as u64);
     }
 
     pub fn dispatch(&self, groupcount_x: u32, groupcount_y: u32, groupcount_z: u32) {
-        unsafe {
-            gpu::SDL_DispatchGPUCompute(self.inner, groupcount_x, groupcount_y, groupcount_z);
-        }
+        self.check_dispatch("dispatch");
+        self.device.backend.dispatch_compute(self.inner, groupcount_x, groupcount_y, groupcount_z);
+        self.device.count(|stats| stats.dispatches += 1);
     }
 
     pub fn dispatch_indirect(&self, buffer: GPUBuffer, offset: u32) {
-        unsafe {
-            gpu::SDL_DispatchGPUComputeIndirect(self.inner, self.device.buffer_raw(buffer), offset);
-        }
+        self.check_dispatch("dispatch_indirect");
+        self.device.backend.dispatch_compute_indirect(self.inner, self.device.buffer_raw(buffer), offset);
+        self.device.count(|stats| stats.dispatches += 1);
     }
 }
 
 impl Drop for ComputePass<'_> {
     fn drop(&mut self) {
-        unsafe {
-            gpu::SDL_EndGPUComputePass(self.inner);
-        }
-        self.pass_active.set(false);
+        self.device.backend.end_compute_pass(self.inner);
     }
 }
 
 impl Drop for CommandBuffer<'_> {
     fn drop(&mut self) {
-        self.device.swapchain.set((std::ptr::null_mut(), 0, 0));
-        if !self.submitted {
-            unsafe {
-                gpu::SDL_CancelGPUCommandBuffer(self.inner);
+        for window in self.device.windows.borrow_mut().values_mut() {
+            if window.swapchain.is_some_and(|s| s.owner == self.id) {
+                window.swapchain = None;
             }
+        }
+        if !self.submitted {
+            self.device.backend.cancel_command_buffer(self.inner);
             self.device.on_command_buffer_done();
         }
     }
@@ -1620,38 +2974,89 @@ impl Drop for CommandBuffer<'_> {
 
 impl Drop for Device {
     fn drop(&mut self) {
-        unsafe {
-            let (tb, _) = self.upload_transfer_buffer.get();
-            if !tb.is_null() {
-                gpu::SDL_ReleaseGPUTransferBuffer(self.inner, tb);
-            }
-            for pending_tb in self.pending_transfer_buffers.borrow().iter() {
-                gpu::SDL_ReleaseGPUTransferBuffer(self.inner, *pending_tb);
-            }
-            self.buffers.for_each(|_, slot| {
-                gpu::SDL_ReleaseGPUBuffer(self.inner, slot.inner);
-            });
-            self.graphics_pipelines.for_each(|_, slot| {
-                gpu::SDL_ReleaseGPUGraphicsPipeline(self.inner, slot.inner);
-            });
-            self.compute_pipelines.for_each(|_, slot| {
-                gpu::SDL_ReleaseGPUComputePipeline(self.inner, slot.inner);
-            });
-            self.shaders.for_each(|_, slot| {
-                gpu::SDL_ReleaseGPUShader(self.inner, slot.inner);
-            });
-            self.samplers.for_each(|_, slot| {
-                gpu::SDL_ReleaseGPUSampler(self.inner, slot.inner);
-            });
-            self.textures.for_each(|_, slot| {
-                gpu::SDL_ReleaseGPUTexture(self.inner, slot.inner);
-            });
-            if let Some(window) = &self.window
-            {
-                gpu::SDL_ReleaseWindowFromGPUDevice(self.inner, window.raw());
+        if self.leak_report.get() {
+            let leaks = self.resource_inventory().leaks();
+            if !leaks.is_empty() {
+                eprintln!("sdl3_gs: {} resources leaked by Device:\n{leaks}", leaks.resources.len());
             }
-            gpu::SDL_DestroyGPUDevice(self.inner);
         }
+        let backend = &self.backend;
+        let (tb, _) = self.upload_transfer_buffer.get();
+        if !tb.is_null() {
+            backend.release_transfer_buffer(tb);
+        }
+        for pending_tb in self.pending_transfer_buffers.borrow().iter() {
+            backend.release_transfer_buffer(*pending_tb);
+        }
+        self.texture_pool.borrow_mut().release_fences();
+        for claimed in self.windows.borrow().values() {
+            backend.release_window(claimed.raw);
+        }
+        // Resources still alive are released with the last of this device
+        // and its shared handles; the backend itself, and with it the GPU
+        // device, goes with the last reference to it.
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::null_backend::Command;
+    use crate::null_backend::tests::{null_device, texture};
+
+    #[test]
+    fn passes_open_one_at_a_time() {
+        let (device, log) = null_device();
+        let target = texture(&device, SDL_GPUTextureUsageFlags::COLOR_TARGET);
+        log.clear();
+
+        let mut cmd = device.acquire_command_buffer().unwrap();
+        {
+            let mut group = cmd.debug_group("frame");
+            group.begin_copy_pass().unwrap();
+            group.begin_render_pass(&[ColorTargetInfo::new(target)], None).unwrap();
+        }
+        cmd.begin_compute_pass(&[], &[]).unwrap();
+        cmd.submit().unwrap();
+
+        let commands = log.take();
+        let order: Vec<&str> = commands
+            .iter()
+            .map(|command| match command {
+                Command::AcquireCommandBuffer { .. } => "acquire",
+                Command::PushDebugGroup { name, .. } if name == "frame" => "push",
+                Command::BeginCopyPass { .. } => "begin copy",
+                Command::EndCopyPass { .. } => "end copy",
+                Command::BeginRenderPass { .. } => "begin render",
+                Command::EndRenderPass { .. } => "end render",
+                Command::PopDebugGroup { .. } => "pop",
+                Command::BeginComputePass { .. } => "begin compute",
+                Command::EndComputePass { .. } => "end compute",
+                Command::Submit { .. } => "submit",
+                _ => "other",
+            })
+            .collect();
+        assert_eq!(order, [
+            "acquire",
+            "push",
+            "begin copy",
+            "end copy",
+            "begin render",
+            "end render",
+            "pop",
+            "begin compute",
+            "end compute",
+            "submit",
+        ]);
+    }
+
+    #[test]
+    fn dropped_command_buffer_is_cancelled() {
+        let (device, log) = null_device();
+        let mut cmd = device.acquire_command_buffer().unwrap();
+        drop(cmd.begin_copy_pass().unwrap());
+        drop(cmd);
+        assert!(matches!(log.commands().last(), Some(Command::Cancel { .. })));
+        assert!(log.live_objects().is_empty());
+    }
+}
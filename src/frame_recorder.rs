@@ -0,0 +1,367 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use sdl3_sys::gpu;
+
+use crate::device::{CommandBuffer, Device, FenceWaiter, Texture, TextureDownload};
+use crate::image::Image;
+
+/// How recorded frames map to video time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameTiming {
+    /// Every captured frame is one video frame. Advance the simulation by
+    /// [`FrameRecorder::frame_delta`] per frame (see
+    /// [`FrameRecorder::simulated_time`]) so the video plays at real speed
+    /// however long each frame took to render.
+    Fixed,
+    /// Frames are placed at the wall-clock time they were captured, counted
+    /// from the first capture, and repeated or dropped to fill the video's
+    /// frame rate.
+    RealTime,
+}
+
+enum Output {
+    Y4m { writer: BufWriter<File>, size: Option<(u32, u32)> },
+    PngSequence { directory: PathBuf },
+}
+
+struct InFlight {
+    transfer: *mut gpu::SDL_GPUTransferBuffer,
+    capacity: u32,
+    download: TextureDownload,
+    fence: FenceWaiter,
+    captured_at: Duration,
+}
+
+/// Records presented frames to a `.y4m` video or a numbered PNG sequence.
+///
+/// [`capture`](Self::capture) records a download of the frame into one of a
+/// small ring of transfer buffers, after the frame's passes and before the
+/// command buffer is submitted. Frames are converted and written a few
+/// frames later, once their command buffers have completed, so recording
+/// doesn't stall the GPU the way [`Device::download_from_buffer`] does. The
+/// CPU only waits when every buffer in the ring is still in flight.
+///
+/// Swapchain frames need [`Device::set_swapchain_capture`]; the recorder
+/// turns it on while it exists.
+pub struct FrameRecorder<'a> {
+    device: &'a Device,
+    output: Output,
+    fps: u32,
+    timing: FrameTiming,
+    ring_size: usize,
+    in_flight: VecDeque<InFlight>,
+    free: Vec<(*mut gpu::SDL_GPUTransferBuffer, u32)>,
+    /// When the first frame was captured; RealTime timing counts from it.
+    started: Option<Instant>,
+    frames_captured: u64,
+    frames_written: u64,
+    /// RealTime timing: the last frame written, repeated to fill gaps.
+    last_frame: Option<Image>,
+    swapchain_capture_was_on: bool,
+}
+
+impl<'a> FrameRecorder<'a> {
+    /// Stream frames to a YUV 4:2:0 `.y4m` file at `fps`.
+    pub fn y4m(device: &'a Device, path: impl AsRef<Path>, fps: u32) -> Result<Self, &'static str> {
+        let file = File::create(path).map_err(|_| "failed to create y4m file")?;
+        Ok(Self::new(device, Output::Y4m { writer: BufWriter::new(file), size: None }, fps))
+    }
+
+    /// Write frames as `frame_000000.png`, `frame_000001.png`, ... into
+    /// `directory`, which is created if missing.
+    pub fn png_sequence(device: &'a Device, directory: impl AsRef<Path>, fps: u32) -> Result<Self, &'static str> {
+        let directory = directory.as_ref().to_path_buf();
+        std::fs::create_dir_all(&directory).map_err(|_| "failed to create frame directory")?;
+        Ok(Self::new(device, Output::PngSequence { directory }, fps))
+    }
+
+    fn new(device: &'a Device, output: Output, fps: u32) -> Self {
+        let swapchain_capture_was_on = device.swapchain_capture();
+        device.set_swapchain_capture(true);
+        Self {
+            device,
+            output,
+            fps: fps.max(1),
+            timing: FrameTiming::Fixed,
+            ring_size: 3,
+            in_flight: VecDeque::new(),
+            free: Vec::new(),
+            started: None,
+            frames_captured: 0,
+            frames_written: 0,
+            last_frame: None,
+            swapchain_capture_was_on,
+        }
+    }
+
+    pub fn set_timing(&mut self, timing: FrameTiming) {
+        self.timing = timing;
+    }
+
+    /// How many frames may be in flight before [`capture`](Self::capture)
+    /// waits for the oldest. Defaults to 3.
+    pub fn set_ring_size(&mut self, frames: usize) {
+        self.ring_size = frames.max(1);
+    }
+
+    /// Duration of one video frame.
+    pub fn frame_delta(&self) -> Duration {
+        Duration::from_secs(1) / self.fps
+    }
+
+    /// Simulation time of the next frame under [`FrameTiming::Fixed`]:
+    /// frames captured so far times [`frame_delta`](Self::frame_delta).
+    pub fn simulated_time(&self) -> Duration {
+        Duration::from_secs_f64(self.frames_captured as f64 / self.fps as f64)
+    }
+
+    pub fn frames_captured(&self) -> u64 {
+        self.frames_captured
+    }
+
+    /// Video frames written so far. Under [`FrameTiming::RealTime`] this
+    /// counts repeated frames too.
+    pub fn frames_written(&self) -> u64 {
+        self.frames_written
+    }
+
+    /// Record a download of the device window's swapchain texture into
+    /// `cmd`, after the frame's passes. Also writes the frames that have
+    /// completed since the last call.
+    pub fn capture(&mut self, cmd: &CommandBuffer) -> Result<(), &'static str> {
+        self.capture_texture(cmd, Texture::SWAPCHAIN)
+    }
+
+    /// Like [`capture`](Self::capture), for any texture in an 8-bit,
+    /// `R10G10B10A2` or `R16G16B16A16_FLOAT` format, e.g. a
+    /// [`Canvas`](crate::canvas::Canvas) when rendering headless.
+    pub fn capture_texture(&mut self, cmd: &CommandBuffer, texture: Texture) -> Result<(), &'static str> {
+        self.collect()?;
+        while self.in_flight.len() >= self.ring_size {
+            let oldest = self.in_flight.pop_front().unwrap();
+            self.write_in_flight(oldest, true)?;
+        }
+
+        let download = cmd.texture_download(texture)?;
+        let (transfer, capacity) = match self.free.iter().position(|&(_, capacity)| capacity >= download.size) {
+            Some(i) => self.free.swap_remove(i),
+            None => (self.device.create_download_transfer_buffer(download.size)?, download.size),
+        };
+        let fence = match cmd.record_texture_download(texture, transfer) {
+            Ok(fence) => fence,
+            Err(e) => {
+                self.free.push((transfer, capacity));
+                return Err(e);
+            }
+        };
+        self.in_flight.push_back(InFlight {
+            transfer,
+            capacity,
+            download,
+            fence,
+            captured_at: self.started.get_or_insert_with(Instant::now).elapsed(),
+        });
+        self.frames_captured += 1;
+        Ok(())
+    }
+
+    /// Write the frames whose command buffers have completed, oldest first,
+    /// without waiting. Returns how many captured frames were written.
+    pub fn collect(&mut self) -> Result<usize, &'static str> {
+        let mut collected = 0;
+        while let Some(front) = self.in_flight.front() {
+            let ready = front.fence.get().is_some_and(|fence| fence.is_signaled());
+            // Nobody else holds the waiter: the command buffer was dropped
+            // without submitting, so the frame will never arrive.
+            let abandoned = front.fence.get().is_none() && Rc::strong_count(&front.fence) == 1;
+            if !ready && !abandoned {
+                break;
+            }
+            let frame = self.in_flight.pop_front().unwrap();
+            if self.write_in_flight(frame, false)? {
+                collected += 1;
+            }
+        }
+        Ok(collected)
+    }
+
+    /// Wait for every frame still in flight, write it and close the output.
+    /// Returns the number of video frames written.
+    pub fn finish(mut self) -> Result<u64, &'static str> {
+        while let Some(frame) = self.in_flight.pop_front() {
+            self.write_in_flight(frame, true)?;
+        }
+        if let Output::Y4m { writer, .. } = &mut self.output {
+            writer.flush().map_err(|_| "failed to write y4m file")?;
+        }
+        Ok(self.frames_written)
+    }
+
+    /// Read back a frame and write it. Returns false if the frame was
+    /// dropped because its command buffer never got submitted.
+    fn write_in_flight(&mut self, frame: InFlight, wait: bool) -> Result<bool, &'static str> {
+        let InFlight { transfer, capacity, download, fence, captured_at } = frame;
+        let Some(fence) = fence.get() else {
+            if Rc::strong_count(&fence) > 1 {
+                // Still recording; the transfer buffer can't be reused yet.
+                self.device.release_transfer_buffer(transfer);
+                return Err("a captured frame's command buffer was not submitted yet");
+            }
+            self.free.push((transfer, capacity));
+            return Ok(false);
+        };
+        self.free.push((transfer, capacity));
+        if wait {
+            fence.wait()?;
+        }
+        let data = self.device.read_transfer_buffer(transfer, download.size)?;
+        let image = Image::from_swapchain_data(download.format, download.width, download.height, &data)?;
+
+        match self.timing {
+            FrameTiming::Fixed => self.write_frame(&image)?,
+            FrameTiming::RealTime => {
+                let slot = real_time_slot(captured_at, self.fps);
+                let (repeats, write) = real_time_fill(self.frames_written, slot, self.last_frame.is_some());
+                if let Some(last) = self.last_frame.take() {
+                    for _ in 0..repeats {
+                        self.write_frame(&last)?;
+                    }
+                }
+                if write {
+                    self.write_frame(&image)?;
+                }
+                self.last_frame = Some(image);
+            }
+        }
+        Ok(true)
+    }
+
+    fn write_frame(&mut self, image: &Image) -> Result<(), &'static str> {
+        match &mut self.output {
+            Output::Y4m { writer, size } => {
+                match size {
+                    None => {
+                        writeln!(writer, "YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C420jpeg XCOLORRANGE=FULL", image.width, image.height, self.fps)
+                            .map_err(|_| "failed to write y4m file")?;
+                        *size = Some((image.width, image.height));
+                    }
+                    Some(size) if *size != (image.width, image.height) => {
+                        return Err("frame size changed while recording y4m");
+                    }
+                    Some(_) => {}
+                }
+                writer.write_all(b"FRAME\n").map_err(|_| "failed to write y4m file")?;
+                writer.write_all(&rgba_to_yuv420(image)).map_err(|_| "failed to write y4m file")?;
+            }
+            Output::PngSequence { directory } => {
+                image.save_png(directory.join(format!("frame_{:06}.png", self.frames_written)))?;
+            }
+        }
+        self.frames_written += 1;
+        Ok(())
+    }
+}
+
+impl Drop for FrameRecorder<'_> {
+    fn drop(&mut self) {
+        // Frames still in flight are not written. SDL defers releasing their
+        // transfer buffers until the GPU is done with them.
+        for frame in self.in_flight.drain(..) {
+            self.device.release_transfer_buffer(frame.transfer);
+        }
+        for (transfer, _) in self.free.drain(..) {
+            self.device.release_transfer_buffer(transfer);
+        }
+        if !self.swapchain_capture_was_on {
+            self.device.set_swapchain_capture(false);
+        }
+    }
+}
+
+/// The video frame a capture `captured_at` after the first one belongs to.
+fn real_time_slot(captured_at: Duration, fps: u32) -> u64 {
+    (captured_at.as_secs_f64() * fps as f64) as u64
+}
+
+/// How often to repeat the last frame before a capture for `slot`, with
+/// `written` video frames out so far, and whether to write the capture
+/// itself. Captures landing in an already written slot are dropped.
+fn real_time_fill(written: u64, slot: u64, has_last: bool) -> (u64, bool) {
+    let repeats = if has_last { slot.saturating_sub(written) } else { 0 };
+    (repeats, written + repeats <= slot)
+}
+
+/// Planar full-range BT.601 Y, U, V with 2x2 subsampled chroma, as
+/// `C420jpeg` y4m expects.
+fn rgba_to_yuv420(image: &Image) -> Vec<u8> {
+    let (width, height) = (image.width as usize, image.height as usize);
+    let (chroma_width, chroma_height) = (width.div_ceil(2), height.div_ceil(2));
+    let mut out = vec![0u8; width * height + 2 * chroma_width * chroma_height];
+    let (luma, chroma) = out.split_at_mut(width * height);
+    let (u_plane, v_plane) = chroma.split_at_mut(chroma_width * chroma_height);
+
+    let rgb = |x: usize, y: usize| {
+        let i = (y * width + x) * 4;
+        let p = &image.pixels[i..i + 3];
+        (p[0] as f32, p[1] as f32, p[2] as f32)
+    };
+    for y in 0..height {
+        for x in 0..width {
+            let (r, g, b) = rgb(x, y);
+            luma[y * width + x] = (0.299 * r + 0.587 * g + 0.114 * b).round() as u8;
+        }
+    }
+    for cy in 0..chroma_height {
+        for cx in 0..chroma_width {
+            let (mut r, mut g, mut b, mut n) = (0.0, 0.0, 0.0, 0.0);
+            for y in cy * 2..(cy * 2 + 2).min(height) {
+                for x in cx * 2..(cx * 2 + 2).min(width) {
+                    let (pr, pg, pb) = rgb(x, y);
+                    r += pr;
+                    g += pg;
+                    b += pb;
+                    n += 1.0;
+                }
+            }
+            let (r, g, b) = (r / n, g / n, b / n);
+            let i = cy * chroma_width + cx;
+            u_plane[i] = (128.0 - 0.168_736 * r - 0.331_264 * g + 0.5 * b).round().clamp(0.0, 255.0) as u8;
+            v_plane[i] = (128.0 + 0.5 * r - 0.418_688 * g - 0.081_312 * b).round().clamp(0.0, 255.0) as u8;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn real_time_slots_count_from_the_first_capture() {
+        assert_eq!(real_time_slot(Duration::ZERO, 30), 0);
+        assert_eq!(real_time_slot(Duration::from_millis(20), 30), 0);
+        assert_eq!(real_time_slot(Duration::from_millis(40), 30), 1);
+        assert_eq!(real_time_slot(Duration::from_millis(1000), 30), 30);
+    }
+
+    #[test]
+    fn real_time_gaps_repeat_the_last_frame() {
+        // The first capture is written as is.
+        assert_eq!(real_time_fill(0, 0, false), (0, true));
+        // The next slot follows directly.
+        assert_eq!(real_time_fill(1, 1, true), (0, true));
+        // Slots 2 and 3 were missed: the last frame fills them.
+        assert_eq!(real_time_fill(2, 4, true), (2, true));
+    }
+
+    #[test]
+    fn real_time_captures_in_a_written_slot_are_dropped() {
+        assert_eq!(real_time_fill(1, 0, true), (0, false));
+        assert_eq!(real_time_fill(5, 4, true), (0, false));
+    }
+}
@@ -0,0 +1,303 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::device::{
+    ComputePipeline, ComputePipelineCreateInfo, Device, GraphicsPipeline, GraphicsPipelineCreateInfo,
+    SDL_GPUShaderFormat, SDL_GPUShaderStage, Shader, ShaderCreateInfo,
+};
+use crate::tools::{compile_shader, recorded_inputs, ShaderTarget};
+
+/// Outcome of recompiling one watched source file.
+#[derive(Debug)]
+pub enum ReloadEvent {
+    /// The source compiled and every object built from it was recreated.
+    Reloaded { path: PathBuf },
+    /// Compiling the source or recreating an object failed. The previous
+    /// shader or pipeline is still in place.
+    Failed { path: PathBuf, message: String },
+}
+
+struct WatchedShader {
+    handle: Shader,
+    source: PathBuf,
+    variant: Option<u32>,
+    entrypoint: String,
+    stage: SDL_GPUShaderStage,
+    num_samplers: u32,
+    num_storage_textures: u32,
+    num_storage_buffers: u32,
+    num_uniform_buffers: u32,
+}
+
+struct WatchedGraphicsPipeline {
+    handle: GraphicsPipeline,
+    info: GraphicsPipelineCreateInfo,
+}
+
+struct WatchedComputePipeline {
+    handle: ComputePipeline,
+    source: PathBuf,
+    variant: Option<u32>,
+    entrypoint: String,
+    num_samplers: u32,
+    num_readonly_storage_textures: u32,
+    num_readonly_storage_buffers: u32,
+    num_readwrite_storage_textures: u32,
+    num_readwrite_storage_buffers: u32,
+    num_uniform_buffers: u32,
+    threadcount: (u32, u32, u32),
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).ok()?.modified().ok()
+}
+
+fn target_for_formats(formats: SDL_GPUShaderFormat) -> Option<(ShaderTarget, SDL_GPUShaderFormat)> {
+    if formats.0 & SDL_GPUShaderFormat::SPIRV.0 != 0 {
+        Some((ShaderTarget::Spirv, SDL_GPUShaderFormat::SPIRV))
+    } else if formats.0 & SDL_GPUShaderFormat::DXIL.0 != 0 {
+        Some((ShaderTarget::Dxil, SDL_GPUShaderFormat::DXIL))
+    } else if formats.0 & SDL_GPUShaderFormat::MSL.0 != 0 {
+        Some((ShaderTarget::Msl, SDL_GPUShaderFormat::MSL))
+    } else {
+        None
+    }
+}
+
+/// Opt-in runtime shader reloading.
+///
+/// Watches the sources in the `shader_dir` passed to
+/// [`prepare_shaders`](crate::tools::prepare_shaders), along with every file
+/// they include. Each call to [`poll`](Self::poll) recompiles sources whose
+/// inputs changed on disk and recreates
+/// the registered `Shader`, `GraphicsPipeline` and `ComputePipeline` objects
+/// in place, so existing handles keep working.
+///
+/// Shaders used by a watched graphics pipeline must stay alive (not be
+/// destroyed after pipeline creation) so the pipeline can be rebuilt.
+pub struct ShaderReloader {
+    shader_dir: PathBuf,
+    out_dir: PathBuf,
+    target: ShaderTarget,
+    format: SDL_GPUShaderFormat,
+    shaders: Vec<WatchedShader>,
+    graphics_pipelines: Vec<WatchedGraphicsPipeline>,
+    compute_pipelines: Vec<WatchedComputePipeline>,
+    mtimes: HashMap<PathBuf, Option<SystemTime>>,
+    /// For every watched file, the sources that must be recompiled when it changes.
+    dependents: HashMap<PathBuf, BTreeSet<PathBuf>>,
+}
+
+impl ShaderReloader {
+    /// Create a reloader compiling from `shader_dir` into `shader_intermediary_dir`
+    /// for the shader format the device consumes.
+    pub fn new(device: &Device, shader_dir: &Path, shader_intermediary_dir: &Path) -> Result<Self, &'static str> {
+        let (target, format) = target_for_formats(device.get_shader_formats())
+            .ok_or("device supports no shader format the reloader can produce")?;
+        fs::create_dir_all(shader_intermediary_dir)
+            .map_err(|_| "failed to create shader intermediary dir")?;
+        Ok(Self {
+            shader_dir: shader_dir.to_path_buf(),
+            out_dir: shader_intermediary_dir.to_path_buf(),
+            target,
+            format,
+            shaders: Vec::new(),
+            graphics_pipelines: Vec::new(),
+            compute_pipelines: Vec::new(),
+            mtimes: HashMap::new(),
+            dependents: HashMap::new(),
+        })
+    }
+
+    fn track(&mut self, source: &Path, variant: Option<u32>) -> PathBuf {
+        let path = self.shader_dir.join(source);
+        let inputs = recorded_inputs(&path, &self.out_dir, self.target, variant);
+        self.track_inputs(&path, &inputs);
+        path
+    }
+
+    fn track_inputs(&mut self, source: &Path, inputs: &[PathBuf]) {
+        for input in std::iter::once(source).chain(inputs.iter().map(PathBuf::as_path)) {
+            self.mtimes.entry(input.to_path_buf()).or_insert_with(|| modified_time(input));
+            self.dependents.entry(input.to_path_buf()).or_default().insert(source.to_path_buf());
+        }
+    }
+
+    /// Watch `shader`, built from `source` (relative to `shader_dir`), or
+    /// from its permutation with feature mask `variant`.
+    /// `info.code` is ignored; the other fields are reused on reload.
+    pub fn watch_shader(&mut self, shader: Shader, source: impl AsRef<Path>, variant: Option<u32>, info: &ShaderCreateInfo) {
+        let source = self.track(source.as_ref(), variant);
+        self.shaders.push(WatchedShader {
+            handle: shader,
+            source,
+            variant,
+            entrypoint: info.entrypoint.to_string(),
+            stage: info.stage,
+            num_samplers: info.num_samplers,
+            num_storage_textures: info.num_storage_textures,
+            num_storage_buffers: info.num_storage_buffers,
+            num_uniform_buffers: info.num_uniform_buffers,
+        });
+    }
+
+    /// Watch `pipeline`, created from `info`. It is rebuilt from `info`
+    /// whenever one of its watched shaders reloads.
+    pub fn watch_graphics_pipeline(&mut self, pipeline: GraphicsPipeline, info: GraphicsPipelineCreateInfo) {
+        self.graphics_pipelines.push(WatchedGraphicsPipeline { handle: pipeline, info });
+    }
+
+    /// Watch `pipeline`, built from `source` (relative to `shader_dir`), or
+    /// from its permutation with feature mask `variant`.
+    /// `info.code` is ignored; the other fields are reused on reload.
+    pub fn watch_compute_pipeline(
+        &mut self,
+        pipeline: ComputePipeline,
+        source: impl AsRef<Path>,
+        variant: Option<u32>,
+        info: &ComputePipelineCreateInfo,
+    ) {
+        let source = self.track(source.as_ref(), variant);
+        self.compute_pipelines.push(WatchedComputePipeline {
+            handle: pipeline,
+            source,
+            variant,
+            entrypoint: info.entrypoint.to_string(),
+            num_samplers: info.num_samplers,
+            num_readonly_storage_textures: info.num_readonly_storage_textures,
+            num_readonly_storage_buffers: info.num_readonly_storage_buffers,
+            num_readwrite_storage_textures: info.num_readwrite_storage_textures,
+            num_readwrite_storage_buffers: info.num_readwrite_storage_buffers,
+            num_uniform_buffers: info.num_uniform_buffers,
+            threadcount: (info.threadcount_x, info.threadcount_y, info.threadcount_z),
+        });
+    }
+
+    /// Stop watching a shader. Call this before destroying it.
+    pub fn unwatch_shader(&mut self, shader: Shader) {
+        self.shaders.retain(|w| w.handle != shader);
+    }
+
+    /// Stop watching a graphics pipeline. Call this before destroying it.
+    pub fn unwatch_graphics_pipeline(&mut self, pipeline: GraphicsPipeline) {
+        self.graphics_pipelines.retain(|w| w.handle != pipeline);
+    }
+
+    /// Stop watching a compute pipeline. Call this before destroying it.
+    pub fn unwatch_compute_pipeline(&mut self, pipeline: ComputePipeline) {
+        self.compute_pipelines.retain(|w| w.handle != pipeline);
+    }
+
+    /// Recompile changed sources and recreate the objects built from them.
+    ///
+    /// Never panics on compile errors; they are returned as
+    /// [`ReloadEvent::Failed`] and the previous objects stay in use.
+    pub fn poll(&mut self, device: &Device) -> Vec<ReloadEvent> {
+        let mut changed = BTreeSet::new();
+        for (path, last) in self.mtimes.iter_mut() {
+            let now = modified_time(path);
+            if now != *last {
+                *last = now;
+                if let Some(sources) = self.dependents.get(path) {
+                    changed.extend(sources.iter().cloned());
+                }
+            }
+        }
+
+        let mut events = Vec::new();
+        let mut reloaded_shaders = Vec::new();
+        for path in changed {
+            match self.reload_source(device, &path, &mut reloaded_shaders) {
+                Ok(inputs) => {
+                    self.track_inputs(&path, &inputs);
+                    events.push(ReloadEvent::Reloaded { path });
+                }
+                Err(message) => events.push(ReloadEvent::Failed { path, message }),
+            }
+        }
+
+        for watched in &self.graphics_pipelines {
+            let info = &watched.info;
+            if !reloaded_shaders.contains(&info.vertex_shader) && !reloaded_shaders.contains(&info.fragment_shader) {
+                continue;
+            }
+            if let Err(e) = device.recreate_graphics_pipeline(watched.handle, info) {
+                let path = self.shaders.iter()
+                    .find(|w| reloaded_shaders.contains(&w.handle)
+                        && (w.handle == info.vertex_shader || w.handle == info.fragment_shader))
+                    .map(|w| w.source.clone())
+                    .unwrap_or_else(|| self.shader_dir.clone());
+                events.push(ReloadEvent::Failed {
+                    path,
+                    message: format!("graphics pipeline {:?}: {e}", watched.handle),
+                });
+            }
+        }
+
+        events
+    }
+
+    /// Recompile every watched variant of `path` and recreate everything
+    /// built from them, returning the transitive inputs of the variants.
+    fn reload_source(&self, device: &Device, path: &Path, reloaded_shaders: &mut Vec<Shader>) -> Result<Vec<PathBuf>, String> {
+        let variants: BTreeSet<Option<u32>> = self.shaders.iter()
+            .filter(|w| w.source == path)
+            .map(|w| w.variant)
+            .chain(self.compute_pipelines.iter().filter(|w| w.source == path).map(|w| w.variant))
+            .collect();
+        let mut inputs = BTreeSet::new();
+        for variant in variants {
+            inputs.extend(self.reload_variant(device, path, variant, reloaded_shaders)?);
+        }
+        Ok(inputs.into_iter().collect())
+    }
+
+    fn reload_variant(
+        &self,
+        device: &Device,
+        path: &Path,
+        variant: Option<u32>,
+        reloaded_shaders: &mut Vec<Shader>,
+    ) -> Result<Vec<PathBuf>, String> {
+        let compiled = compile_shader(path, &self.out_dir, self.target, variant).map_err(|e| e.to_string())?;
+        let output = &compiled.output;
+        let code = fs::read(output).map_err(|e| format!("failed to read {}: {e}", output.display()))?;
+
+        for watched in self.shaders.iter().filter(|w| w.source == path && w.variant == variant) {
+            device.recreate_shader(watched.handle, &ShaderCreateInfo {
+                code: &code,
+                entrypoint: &watched.entrypoint,
+                format: self.format,
+                stage: watched.stage,
+                num_samplers: watched.num_samplers,
+                num_storage_textures: watched.num_storage_textures,
+                num_storage_buffers: watched.num_storage_buffers,
+                num_uniform_buffers: watched.num_uniform_buffers,
+                name: None,
+            }).map_err(|e| format!("shader {:?}: {e}", watched.handle))?;
+            reloaded_shaders.push(watched.handle);
+        }
+
+        for watched in self.compute_pipelines.iter().filter(|w| w.source == path && w.variant == variant) {
+            device.recreate_compute_pipeline(watched.handle, &ComputePipelineCreateInfo {
+                code: &code,
+                entrypoint: &watched.entrypoint,
+                format: self.format,
+                num_samplers: watched.num_samplers,
+                num_readonly_storage_textures: watched.num_readonly_storage_textures,
+                num_readonly_storage_buffers: watched.num_readonly_storage_buffers,
+                num_readwrite_storage_textures: watched.num_readwrite_storage_textures,
+                num_readwrite_storage_buffers: watched.num_readwrite_storage_buffers,
+                num_uniform_buffers: watched.num_uniform_buffers,
+                threadcount_x: watched.threadcount.0,
+                threadcount_y: watched.threadcount.1,
+                threadcount_z: watched.threadcount.2,
+                name: None,
+            }).map_err(|e| format!("compute pipeline {:?}: {e}", watched.handle))?;
+        }
+
+        Ok(compiled.inputs)
+    }
+}
@@ -0,0 +1,180 @@
+use std::path::Path;
+
+use sdl3_sys as sys;
+use sys::gpu::SDL_GPUTextureFormat;
+
+/// An 8-bit RGBA image in CPU memory, rows top to bottom.
+#[derive(Clone)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    /// `width * height * 4` bytes, R, G, B, A per pixel.
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self { width, height, pixels: vec![0; width as usize * height as usize * 4] }
+    }
+
+    /// Convert tightly packed texel data downloaded from a texture of
+    /// `format` to RGBA. 8-bit RGBA and BGRA formats are supported.
+    pub fn from_texture_data(format: SDL_GPUTextureFormat, width: u32, height: u32, data: &[u8]) -> Result<Self, &'static str> {
+        let texels = width as usize * height as usize;
+        if data.len() < texels * 4 {
+            return Err("texture data is smaller than the image");
+        }
+        let data = &data[..texels * 4];
+        let pixels = match format {
+            SDL_GPUTextureFormat::R8G8B8A8_UNORM | SDL_GPUTextureFormat::R8G8B8A8_UNORM_SRGB => data.to_vec(),
+            SDL_GPUTextureFormat::B8G8R8A8_UNORM | SDL_GPUTextureFormat::B8G8R8A8_UNORM_SRGB => data
+                .chunks_exact(4)
+                .flat_map(|bgra| [bgra[2], bgra[1], bgra[0], bgra[3]])
+                .collect(),
+            _ => return Err("unsupported texture format for readback"),
+        };
+        Ok(Self { width, height, pixels })
+    }
+
+    /// Convert tightly packed data downloaded from a swapchain texture of
+    /// `format` to opaque 8-bit sRGB, following the swapchain composition
+    /// that format implies: 8-bit formats are already sRGB encoded,
+    /// `R16G16B16A16_FLOAT` holds extended linear sRGB and
+    /// `R10G10B10A2_UNORM` holds BT.2020 PQ. HDR values brighter than SDR
+    /// white are clipped.
+    pub fn from_swapchain_data(format: SDL_GPUTextureFormat, width: u32, height: u32, data: &[u8]) -> Result<Self, &'static str> {
+        let texels = width as usize * height as usize;
+        let texel_size = match format {
+            SDL_GPUTextureFormat::R16G16B16A16_FLOAT => 8,
+            _ => 4,
+        };
+        if data.len() < texels * texel_size {
+            return Err("texture data is smaller than the image");
+        }
+        let data = &data[..texels * texel_size];
+        let mut image = match format {
+            SDL_GPUTextureFormat::R16G16B16A16_FLOAT => {
+                let pixels = data
+                    .chunks_exact(8)
+                    .flat_map(|texel| {
+                        let channel = |i: usize| f16_to_f32(u16::from_le_bytes([texel[i * 2], texel[i * 2 + 1]]));
+                        [encode_srgb(channel(0)), encode_srgb(channel(1)), encode_srgb(channel(2)), 255]
+                    })
+                    .collect();
+                Self { width, height, pixels }
+            }
+            SDL_GPUTextureFormat::R10G10B10A2_UNORM => {
+                let pixels = data
+                    .chunks_exact(4)
+                    .flat_map(|texel| {
+                        let packed = u32::from_le_bytes([texel[0], texel[1], texel[2], texel[3]]);
+                        let channel = |shift: u32| pq_to_sdr_linear(((packed >> shift) & 0x3ff) as f32 / 1023.0);
+                        let [r, g, b] = bt2020_to_bt709([channel(0), channel(10), channel(20)]);
+                        [encode_srgb(r), encode_srgb(g), encode_srgb(b), 255]
+                    })
+                    .collect();
+                Self { width, height, pixels }
+            }
+            _ => Self::from_texture_data(format, width, height, data)?,
+        };
+        for alpha in image.pixels.iter_mut().skip(3).step_by(4) {
+            *alpha = 255;
+        }
+        Ok(image)
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        [self.pixels[i], self.pixels[i + 1], self.pixels[i + 2], self.pixels[i + 3]]
+    }
+
+    /// Write the image to `path`: BMP if the extension is `.bmp`, PNG
+    /// otherwise.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), &'static str> {
+        let path = path.as_ref();
+        let bmp = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("bmp"));
+        self.write(path, bmp)
+    }
+
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<(), &'static str> {
+        self.write(path.as_ref(), false)
+    }
+
+    pub fn save_bmp(&self, path: impl AsRef<Path>) -> Result<(), &'static str> {
+        self.write(path.as_ref(), true)
+    }
+
+    fn write(&self, path: &Path, bmp: bool) -> Result<(), &'static str> {
+        let path_c = std::ffi::CString::new(path.to_string_lossy().as_bytes())
+            .map_err(|_| "path contains interior nul byte")?;
+        unsafe {
+            // SDL only reads the pixels, the surface doesn't outlive this call.
+            let surface = sys::surface::SDL_CreateSurfaceFrom(
+                self.width as i32,
+                self.height as i32,
+                sys::pixels::SDL_PixelFormat::RGBA32,
+                self.pixels.as_ptr() as *mut std::ffi::c_void,
+                self.width as i32 * 4,
+            );
+            if surface.is_null() {
+                return Err("SDL_CreateSurfaceFrom failed");
+            }
+            let ok = if bmp {
+                sys::surface::SDL_SaveBMP(surface, path_c.as_ptr())
+            } else {
+                sys::surface::SDL_SavePNG(surface, path_c.as_ptr())
+            };
+            sys::surface::SDL_DestroySurface(surface);
+            if !ok {
+                return Err(if bmp { "SDL_SaveBMP failed" } else { "SDL_SavePNG failed" });
+            }
+        }
+        Ok(())
+    }
+}
+
+fn f16_to_f32(half: u16) -> f32 {
+    let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((half >> 10) & 0x1f) as i32;
+    let mantissa = (half & 0x3ff) as f32;
+    match exponent {
+        0 => sign * mantissa * (-24f32).exp2(),
+        0x1f if mantissa == 0.0 => sign * f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * ((exponent - 15) as f32).exp2(),
+    }
+}
+
+/// Linear `[0, 1]` to 8-bit sRGB. Out of range values are clipped.
+fn encode_srgb(linear: f32) -> u8 {
+    let linear = if linear.is_nan() { 0.0 } else { linear.clamp(0.0, 1.0) };
+    let encoded = if linear <= 0.003_130_8 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    };
+    (encoded * 255.0 + 0.5) as u8
+}
+
+/// SDR reference white in nits, per ITU-R BT.2408.
+const SDR_WHITE_NITS: f32 = 203.0;
+
+/// Decode an ST 2084 (PQ) value to linear light relative to SDR white.
+fn pq_to_sdr_linear(encoded: f32) -> f32 {
+    const M1: f32 = 2610.0 / 16384.0;
+    const M2: f32 = 2523.0 / 4096.0 * 128.0;
+    const C1: f32 = 3424.0 / 4096.0;
+    const C2: f32 = 2413.0 / 4096.0 * 32.0;
+    const C3: f32 = 2392.0 / 4096.0 * 32.0;
+    let p = encoded.powf(1.0 / M2);
+    let nits = 10000.0 * ((p - C1).max(0.0) / (C2 - C3 * p)).powf(1.0 / M1);
+    nits / SDR_WHITE_NITS
+}
+
+fn bt2020_to_bt709([r, g, b]: [f32; 3]) -> [f32; 3] {
+    [
+        1.6605 * r - 0.5876 * g - 0.0728 * b,
+        -0.1246 * r + 1.1329 * g - 0.0083 * b,
+        -0.0182 * r - 0.1006 * g + 1.1187 * b,
+    ]
+}
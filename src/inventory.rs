@@ -0,0 +1,117 @@
+use std::fmt;
+use std::panic::Location;
+
+use sdl3_sys::gpu;
+
+use crate::device::SDL_GPUTextureType;
+
+/// Kind of a resource held by a [`Device`](crate::device::Device).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ResourceKind {
+    Texture,
+    Buffer,
+    Sampler,
+    Shader,
+    GraphicsPipeline,
+    ComputePipeline,
+}
+
+impl ResourceKind {
+    fn label(self) -> &'static str {
+        match self {
+            ResourceKind::Texture => "texture",
+            ResourceKind::Buffer => "buffer",
+            ResourceKind::Sampler => "sampler",
+            ResourceKind::Shader => "shader",
+            ResourceKind::GraphicsPipeline => "graphics pipeline",
+            ResourceKind::ComputePipeline => "compute pipeline",
+        }
+    }
+}
+
+/// A resource alive in a device, as listed by
+/// [`Device::resource_inventory`](crate::device::Device::resource_inventory).
+#[derive(Clone, Debug)]
+pub struct LiveResource {
+    pub kind: ResourceKind,
+    /// Index of the handle, e.g. `Texture(handle)`.
+    pub handle: i32,
+    pub name: Option<String>,
+    /// Width and height, for textures.
+    pub dimensions: Option<(u32, u32)>,
+    /// Approximate GPU memory size in bytes, for textures and buffers.
+    pub bytes: u64,
+    /// Where the resource was created.
+    pub created_at: &'static Location<'static>,
+    /// Owned by the device itself (texture pool, swapchain capture) rather
+    /// than by a handle the caller destroys. Never reported as a leak.
+    pub internal: bool,
+}
+
+/// Snapshot of the resources alive in a device.
+#[derive(Clone, Debug, Default)]
+pub struct ResourceInventory {
+    /// Sorted by kind, then handle.
+    pub resources: Vec<LiveResource>,
+}
+
+impl ResourceInventory {
+    pub fn is_empty(&self) -> bool {
+        self.resources.is_empty()
+    }
+
+    /// Number of live resources of `kind`.
+    pub fn count(&self, kind: ResourceKind) -> usize {
+        self.resources.iter().filter(|r| r.kind == kind).count()
+    }
+
+    /// Approximate GPU memory size of every listed resource, in bytes.
+    pub fn total_bytes(&self) -> u64 {
+        self.resources.iter().map(|r| r.bytes).sum()
+    }
+
+    /// The resources the caller still holds handles to, i.e. those that
+    /// leak if the device is dropped now.
+    pub fn leaks(&self) -> ResourceInventory {
+        ResourceInventory {
+            resources: self.resources.iter().filter(|r| !r.internal).cloned().collect(),
+        }
+    }
+}
+
+/// One line per resource: kind, handle, name, size and creation site.
+impl fmt::Display for ResourceInventory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for r in &self.resources {
+            write!(f, "{} #{}", r.kind.label(), r.handle)?;
+            if let Some(name) = &r.name {
+                write!(f, " \"{name}\"")?;
+            }
+            if let Some((w, h)) = r.dimensions {
+                write!(f, " {w}x{h}")?;
+            }
+            if r.bytes > 0 {
+                write!(f, " {:.1} KiB", r.bytes as f64 / 1024.0)?;
+            }
+            if r.internal {
+                write!(f, " (device-owned)")?;
+            }
+            writeln!(f, ", created at {}", r.created_at)?;
+        }
+        Ok(())
+    }
+}
+
+/// Approximate memory size of a texture, every mip level and layer included.
+pub(crate) fn texture_bytes(info: &gpu::SDL_GPUTextureCreateInfo) -> u64 {
+    let is_3d = info.r#type == SDL_GPUTextureType::_3D;
+    (0..info.num_levels.max(1))
+        .map(|level| {
+            let w = (info.width >> level).max(1);
+            let h = (info.height >> level).max(1);
+            let d = if is_3d { (info.layer_count_or_depth >> level).max(1) } else { info.layer_count_or_depth.max(1) };
+            unsafe { gpu::SDL_CalculateGPUTextureFormatSize(info.format, w, h, d) as u64 }
+        })
+        .sum::<u64>()
+        << info.sample_count.0
+}
@@ -1,11 +1,27 @@
 //#![allow(unused)]
+mod backend;
 pub mod device;
 pub mod event;
+pub mod frame_recorder;
 pub mod slot_map;
 pub mod window;
 pub mod tools;
 pub mod callbacks;
+pub mod canvas;
 pub mod properties;
+pub mod hot_reload;
+pub mod image;
+pub mod inventory;
+pub mod null_backend;
+pub mod pipeline_cache;
+pub mod render_graph;
+pub mod render_target;
+pub mod screenshot;
+pub mod shared_device;
+pub mod texture_pool;
+mod validation;
+#[cfg(feature = "naga")]
+pub mod shader_compiler;
 
 pub use sdl3_sys as sys;
 
@@ -20,6 +36,7 @@ pub fn sdl_init(flags : SDL_InitFlags)
     }
 }
 
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub fn set_hint(name: *const core::ffi::c_char, value: &core::ffi::CStr) -> bool
 {
     unsafe
@@ -0,0 +1,1143 @@
+//! A GPU backend that draws nothing and records every call instead, for
+//! testing rendering code without a GPU.
+//!
+//! Create a device with [`Device::new_null`](crate::device::Device::new_null)
+//! and use it like any other; the returned [`CommandLog`] lists the calls
+//! that reached the backend, with the objects they resolved to.
+//!
+//! Buffers and transfer buffers are backed by memory, so uploads and
+//! downloads through buffers round-trip. Textures have no contents and
+//! download as zeros.
+
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use sdl3_sys as sys;
+use sys::gpu::{
+    self, SDL_GPUBufferUsageFlags, SDL_GPUIndexElementSize, SDL_GPUPresentMode, SDL_GPUPrimitiveType,
+    SDL_GPUSampleCount, SDL_GPUShaderFormat, SDL_GPUShaderStage, SDL_GPUSwapchainComposition, SDL_GPUTextureFormat,
+    SDL_GPUTextureUsageFlags, SDL_GPUTransferBufferUsage,
+};
+use sys::video::SDL_Window;
+
+use crate::backend::Backend;
+use crate::properties::read_string_property;
+
+/// An object created by the null backend. Ids count up from 1 in creation
+/// order and are never reused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ObjectId(pub u64);
+
+impl ObjectId {
+    /// Stands for a null pointer, e.g. an unset resolve texture.
+    pub const NULL: ObjectId = ObjectId(0);
+
+    fn of<T>(ptr: *const T) -> Self {
+        ObjectId(ptr as usize as u64)
+    }
+
+    fn as_ptr<T>(self) -> *mut T {
+        self.0 as usize as *mut T
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ObjectKind {
+    Texture,
+    Shader,
+    GraphicsPipeline,
+    ComputePipeline,
+    Buffer,
+    Sampler,
+    TransferBuffer,
+    CommandBuffer,
+    Fence,
+    CopyPass,
+    RenderPass,
+    ComputePass,
+}
+
+/// A call that reached the null backend. Not `Debug`, as the SDL enums it
+/// holds only are with the `debug-impls` feature of `sdl3-sys`; compare
+/// with `==` or `matches!`.
+#[derive(Clone, PartialEq)]
+pub enum Command {
+    CreateTexture {
+        texture: ObjectId,
+        name: Option<String>,
+        format: SDL_GPUTextureFormat,
+        usage: SDL_GPUTextureUsageFlags,
+        width: u32,
+        height: u32,
+        layer_count_or_depth: u32,
+        num_levels: u32,
+        sample_count: SDL_GPUSampleCount,
+    },
+    ReleaseTexture { texture: ObjectId },
+    CreateShader {
+        shader: ObjectId,
+        name: Option<String>,
+        stage: SDL_GPUShaderStage,
+        entrypoint: String,
+        code_size: usize,
+        num_samplers: u32,
+        num_storage_textures: u32,
+        num_storage_buffers: u32,
+        num_uniform_buffers: u32,
+    },
+    ReleaseShader { shader: ObjectId },
+    CreateGraphicsPipeline {
+        pipeline: ObjectId,
+        name: Option<String>,
+        vertex_shader: ObjectId,
+        fragment_shader: ObjectId,
+        primitive_type: SDL_GPUPrimitiveType,
+        color_target_formats: Vec<SDL_GPUTextureFormat>,
+        depth_stencil_format: Option<SDL_GPUTextureFormat>,
+    },
+    ReleaseGraphicsPipeline { pipeline: ObjectId },
+    CreateComputePipeline { pipeline: ObjectId, name: Option<String>, entrypoint: String, threadcount: [u32; 3] },
+    ReleaseComputePipeline { pipeline: ObjectId },
+    CreateBuffer { buffer: ObjectId, name: Option<String>, usage: SDL_GPUBufferUsageFlags, size: u32 },
+    ReleaseBuffer { buffer: ObjectId },
+    CreateSampler { sampler: ObjectId, name: Option<String> },
+    ReleaseSampler { sampler: ObjectId },
+    CreateTransferBuffer { transfer_buffer: ObjectId, usage: SDL_GPUTransferBufferUsage, size: u32 },
+    ReleaseTransferBuffer { transfer_buffer: ObjectId },
+    SetTextureName { texture: ObjectId, name: String },
+    SetBufferName { buffer: ObjectId, name: String },
+
+    ClaimWindow,
+    ReleaseWindow,
+    SetSwapchainParameters { composition: SDL_GPUSwapchainComposition, present_mode: SDL_GPUPresentMode },
+    SetAllowedFramesInFlight { frames: u32 },
+
+    AcquireCommandBuffer { command_buffer: ObjectId },
+    /// `texture` is [`ObjectId::NULL`] when there was no swapchain texture
+    /// to acquire.
+    AcquireSwapchainTexture { command_buffer: ObjectId, texture: ObjectId, width: u32, height: u32 },
+    Submit { command_buffer: ObjectId, fence: Option<ObjectId> },
+    Cancel { command_buffer: ObjectId },
+    ReleaseFence { fence: ObjectId },
+    Blit { command_buffer: ObjectId, source: ObjectId, destination: ObjectId },
+    InsertDebugLabel { command_buffer: ObjectId, text: String },
+    PushDebugGroup { command_buffer: ObjectId, name: String },
+    PopDebugGroup { command_buffer: ObjectId },
+    PushVertexUniformData { command_buffer: ObjectId, slot_index: u32, data: Vec<u8> },
+    PushFragmentUniformData { command_buffer: ObjectId, slot_index: u32, data: Vec<u8> },
+    PushComputeUniformData { command_buffer: ObjectId, slot_index: u32, data: Vec<u8> },
+
+    BeginCopyPass { command_buffer: ObjectId, pass: ObjectId },
+    /// `data` is what the transfer buffer held at the time of the call.
+    UploadToBuffer { transfer_buffer: ObjectId, buffer: ObjectId, offset: u32, data: Vec<u8> },
+    /// `data` is what the transfer buffer held at the time of the call.
+    UploadToTexture { transfer_buffer: ObjectId, texture: ObjectId, x: u32, y: u32, w: u32, h: u32, data: Vec<u8> },
+    DownloadFromBuffer { buffer: ObjectId, offset: u32, size: u32, transfer_buffer: ObjectId },
+    DownloadFromTexture { texture: ObjectId, x: u32, y: u32, w: u32, h: u32, transfer_buffer: ObjectId },
+    CopyBufferToBuffer { source: ObjectId, source_offset: u32, destination: ObjectId, destination_offset: u32, size: u32 },
+    EndCopyPass { pass: ObjectId },
+
+    BeginRenderPass {
+        command_buffer: ObjectId,
+        pass: ObjectId,
+        color_targets: Vec<ObjectId>,
+        depth_stencil_target: Option<ObjectId>,
+    },
+    BindGraphicsPipeline { pipeline: ObjectId },
+    /// Buffer and offset per slot, starting at `first_slot`.
+    BindVertexBuffers { first_slot: u32, buffers: Vec<(ObjectId, u32)> },
+    BindIndexBuffer { buffer: ObjectId, offset: u32, index_element_size: SDL_GPUIndexElementSize },
+    /// Texture and sampler per slot, starting at `first_slot`.
+    BindFragmentSamplers { first_slot: u32, bindings: Vec<(ObjectId, ObjectId)> },
+    BindFragmentStorageTextures { first_slot: u32, textures: Vec<ObjectId> },
+    BindFragmentStorageBuffers { first_slot: u32, buffers: Vec<ObjectId> },
+    BindVertexStorageBuffers { first_slot: u32, buffers: Vec<ObjectId> },
+    BindVertexSamplers { first_slot: u32, bindings: Vec<(ObjectId, ObjectId)> },
+    BindVertexStorageTextures { first_slot: u32, textures: Vec<ObjectId> },
+    Draw { num_vertices: u32, num_instances: u32, first_vertex: u32, first_instance: u32 },
+    DrawIndexed { num_indices: u32, num_instances: u32, first_index: u32, vertex_offset: i32, first_instance: u32 },
+    DrawIndirect { buffer: ObjectId, offset: u32, draw_count: u32 },
+    DrawIndexedIndirect { buffer: ObjectId, offset: u32, draw_count: u32 },
+    SetViewport { x: f32, y: f32, w: f32, h: f32, min_depth: f32, max_depth: f32 },
+    SetScissor { x: i32, y: i32, w: i32, h: i32 },
+    SetStencilReference { reference: u8 },
+    /// RGBA.
+    SetBlendConstants { constants: [f32; 4] },
+    EndRenderPass { pass: ObjectId },
+
+    BeginComputePass {
+        command_buffer: ObjectId,
+        pass: ObjectId,
+        storage_textures: Vec<ObjectId>,
+        storage_buffers: Vec<ObjectId>,
+    },
+    BindComputePipeline { pipeline: ObjectId },
+    BindComputeStorageTextures { first_slot: u32, textures: Vec<ObjectId> },
+    BindComputeStorageBuffers { first_slot: u32, buffers: Vec<ObjectId> },
+    BindComputeSamplers { first_slot: u32, bindings: Vec<(ObjectId, ObjectId)> },
+    Dispatch { groupcount_x: u32, groupcount_y: u32, groupcount_z: u32 },
+    DispatchIndirect { buffer: ObjectId, offset: u32 },
+    EndComputePass { pass: ObjectId },
+}
+
+struct NullObject {
+    kind: ObjectKind,
+    format: SDL_GPUTextureFormat,
+    /// Contents of buffers and transfer buffers.
+    memory: Vec<u8>,
+}
+
+struct NullState {
+    commands: Vec<Command>,
+    last_id: u64,
+    objects: HashMap<ObjectId, NullObject>,
+    /// Swapchain texture per window, never released.
+    swapchains: HashMap<usize, ObjectId>,
+    swapchain_size: (u32, u32),
+}
+
+/// The calls recorded by a null device, shared with the device.
+#[derive(Clone)]
+pub struct CommandLog {
+    state: Arc<Mutex<NullState>>,
+}
+
+impl CommandLog {
+    /// Everything recorded so far, oldest first.
+    pub fn commands(&self) -> Vec<Command> {
+        lock(&self.state).commands.clone()
+    }
+
+    /// Everything recorded so far, leaving the log empty.
+    pub fn take(&self) -> Vec<Command> {
+        std::mem::take(&mut lock(&self.state).commands)
+    }
+
+    pub fn clear(&self) {
+        lock(&self.state).commands.clear();
+    }
+
+    /// Objects created and not yet released or ended, oldest first.
+    /// Swapchain textures are not included.
+    pub fn live_objects(&self) -> Vec<(ObjectKind, ObjectId)> {
+        let state = lock(&self.state);
+        let mut live: Vec<_> = state.objects.iter().map(|(id, object)| (object.kind, *id)).collect();
+        live.sort_by_key(|(_, id)| *id);
+        live
+    }
+
+    pub fn is_live(&self, id: ObjectId) -> bool {
+        lock(&self.state).objects.contains_key(&id)
+    }
+
+    /// Current contents of a live buffer or transfer buffer.
+    pub fn buffer_contents(&self, id: ObjectId) -> Option<Vec<u8>> {
+        lock(&self.state).objects.get(&id).map(|object| object.memory.clone())
+    }
+
+    /// Size of the swapchain textures handed out from now on, 1280×720 by
+    /// default. A zero size makes acquiring return no texture, as for a
+    /// minimized window.
+    pub fn set_swapchain_size(&self, width: u32, height: u32) {
+        lock(&self.state).swapchain_size = (width, height);
+    }
+}
+
+pub(crate) struct NullBackend {
+    state: Arc<Mutex<NullState>>,
+    shader_formats: SDL_GPUShaderFormat,
+}
+
+impl NullBackend {
+    /// A backend accepting shaders in `shader_formats`, and the log it
+    /// records into.
+    pub fn new(shader_formats: SDL_GPUShaderFormat) -> (Self, CommandLog) {
+        let state = Arc::new(Mutex::new(NullState {
+            commands: Vec::new(),
+            last_id: 0,
+            objects: HashMap::new(),
+            swapchains: HashMap::new(),
+            swapchain_size: (1280, 720),
+        }));
+        (Self { state: state.clone(), shader_formats }, CommandLog { state })
+    }
+
+    fn record(&self, command: Command) {
+        lock(&self.state).commands.push(command);
+    }
+
+    fn next_id(&self) -> ObjectId {
+        let mut state = lock(&self.state);
+        state.last_id += 1;
+        ObjectId(state.last_id)
+    }
+
+    fn create(&self, kind: ObjectKind, format: SDL_GPUTextureFormat, size: u32) -> ObjectId {
+        let id = self.next_id();
+        let object = NullObject { kind, format, memory: vec![0; size as usize] };
+        lock(&self.state).objects.insert(id, object);
+        id
+    }
+
+    /// Forget a live object. Releasing an object twice, or one of another
+    /// kind, is a bug in the code under test and panics.
+    fn release(&self, kind: ObjectKind, id: ObjectId) {
+        let removed = lock(&self.state).objects.remove(&id);
+        match removed {
+            Some(object) if object.kind == kind => {}
+            _ if std::thread::panicking() => {}
+            Some(object) => panic!("released {:?} {id:?} as a {kind:?}", object.kind),
+            None => panic!("released {kind:?} {id:?}, which is not live"),
+        }
+    }
+
+    fn memory(&self, id: ObjectId, offset: u32, size: u32) -> Vec<u8> {
+        let state = lock(&self.state);
+        let Some(object) = state.objects.get(&id) else {
+            return Vec::new();
+        };
+        let start = (offset as usize).min(object.memory.len());
+        let end = (offset as usize + size as usize).min(object.memory.len());
+        object.memory[start..end].to_vec()
+    }
+
+    fn write_memory(&self, id: ObjectId, offset: u32, data: &[u8]) {
+        let mut state = lock(&self.state);
+        if let Some(object) = state.objects.get_mut(&id) {
+            let start = (offset as usize).min(object.memory.len());
+            let end = (start + data.len()).min(object.memory.len());
+            object.memory[start..end].copy_from_slice(&data[..end - start]);
+        }
+    }
+
+    fn texture_size(&self, texture: ObjectId, w: u32, h: u32, d: u32) -> u32 {
+        let format = lock(&self.state).objects.get(&texture).map_or(SDL_GPUTextureFormat::INVALID, |o| o.format);
+        unsafe { gpu::SDL_CalculateGPUTextureFormatSize(format, w, h, d) }
+    }
+}
+
+/// The state stays consistent across a panic in the code under test, so
+/// poisoning is ignored.
+fn lock(state: &Mutex<NullState>) -> MutexGuard<'_, NullState> {
+    state.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn ids<T>(pointers: &[*mut T]) -> Vec<ObjectId> {
+    pointers.iter().map(|p| ObjectId::of(*p)).collect()
+}
+
+fn sampler_bindings(bindings: &[gpu::SDL_GPUTextureSamplerBinding]) -> Vec<(ObjectId, ObjectId)> {
+    bindings.iter().map(|b| (ObjectId::of(b.texture), ObjectId::of(b.sampler))).collect()
+}
+
+fn entrypoint(name: *const std::ffi::c_char) -> String {
+    if name.is_null() {
+        return String::new();
+    }
+    unsafe { std::ffi::CStr::from_ptr(name).to_string_lossy().into_owned() }
+}
+
+impl Backend for NullBackend {
+    fn create_texture(&self, info: &gpu::SDL_GPUTextureCreateInfo) -> *mut gpu::SDL_GPUTexture {
+        let texture = self.create(ObjectKind::Texture, info.format, 0);
+        self.record(Command::CreateTexture {
+            texture,
+            name: read_string_property(info.props, gpu::SDL_PROP_GPU_TEXTURE_CREATE_NAME_STRING),
+            format: info.format,
+            usage: info.usage,
+            width: info.width,
+            height: info.height,
+            layer_count_or_depth: info.layer_count_or_depth,
+            num_levels: info.num_levels,
+            sample_count: info.sample_count,
+        });
+        texture.as_ptr()
+    }
+
+    fn release_texture(&self, texture: *mut gpu::SDL_GPUTexture) {
+        let texture = ObjectId::of(texture);
+        self.release(ObjectKind::Texture, texture);
+        self.record(Command::ReleaseTexture { texture });
+    }
+
+    fn create_shader(&self, info: &gpu::SDL_GPUShaderCreateInfo) -> *mut gpu::SDL_GPUShader {
+        let shader = self.create(ObjectKind::Shader, SDL_GPUTextureFormat::INVALID, 0);
+        self.record(Command::CreateShader {
+            shader,
+            name: read_string_property(info.props, gpu::SDL_PROP_GPU_SHADER_CREATE_NAME_STRING),
+            stage: info.stage,
+            entrypoint: entrypoint(info.entrypoint),
+            code_size: info.code_size,
+            num_samplers: info.num_samplers,
+            num_storage_textures: info.num_storage_textures,
+            num_storage_buffers: info.num_storage_buffers,
+            num_uniform_buffers: info.num_uniform_buffers,
+        });
+        shader.as_ptr()
+    }
+
+    fn release_shader(&self, shader: *mut gpu::SDL_GPUShader) {
+        let shader = ObjectId::of(shader);
+        self.release(ObjectKind::Shader, shader);
+        self.record(Command::ReleaseShader { shader });
+    }
+
+    fn create_graphics_pipeline(&self, info: &gpu::SDL_GPUGraphicsPipelineCreateInfo) -> *mut gpu::SDL_GPUGraphicsPipeline {
+        let target_info = &info.target_info;
+        let color_target_formats = if target_info.num_color_targets == 0 {
+            Vec::new()
+        } else {
+            let descriptions = unsafe {
+                std::slice::from_raw_parts(target_info.color_target_descriptions, target_info.num_color_targets as usize)
+            };
+            descriptions.iter().map(|d| d.format).collect()
+        };
+        let pipeline = self.create(ObjectKind::GraphicsPipeline, SDL_GPUTextureFormat::INVALID, 0);
+        self.record(Command::CreateGraphicsPipeline {
+            pipeline,
+            name: read_string_property(info.props, gpu::SDL_PROP_GPU_GRAPHICSPIPELINE_CREATE_NAME_STRING),
+            vertex_shader: ObjectId::of(info.vertex_shader),
+            fragment_shader: ObjectId::of(info.fragment_shader),
+            primitive_type: info.primitive_type,
+            color_target_formats,
+            depth_stencil_format: target_info.has_depth_stencil_target.then_some(target_info.depth_stencil_format),
+        });
+        pipeline.as_ptr()
+    }
+
+    fn release_graphics_pipeline(&self, pipeline: *mut gpu::SDL_GPUGraphicsPipeline) {
+        let pipeline = ObjectId::of(pipeline);
+        self.release(ObjectKind::GraphicsPipeline, pipeline);
+        self.record(Command::ReleaseGraphicsPipeline { pipeline });
+    }
+
+    fn create_compute_pipeline(&self, info: &gpu::SDL_GPUComputePipelineCreateInfo) -> *mut gpu::SDL_GPUComputePipeline {
+        let pipeline = self.create(ObjectKind::ComputePipeline, SDL_GPUTextureFormat::INVALID, 0);
+        self.record(Command::CreateComputePipeline {
+            pipeline,
+            name: read_string_property(info.props, gpu::SDL_PROP_GPU_COMPUTEPIPELINE_CREATE_NAME_STRING),
+            entrypoint: entrypoint(info.entrypoint),
+            threadcount: [info.threadcount_x, info.threadcount_y, info.threadcount_z],
+        });
+        pipeline.as_ptr()
+    }
+
+    fn release_compute_pipeline(&self, pipeline: *mut gpu::SDL_GPUComputePipeline) {
+        let pipeline = ObjectId::of(pipeline);
+        self.release(ObjectKind::ComputePipeline, pipeline);
+        self.record(Command::ReleaseComputePipeline { pipeline });
+    }
+
+    fn create_buffer(&self, info: &gpu::SDL_GPUBufferCreateInfo) -> *mut gpu::SDL_GPUBuffer {
+        let buffer = self.create(ObjectKind::Buffer, SDL_GPUTextureFormat::INVALID, info.size);
+        let name = read_string_property(info.props, gpu::SDL_PROP_GPU_BUFFER_CREATE_NAME_STRING);
+        self.record(Command::CreateBuffer { buffer, name, usage: info.usage, size: info.size });
+        buffer.as_ptr()
+    }
+
+    fn release_buffer(&self, buffer: *mut gpu::SDL_GPUBuffer) {
+        let buffer = ObjectId::of(buffer);
+        self.release(ObjectKind::Buffer, buffer);
+        self.record(Command::ReleaseBuffer { buffer });
+    }
+
+    fn create_sampler(&self, info: &gpu::SDL_GPUSamplerCreateInfo) -> *mut gpu::SDL_GPUSampler {
+        let sampler = self.create(ObjectKind::Sampler, SDL_GPUTextureFormat::INVALID, 0);
+        let name = read_string_property(info.props, gpu::SDL_PROP_GPU_SAMPLER_CREATE_NAME_STRING);
+        self.record(Command::CreateSampler { sampler, name });
+        sampler.as_ptr()
+    }
+
+    fn release_sampler(&self, sampler: *mut gpu::SDL_GPUSampler) {
+        let sampler = ObjectId::of(sampler);
+        self.release(ObjectKind::Sampler, sampler);
+        self.record(Command::ReleaseSampler { sampler });
+    }
+
+    fn create_transfer_buffer(&self, info: &gpu::SDL_GPUTransferBufferCreateInfo) -> *mut gpu::SDL_GPUTransferBuffer {
+        let transfer_buffer = self.create(ObjectKind::TransferBuffer, SDL_GPUTextureFormat::INVALID, info.size);
+        self.record(Command::CreateTransferBuffer { transfer_buffer, usage: info.usage, size: info.size });
+        transfer_buffer.as_ptr()
+    }
+
+    fn release_transfer_buffer(&self, transfer: *mut gpu::SDL_GPUTransferBuffer) {
+        let transfer_buffer = ObjectId::of(transfer);
+        self.release(ObjectKind::TransferBuffer, transfer_buffer);
+        self.record(Command::ReleaseTransferBuffer { transfer_buffer });
+    }
+
+    fn map_transfer_buffer(&self, transfer: *mut gpu::SDL_GPUTransferBuffer, _cycle: bool) -> *mut u8 {
+        // The memory stays put until the transfer buffer is released.
+        let mut state = lock(&self.state);
+        state.objects.get_mut(&ObjectId::of(transfer))
+            .map_or(std::ptr::null_mut(), |object| object.memory.as_mut_ptr())
+    }
+
+    fn unmap_transfer_buffer(&self, _transfer: *mut gpu::SDL_GPUTransferBuffer) {}
+
+    fn set_texture_name(&self, texture: *mut gpu::SDL_GPUTexture, name: &CStr) {
+        self.record(Command::SetTextureName { texture: ObjectId::of(texture), name: name.to_string_lossy().into_owned() });
+    }
+
+    fn set_buffer_name(&self, buffer: *mut gpu::SDL_GPUBuffer, name: &CStr) {
+        self.record(Command::SetBufferName { buffer: ObjectId::of(buffer), name: name.to_string_lossy().into_owned() });
+    }
+
+    fn claim_window(&self, _window: *mut SDL_Window) -> bool {
+        self.record(Command::ClaimWindow);
+        true
+    }
+
+    fn release_window(&self, window: *mut SDL_Window) {
+        lock(&self.state).swapchains.remove(&(window as usize));
+        self.record(Command::ReleaseWindow);
+    }
+
+    fn window_supports_present_mode(&self, _window: *mut SDL_Window, _mode: SDL_GPUPresentMode) -> bool {
+        true
+    }
+
+    fn window_supports_swapchain_composition(&self, _window: *mut SDL_Window, _composition: SDL_GPUSwapchainComposition) -> bool {
+        true
+    }
+
+    fn set_swapchain_parameters(
+        &self,
+        _window: *mut SDL_Window,
+        composition: SDL_GPUSwapchainComposition,
+        present_mode: SDL_GPUPresentMode,
+    ) -> bool {
+        self.record(Command::SetSwapchainParameters { composition, present_mode });
+        true
+    }
+
+    fn set_allowed_frames_in_flight(&self, frames: u32) -> bool {
+        self.record(Command::SetAllowedFramesInFlight { frames });
+        true
+    }
+
+    fn swapchain_texture_format(&self, _window: *mut SDL_Window) -> SDL_GPUTextureFormat {
+        SDL_GPUTextureFormat::B8G8R8A8_UNORM
+    }
+
+    fn wait_for_swapchain(&self, _window: *mut SDL_Window) -> bool {
+        true
+    }
+
+    fn shader_formats(&self) -> SDL_GPUShaderFormat {
+        self.shader_formats
+    }
+
+    fn driver_name(&self) -> String {
+        "null".to_string()
+    }
+
+    fn device_properties(&self) -> sys::properties::SDL_PropertiesID {
+        sys::properties::SDL_PropertiesID(0)
+    }
+
+    fn acquire_command_buffer(&self) -> *mut gpu::SDL_GPUCommandBuffer {
+        let command_buffer = self.create(ObjectKind::CommandBuffer, SDL_GPUTextureFormat::INVALID, 0);
+        self.record(Command::AcquireCommandBuffer { command_buffer });
+        command_buffer.as_ptr()
+    }
+
+    fn submit_command_buffer(&self, cmd: *mut gpu::SDL_GPUCommandBuffer) -> bool {
+        let command_buffer = ObjectId::of(cmd);
+        self.release(ObjectKind::CommandBuffer, command_buffer);
+        self.record(Command::Submit { command_buffer, fence: None });
+        true
+    }
+
+    fn submit_command_buffer_and_acquire_fence(&self, cmd: *mut gpu::SDL_GPUCommandBuffer) -> *mut gpu::SDL_GPUFence {
+        let command_buffer = ObjectId::of(cmd);
+        self.release(ObjectKind::CommandBuffer, command_buffer);
+        let fence = self.create(ObjectKind::Fence, SDL_GPUTextureFormat::INVALID, 0);
+        self.record(Command::Submit { command_buffer, fence: Some(fence) });
+        fence.as_ptr()
+    }
+
+    fn cancel_command_buffer(&self, cmd: *mut gpu::SDL_GPUCommandBuffer) {
+        let command_buffer = ObjectId::of(cmd);
+        self.release(ObjectKind::CommandBuffer, command_buffer);
+        self.record(Command::Cancel { command_buffer });
+    }
+
+    fn query_fence(&self, _fence: *mut gpu::SDL_GPUFence) -> bool {
+        true
+    }
+
+    fn wait_for_fence(&self, _fence: *mut gpu::SDL_GPUFence) -> bool {
+        true
+    }
+
+    fn release_fence(&self, fence: *mut gpu::SDL_GPUFence) {
+        let fence = ObjectId::of(fence);
+        self.release(ObjectKind::Fence, fence);
+        self.record(Command::ReleaseFence { fence });
+    }
+
+    fn acquire_swapchain_texture(
+        &self,
+        cmd: *mut gpu::SDL_GPUCommandBuffer,
+        window: *mut SDL_Window,
+        _wait: bool,
+    ) -> Option<(*mut gpu::SDL_GPUTexture, u32, u32)> {
+        let (width, height) = lock(&self.state).swapchain_size;
+        let texture = if width == 0 || height == 0 {
+            ObjectId::NULL
+        } else {
+            let existing = lock(&self.state).swapchains.get(&(window as usize)).copied();
+            existing.unwrap_or_else(|| {
+                let id = self.next_id();
+                lock(&self.state).swapchains.insert(window as usize, id);
+                id
+            })
+        };
+        self.record(Command::AcquireSwapchainTexture { command_buffer: ObjectId::of(cmd), texture, width, height });
+        Some((texture.as_ptr(), width, height))
+    }
+
+    fn blit_texture(&self, cmd: *mut gpu::SDL_GPUCommandBuffer, info: &gpu::SDL_GPUBlitInfo) {
+        self.record(Command::Blit {
+            command_buffer: ObjectId::of(cmd),
+            source: ObjectId::of(info.source.texture),
+            destination: ObjectId::of(info.destination.texture),
+        });
+    }
+
+    fn insert_debug_label(&self, cmd: *mut gpu::SDL_GPUCommandBuffer, text: &CStr) {
+        self.record(Command::InsertDebugLabel { command_buffer: ObjectId::of(cmd), text: text.to_string_lossy().into_owned() });
+    }
+
+    fn push_debug_group(&self, cmd: *mut gpu::SDL_GPUCommandBuffer, name: &CStr) {
+        self.record(Command::PushDebugGroup { command_buffer: ObjectId::of(cmd), name: name.to_string_lossy().into_owned() });
+    }
+
+    fn pop_debug_group(&self, cmd: *mut gpu::SDL_GPUCommandBuffer) {
+        self.record(Command::PopDebugGroup { command_buffer: ObjectId::of(cmd) });
+    }
+
+    fn push_vertex_uniform_data(&self, cmd: *mut gpu::SDL_GPUCommandBuffer, slot_index: u32, data: &[u8]) {
+        self.record(Command::PushVertexUniformData { command_buffer: ObjectId::of(cmd), slot_index, data: data.to_vec() });
+    }
+
+    fn push_fragment_uniform_data(&self, cmd: *mut gpu::SDL_GPUCommandBuffer, slot_index: u32, data: &[u8]) {
+        self.record(Command::PushFragmentUniformData { command_buffer: ObjectId::of(cmd), slot_index, data: data.to_vec() });
+    }
+
+    fn push_compute_uniform_data(&self, cmd: *mut gpu::SDL_GPUCommandBuffer, slot_index: u32, data: &[u8]) {
+        self.record(Command::PushComputeUniformData { command_buffer: ObjectId::of(cmd), slot_index, data: data.to_vec() });
+    }
+
+    fn begin_copy_pass(&self, cmd: *mut gpu::SDL_GPUCommandBuffer) -> *mut gpu::SDL_GPUCopyPass {
+        let pass = self.create(ObjectKind::CopyPass, SDL_GPUTextureFormat::INVALID, 0);
+        self.record(Command::BeginCopyPass { command_buffer: ObjectId::of(cmd), pass });
+        pass.as_ptr()
+    }
+
+    fn upload_to_buffer(
+        &self,
+        _pass: *mut gpu::SDL_GPUCopyPass,
+        source: &gpu::SDL_GPUTransferBufferLocation,
+        destination: &gpu::SDL_GPUBufferRegion,
+        _cycle: bool,
+    ) {
+        let transfer_buffer = ObjectId::of(source.transfer_buffer);
+        let buffer = ObjectId::of(destination.buffer);
+        let data = self.memory(transfer_buffer, source.offset, destination.size);
+        self.write_memory(buffer, destination.offset, &data);
+        self.record(Command::UploadToBuffer { transfer_buffer, buffer, offset: destination.offset, data });
+    }
+
+    fn upload_to_texture(
+        &self,
+        _pass: *mut gpu::SDL_GPUCopyPass,
+        source: &gpu::SDL_GPUTextureTransferInfo,
+        destination: &gpu::SDL_GPUTextureRegion,
+        _cycle: bool,
+    ) {
+        let transfer_buffer = ObjectId::of(source.transfer_buffer);
+        let texture = ObjectId::of(destination.texture);
+        let size = self.texture_size(texture, destination.w, destination.h, destination.d);
+        let data = self.memory(transfer_buffer, source.offset, size);
+        self.record(Command::UploadToTexture {
+            transfer_buffer,
+            texture,
+            x: destination.x,
+            y: destination.y,
+            w: destination.w,
+            h: destination.h,
+            data,
+        });
+    }
+
+    fn download_from_buffer(
+        &self,
+        _pass: *mut gpu::SDL_GPUCopyPass,
+        source: &gpu::SDL_GPUBufferRegion,
+        destination: &gpu::SDL_GPUTransferBufferLocation,
+    ) {
+        let buffer = ObjectId::of(source.buffer);
+        let transfer_buffer = ObjectId::of(destination.transfer_buffer);
+        let data = self.memory(buffer, source.offset, source.size);
+        self.write_memory(transfer_buffer, destination.offset, &data);
+        self.record(Command::DownloadFromBuffer { buffer, offset: source.offset, size: source.size, transfer_buffer });
+    }
+
+    fn download_from_texture(
+        &self,
+        _pass: *mut gpu::SDL_GPUCopyPass,
+        source: &gpu::SDL_GPUTextureRegion,
+        destination: &gpu::SDL_GPUTextureTransferInfo,
+    ) {
+        let texture = ObjectId::of(source.texture);
+        let transfer_buffer = ObjectId::of(destination.transfer_buffer);
+        let size = self.texture_size(texture, source.w, source.h, source.d);
+        self.write_memory(transfer_buffer, destination.offset, &vec![0; size as usize]);
+        self.record(Command::DownloadFromTexture {
+            texture,
+            x: source.x,
+            y: source.y,
+            w: source.w,
+            h: source.h,
+            transfer_buffer,
+        });
+    }
+
+    fn copy_buffer_to_buffer(
+        &self,
+        _pass: *mut gpu::SDL_GPUCopyPass,
+        source: &gpu::SDL_GPUBufferLocation,
+        destination: &gpu::SDL_GPUBufferLocation,
+        size: u32,
+        _cycle: bool,
+    ) {
+        let (src, dst) = (ObjectId::of(source.buffer), ObjectId::of(destination.buffer));
+        let data = self.memory(src, source.offset, size);
+        self.write_memory(dst, destination.offset, &data);
+        self.record(Command::CopyBufferToBuffer {
+            source: src,
+            source_offset: source.offset,
+            destination: dst,
+            destination_offset: destination.offset,
+            size,
+        });
+    }
+
+    fn end_copy_pass(&self, pass: *mut gpu::SDL_GPUCopyPass) {
+        let pass = ObjectId::of(pass);
+        self.release(ObjectKind::CopyPass, pass);
+        self.record(Command::EndCopyPass { pass });
+    }
+
+    fn begin_render_pass(
+        &self,
+        cmd: *mut gpu::SDL_GPUCommandBuffer,
+        color_targets: &[gpu::SDL_GPUColorTargetInfo],
+        depth_stencil_target: Option<&gpu::SDL_GPUDepthStencilTargetInfo>,
+    ) -> *mut gpu::SDL_GPURenderPass {
+        let pass = self.create(ObjectKind::RenderPass, SDL_GPUTextureFormat::INVALID, 0);
+        self.record(Command::BeginRenderPass {
+            command_buffer: ObjectId::of(cmd),
+            pass,
+            color_targets: color_targets.iter().map(|t| ObjectId::of(t.texture)).collect(),
+            depth_stencil_target: depth_stencil_target.map(|t| ObjectId::of(t.texture)),
+        });
+        pass.as_ptr()
+    }
+
+    fn bind_graphics_pipeline(&self, _pass: *mut gpu::SDL_GPURenderPass, pipeline: *mut gpu::SDL_GPUGraphicsPipeline) {
+        self.record(Command::BindGraphicsPipeline { pipeline: ObjectId::of(pipeline) });
+    }
+
+    fn bind_vertex_buffers(&self, _pass: *mut gpu::SDL_GPURenderPass, first_slot: u32, bindings: &[gpu::SDL_GPUBufferBinding]) {
+        let buffers = bindings.iter().map(|b| (ObjectId::of(b.buffer), b.offset)).collect();
+        self.record(Command::BindVertexBuffers { first_slot, buffers });
+    }
+
+    fn bind_index_buffer(
+        &self,
+        _pass: *mut gpu::SDL_GPURenderPass,
+        binding: &gpu::SDL_GPUBufferBinding,
+        index_element_size: SDL_GPUIndexElementSize,
+    ) {
+        self.record(Command::BindIndexBuffer { buffer: ObjectId::of(binding.buffer), offset: binding.offset, index_element_size });
+    }
+
+    fn bind_fragment_samplers(&self, _pass: *mut gpu::SDL_GPURenderPass, first_slot: u32, bindings: &[gpu::SDL_GPUTextureSamplerBinding]) {
+        self.record(Command::BindFragmentSamplers { first_slot, bindings: sampler_bindings(bindings) });
+    }
+
+    fn bind_fragment_storage_textures(&self, _pass: *mut gpu::SDL_GPURenderPass, first_slot: u32, textures: &[*mut gpu::SDL_GPUTexture]) {
+        self.record(Command::BindFragmentStorageTextures { first_slot, textures: ids(textures) });
+    }
+
+    fn bind_fragment_storage_buffers(&self, _pass: *mut gpu::SDL_GPURenderPass, first_slot: u32, buffers: &[*mut gpu::SDL_GPUBuffer]) {
+        self.record(Command::BindFragmentStorageBuffers { first_slot, buffers: ids(buffers) });
+    }
+
+    fn bind_vertex_storage_buffers(&self, _pass: *mut gpu::SDL_GPURenderPass, first_slot: u32, buffers: &[*mut gpu::SDL_GPUBuffer]) {
+        self.record(Command::BindVertexStorageBuffers { first_slot, buffers: ids(buffers) });
+    }
+
+    fn bind_vertex_samplers(&self, _pass: *mut gpu::SDL_GPURenderPass, first_slot: u32, bindings: &[gpu::SDL_GPUTextureSamplerBinding]) {
+        self.record(Command::BindVertexSamplers { first_slot, bindings: sampler_bindings(bindings) });
+    }
+
+    fn bind_vertex_storage_textures(&self, _pass: *mut gpu::SDL_GPURenderPass, first_slot: u32, textures: &[*mut gpu::SDL_GPUTexture]) {
+        self.record(Command::BindVertexStorageTextures { first_slot, textures: ids(textures) });
+    }
+
+    fn draw_primitives(&self, _pass: *mut gpu::SDL_GPURenderPass, num_vertices: u32, num_instances: u32, first_vertex: u32, first_instance: u32) {
+        self.record(Command::Draw { num_vertices, num_instances, first_vertex, first_instance });
+    }
+
+    fn draw_indexed_primitives(
+        &self,
+        _pass: *mut gpu::SDL_GPURenderPass,
+        num_indices: u32,
+        num_instances: u32,
+        first_index: u32,
+        vertex_offset: i32,
+        first_instance: u32,
+    ) {
+        self.record(Command::DrawIndexed { num_indices, num_instances, first_index, vertex_offset, first_instance });
+    }
+
+    fn draw_primitives_indirect(&self, _pass: *mut gpu::SDL_GPURenderPass, buffer: *mut gpu::SDL_GPUBuffer, offset: u32, draw_count: u32) {
+        self.record(Command::DrawIndirect { buffer: ObjectId::of(buffer), offset, draw_count });
+    }
+
+    fn draw_indexed_primitives_indirect(&self, _pass: *mut gpu::SDL_GPURenderPass, buffer: *mut gpu::SDL_GPUBuffer, offset: u32, draw_count: u32) {
+        self.record(Command::DrawIndexedIndirect { buffer: ObjectId::of(buffer), offset, draw_count });
+    }
+
+    fn set_viewport(&self, _pass: *mut gpu::SDL_GPURenderPass, viewport: &gpu::SDL_GPUViewport) {
+        self.record(Command::SetViewport {
+            x: viewport.x,
+            y: viewport.y,
+            w: viewport.w,
+            h: viewport.h,
+            min_depth: viewport.min_depth,
+            max_depth: viewport.max_depth,
+        });
+    }
+
+    fn set_scissor(&self, _pass: *mut gpu::SDL_GPURenderPass, rect: &sys::rect::SDL_Rect) {
+        self.record(Command::SetScissor { x: rect.x, y: rect.y, w: rect.w, h: rect.h });
+    }
+
+    fn set_stencil_reference(&self, _pass: *mut gpu::SDL_GPURenderPass, reference: u8) {
+        self.record(Command::SetStencilReference { reference });
+    }
+
+    fn set_blend_constants(&self, _pass: *mut gpu::SDL_GPURenderPass, c: sys::pixels::SDL_FColor) {
+        self.record(Command::SetBlendConstants { constants: [c.r, c.g, c.b, c.a] });
+    }
+
+    fn end_render_pass(&self, pass: *mut gpu::SDL_GPURenderPass) {
+        let pass = ObjectId::of(pass);
+        self.release(ObjectKind::RenderPass, pass);
+        self.record(Command::EndRenderPass { pass });
+    }
+
+    fn begin_compute_pass(
+        &self,
+        cmd: *mut gpu::SDL_GPUCommandBuffer,
+        storage_textures: &[gpu::SDL_GPUStorageTextureReadWriteBinding],
+        storage_buffers: &[gpu::SDL_GPUStorageBufferReadWriteBinding],
+    ) -> *mut gpu::SDL_GPUComputePass {
+        let pass = self.create(ObjectKind::ComputePass, SDL_GPUTextureFormat::INVALID, 0);
+        self.record(Command::BeginComputePass {
+            command_buffer: ObjectId::of(cmd),
+            pass,
+            storage_textures: storage_textures.iter().map(|b| ObjectId::of(b.texture)).collect(),
+            storage_buffers: storage_buffers.iter().map(|b| ObjectId::of(b.buffer)).collect(),
+        });
+        pass.as_ptr()
+    }
+
+    fn bind_compute_pipeline(&self, _pass: *mut gpu::SDL_GPUComputePass, pipeline: *mut gpu::SDL_GPUComputePipeline) {
+        self.record(Command::BindComputePipeline { pipeline: ObjectId::of(pipeline) });
+    }
+
+    fn bind_compute_storage_textures(&self, _pass: *mut gpu::SDL_GPUComputePass, first_slot: u32, textures: &[*mut gpu::SDL_GPUTexture]) {
+        self.record(Command::BindComputeStorageTextures { first_slot, textures: ids(textures) });
+    }
+
+    fn bind_compute_storage_buffers(&self, _pass: *mut gpu::SDL_GPUComputePass, first_slot: u32, buffers: &[*mut gpu::SDL_GPUBuffer]) {
+        self.record(Command::BindComputeStorageBuffers { first_slot, buffers: ids(buffers) });
+    }
+
+    fn bind_compute_samplers(&self, _pass: *mut gpu::SDL_GPUComputePass, first_slot: u32, bindings: &[gpu::SDL_GPUTextureSamplerBinding]) {
+        self.record(Command::BindComputeSamplers { first_slot, bindings: sampler_bindings(bindings) });
+    }
+
+    fn dispatch_compute(&self, _pass: *mut gpu::SDL_GPUComputePass, groupcount_x: u32, groupcount_y: u32, groupcount_z: u32) {
+        self.record(Command::Dispatch { groupcount_x, groupcount_y, groupcount_z });
+    }
+
+    fn dispatch_compute_indirect(&self, _pass: *mut gpu::SDL_GPUComputePass, buffer: *mut gpu::SDL_GPUBuffer, offset: u32) {
+        self.record(Command::DispatchIndirect { buffer: ObjectId::of(buffer), offset });
+    }
+
+    fn end_compute_pass(&self, pass: *mut gpu::SDL_GPUComputePass) {
+        let pass = ObjectId::of(pass);
+        self.release(ObjectKind::ComputePass, pass);
+        self.record(Command::EndComputePass { pass });
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::device::{
+        ColorTargetInfo, Device, FrameStats, GPUBuffer, GPUBufferBinding, GraphicsPipeline, GraphicsPipelineCreateInfo,
+        Sampler, Shader, ShaderCreateInfo, Texture, TextureDesc,
+    };
+
+    pub(crate) const COLOR_FORMAT: SDL_GPUTextureFormat = SDL_GPUTextureFormat::R8G8B8A8_UNORM;
+
+    pub(crate) fn null_device() -> (Device, CommandLog) {
+        Device::new_null(SDL_GPUShaderFormat::SPIRV)
+    }
+
+    pub(crate) fn texture(device: &Device, usage: SDL_GPUTextureUsageFlags) -> Texture {
+        device.create_texture(&TextureDesc::new(COLOR_FORMAT, 64, 64, usage).to_create_info()).unwrap()
+    }
+
+    pub(crate) fn sampler(device: &Device) -> Sampler {
+        device.create_sampler(&Default::default()).unwrap()
+    }
+
+    pub(crate) fn shader(device: &Device, stage: SDL_GPUShaderStage, num_samplers: u32, num_storage_textures: u32) -> Shader {
+        device
+            .create_shader(&ShaderCreateInfo {
+                code: &[0; 4],
+                entrypoint: "main",
+                format: SDL_GPUShaderFormat::SPIRV,
+                stage,
+                num_samplers,
+                num_storage_textures,
+                num_storage_buffers: 0,
+                num_uniform_buffers: 0,
+                name: None,
+            })
+            .unwrap()
+    }
+
+    /// A pipeline reading vertex buffer slot 0 and rendering into one
+    /// target of `format`.
+    pub(crate) fn pipeline(
+        device: &Device,
+        vertex_shader: Shader,
+        fragment_shader: Shader,
+        format: SDL_GPUTextureFormat,
+    ) -> GraphicsPipeline {
+        device
+            .create_graphics_pipeline(&GraphicsPipelineCreateInfo {
+                vertex_shader,
+                fragment_shader,
+                vertex_attributes: vec![gpu::SDL_GPUVertexAttribute {
+                    location: 0,
+                    buffer_slot: 0,
+                    format: gpu::SDL_GPUVertexElementFormat::FLOAT2,
+                    offset: 0,
+                }],
+                vertex_buffer_descriptions: vec![gpu::SDL_GPUVertexBufferDescription { slot: 0, pitch: 8, ..Default::default() }],
+                primitive_type: SDL_GPUPrimitiveType::TRIANGLELIST,
+                rasterizer_state: Default::default(),
+                multisample_state: Default::default(),
+                depth_stencil_state: Default::default(),
+                color_target_descriptions: vec![gpu::SDL_GPUColorTargetDescription { format, blend_state: Default::default() }],
+                depth_stencil_format: Default::default(),
+                has_depth_stencil_target: false,
+                name: Some("test pipeline".to_string()),
+            })
+            .unwrap()
+    }
+
+    pub(crate) fn vertex_buffer(device: &Device) -> GPUBuffer {
+        device.create_buffer(SDL_GPUBufferUsageFlags::VERTEX, 64).unwrap()
+    }
+
+    fn texture_id(device: &Device, texture: Texture) -> ObjectId {
+        ObjectId::of(device.texture_raw(texture))
+    }
+
+    fn buffer_id(device: &Device, buffer: GPUBuffer) -> ObjectId {
+        ObjectId::of(device.buffer_raw(buffer))
+    }
+
+    #[test]
+    fn records_creates() {
+        let (device, log) = null_device();
+        let target = device
+            .create_texture_named(&TextureDesc::new(COLOR_FORMAT, 32, 16, SDL_GPUTextureUsageFlags::COLOR_TARGET).to_create_info(), "target")
+            .unwrap();
+        let buffer = device.create_buffer_named(SDL_GPUBufferUsageFlags::VERTEX, 48, "vertices").unwrap();
+        let vs = shader(&device, SDL_GPUShaderStage::VERTEX, 0, 0);
+        let fs = shader(&device, SDL_GPUShaderStage::FRAGMENT, 1, 0);
+        pipeline(&device, vs, fs, COLOR_FORMAT);
+
+        let commands = log.commands();
+        assert_eq!(commands.len(), 5);
+        assert!(matches!(&commands[0], Command::CreateTexture { texture, name: Some(name), width: 32, height: 16, format, .. }
+            if *texture == texture_id(&device, target) && name == "target" && *format == COLOR_FORMAT));
+        assert!(commands[1] == Command::CreateBuffer {
+            buffer: buffer_id(&device, buffer),
+            name: Some("vertices".to_string()),
+            usage: SDL_GPUBufferUsageFlags::VERTEX,
+            size: 48,
+        });
+        assert!(matches!(&commands[2], Command::CreateShader { stage, num_samplers: 0, code_size: 4, .. } if *stage == SDL_GPUShaderStage::VERTEX));
+        assert!(matches!(&commands[3], Command::CreateShader { stage, num_samplers: 1, .. } if *stage == SDL_GPUShaderStage::FRAGMENT));
+        let (Command::CreateShader { shader: vs_id, .. }, Command::CreateShader { shader: fs_id, .. }) = (&commands[2], &commands[3]) else {
+            unreachable!();
+        };
+        assert!(matches!(&commands[4], Command::CreateGraphicsPipeline { name: Some(name), vertex_shader, fragment_shader, color_target_formats, depth_stencil_format: None, .. }
+            if name == "test pipeline" && vertex_shader == vs_id && fragment_shader == fs_id && *color_target_formats == [COLOR_FORMAT]));
+    }
+
+    #[test]
+    fn records_binds_and_draws() {
+        let (device, log) = null_device();
+        let target = texture(&device, SDL_GPUTextureUsageFlags::COLOR_TARGET);
+        let vs = shader(&device, SDL_GPUShaderStage::VERTEX, 0, 0);
+        let fs = shader(&device, SDL_GPUShaderStage::FRAGMENT, 0, 0);
+        let pipeline = pipeline(&device, vs, fs, COLOR_FORMAT);
+        let vertices = vertex_buffer(&device);
+        log.clear();
+
+        let mut cmd = device.acquire_command_buffer().unwrap();
+        {
+            let pass = cmd.begin_render_pass(&[ColorTargetInfo::new(target)], None).unwrap();
+            pass.bind_graphics_pipeline(pipeline);
+            pass.bind_vertex_buffers(1, &[GPUBufferBinding { buffer: vertices, offset: 16 }]);
+            pass.draw_primitives(3, 2, 0, 0);
+        }
+        cmd.submit().unwrap();
+
+        let commands = log.take();
+        let Command::AcquireCommandBuffer { command_buffer } = commands[0] else {
+            panic!("expected AcquireCommandBuffer first");
+        };
+        let Command::BeginRenderPass { command_buffer: pass_cmd, pass, ref color_targets, depth_stencil_target: None } = commands[1] else {
+            panic!("expected BeginRenderPass second");
+        };
+        assert_eq!(pass_cmd, command_buffer);
+        assert_eq!(*color_targets, [texture_id(&device, target)]);
+        assert!(matches!(commands[2], Command::BindGraphicsPipeline { .. }));
+        assert!(commands[3] == Command::BindVertexBuffers { first_slot: 1, buffers: vec![(buffer_id(&device, vertices), 16)] });
+        assert!(commands[4] == Command::Draw { num_vertices: 3, num_instances: 2, first_vertex: 0, first_instance: 0 });
+        assert!(commands[5] == Command::EndRenderPass { pass });
+        assert!(matches!(commands[6], Command::Submit { command_buffer: submitted, .. } if submitted == command_buffer));
+        assert_eq!(commands.len(), 7);
+        assert!(!log.is_live(pass));
+        assert_eq!(device.frame_stats().draws, 1);
+    }
+
+    #[test]
+    fn records_uploads() {
+        let (device, log) = null_device();
+        let buffer = device.create_buffer(SDL_GPUBufferUsageFlags::VERTEX, 8).unwrap();
+        log.clear();
+
+        device.upload_to_buffer(None, buffer, 2, &[1, 2, 3, 4]).unwrap();
+        let id = buffer_id(&device, buffer);
+        assert!(log.commands().iter().any(|c| matches!(c, Command::UploadToBuffer { buffer, offset: 2, data, .. }
+            if *buffer == id && *data == [1, 2, 3, 4])));
+        assert_eq!(log.buffer_contents(id).unwrap(), [0, 0, 1, 2, 3, 4, 0, 0]);
+        assert_eq!(device.download_from_buffer(buffer, 2, 4).unwrap(), [1, 2, 3, 4]);
+        assert_eq!(device.upload_to_buffer(None, buffer, 6, &[0; 4]), Err("data exceeds buffer size"));
+    }
+
+    #[test]
+    fn frame_stats_last_until_taken() {
+        let (device, _log) = null_device();
+        let buffer = vertex_buffer(&device);
+        device.upload_to_buffer(None, buffer, 0, &[0; 4]).unwrap();
+        // Opening the frame's command buffer doesn't drop the upload.
+        device.acquire_command_buffer().unwrap().submit().unwrap();
+
+        assert_eq!(device.frame_stats().upload_bytes, 4);
+        assert_eq!(device.take_frame_stats().upload_bytes, 4);
+        assert_eq!(device.frame_stats(), FrameStats::default());
+    }
+
+    #[test]
+    fn destroyed_objects_are_not_live() {
+        let (device, log) = null_device();
+        let mut kept = texture(&device, SDL_GPUTextureUsageFlags::SAMPLER);
+        let mut destroyed = vertex_buffer(&device);
+        let destroyed_id = buffer_id(&device, destroyed);
+        assert_eq!(log.live_objects().len(), 2);
+
+        destroyed.destroy(&device);
+        assert!(!log.is_live(destroyed_id));
+        assert!(log.commands().contains(&Command::ReleaseBuffer { buffer: destroyed_id }));
+        assert_eq!(log.live_objects(), [(ObjectKind::Texture, texture_id(&device, kept))]);
+        kept.destroy(&device);
+        assert!(log.live_objects().is_empty());
+    }
+
+    #[test]
+    fn dropping_the_device_releases_everything() {
+        let (device, log) = null_device();
+        let vs = shader(&device, SDL_GPUShaderStage::VERTEX, 0, 0);
+        let fs = shader(&device, SDL_GPUShaderStage::FRAGMENT, 0, 0);
+        pipeline(&device, vs, fs, COLOR_FORMAT);
+        texture(&device, SDL_GPUTextureUsageFlags::SAMPLER);
+        sampler(&device);
+        device.upload_to_buffer(None, vertex_buffer(&device), 0, &[0; 8]).unwrap();
+        assert!(!log.live_objects().is_empty());
+
+        drop(device);
+        assert!(log.live_objects().is_empty());
+    }
+
+    #[test]
+    fn shared_handles_keep_resources_alive() {
+        let (device, log) = null_device();
+        let shared = device.shared();
+        shared.create_buffer(SDL_GPUBufferUsageFlags::VERTEX, 4).unwrap();
+        drop(device);
+        assert_eq!(log.live_objects().len(), 1);
+        drop(shared);
+        assert!(log.live_objects().is_empty());
+    }
+
+    #[test]
+    fn shared_device_destroys_pipelines() {
+        let (device, log) = null_device();
+        let vs = shader(&device, SDL_GPUShaderStage::VERTEX, 0, 0);
+        let fs = shader(&device, SDL_GPUShaderStage::FRAGMENT, 0, 0);
+        let mut pipeline = pipeline(&device, vs, fs, COLOR_FORMAT);
+        let id = log.live_objects().into_iter().find(|(kind, _)| *kind == ObjectKind::GraphicsPipeline).unwrap().1;
+
+        device.shared().destroy_graphics_pipeline(&mut pipeline);
+        assert!(!log.is_live(id));
+        assert_eq!(pipeline.0, -1);
+    }
+
+    #[test]
+    #[should_panic(expected = "which is not live")]
+    fn double_release_panics() {
+        let (backend, _log) = NullBackend::new(SDL_GPUShaderFormat::SPIRV);
+        let info = TextureDesc::new(COLOR_FORMAT, 4, 4, SDL_GPUTextureUsageFlags::SAMPLER).to_create_info();
+        let texture = backend.create_texture(&info);
+        backend.release_texture(texture);
+        backend.release_texture(texture);
+    }
+
+    #[test]
+    #[should_panic(expected = "released Texture")]
+    fn release_as_wrong_kind_panics() {
+        let (backend, _log) = NullBackend::new(SDL_GPUShaderFormat::SPIRV);
+        let info = TextureDesc::new(COLOR_FORMAT, 4, 4, SDL_GPUTextureUsageFlags::SAMPLER).to_create_info();
+        let texture = backend.create_texture(&info);
+        backend.release_buffer(texture.cast());
+    }
+}
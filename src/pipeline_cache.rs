@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+
+use crate::device::{
+    ComputePipeline, ComputePipelineCreateInfo, Device, GraphicsPipeline, GraphicsPipelineCreateInfo,
+    SDL_GPUShaderFormat,
+};
+
+/// Owned form of [`ComputePipelineCreateInfo`], used as the cache key.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct ComputePipelineKey {
+    code: Vec<u8>,
+    entrypoint: String,
+    format: SDL_GPUShaderFormat,
+    counts: [u32; 6],
+    threadcount: [u32; 3],
+}
+
+impl ComputePipelineKey {
+    fn new(info: &ComputePipelineCreateInfo) -> Self {
+        Self {
+            code: info.code.to_vec(),
+            entrypoint: info.entrypoint.to_string(),
+            format: info.format,
+            counts: [
+                info.num_samplers,
+                info.num_readonly_storage_textures,
+                info.num_readonly_storage_buffers,
+                info.num_readwrite_storage_textures,
+                info.num_readwrite_storage_buffers,
+                info.num_uniform_buffers,
+            ],
+            threadcount: [info.threadcount_x, info.threadcount_y, info.threadcount_z],
+        }
+    }
+}
+
+/// [`GraphicsPipelineCreateInfo`] with the serials of its vertex and
+/// fragment shaders, so a shader slot reused after a destroy doesn't hit
+/// pipelines built from the old shader.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct GraphicsPipelineKey {
+    info: GraphicsPipelineCreateInfo,
+    shader_serials: [u64; 2],
+}
+
+impl GraphicsPipelineKey {
+    fn new(device: &Device, info: &GraphicsPipelineCreateInfo) -> Self {
+        Self {
+            info: info.clone(),
+            shader_serials: [device.shader_serial(info.vertex_shader), device.shader_serial(info.fragment_shader)],
+        }
+    }
+}
+
+struct CacheEntry<T> {
+    handle: T,
+    refs: u32,
+}
+
+/// Deduplicates pipeline creation.
+///
+/// Pipelines are keyed by their full create info (shaders, vertex layout,
+/// rasterizer, blend, depth-stencil and target formats for graphics; code,
+/// entry point and resource counts for compute). Asking for a pipeline that
+/// is already cached returns the same handle and adds a reference instead of
+/// calling into the driver.
+///
+/// Released pipelines stay cached with zero references until
+/// [`evict_unused`](Self::evict_unused) destroys them, so state that is
+/// dropped and requested again in the same frame is not rebuilt.
+///
+/// A shader destroyed while pipelines built from it are cached doesn't
+/// invalidate them, but a new shader in its slot gets new pipelines.
+#[derive(Default)]
+pub struct PipelineCache {
+    graphics: HashMap<GraphicsPipelineKey, CacheEntry<GraphicsPipeline>>,
+    compute: HashMap<ComputePipelineKey, CacheEntry<ComputePipeline>>,
+}
+
+impl PipelineCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the cached pipeline matching `info`, or create one. Each call
+    /// adds a reference; pair it with
+    /// [`release_graphics_pipeline`](Self::release_graphics_pipeline).
+    pub fn get_or_create_graphics_pipeline(&mut self, device: &Device, info: &GraphicsPipelineCreateInfo) -> Result<GraphicsPipeline, &'static str> {
+        let key = GraphicsPipelineKey::new(device, info);
+        if let Some(entry) = self.graphics.get_mut(&key) {
+            entry.refs += 1;
+            return Ok(entry.handle);
+        }
+        let handle = device.create_graphics_pipeline(info)?;
+        self.graphics.insert(key, CacheEntry { handle, refs: 1 });
+        Ok(handle)
+    }
+
+    /// Return the cached pipeline matching `info`, or create one. Each call
+    /// adds a reference; pair it with
+    /// [`release_compute_pipeline`](Self::release_compute_pipeline).
+    pub fn get_or_create_compute_pipeline(&mut self, device: &Device, info: &ComputePipelineCreateInfo) -> Result<ComputePipeline, &'static str> {
+        let key = ComputePipelineKey::new(info);
+        if let Some(entry) = self.compute.get_mut(&key) {
+            entry.refs += 1;
+            return Ok(entry.handle);
+        }
+        let handle = device.create_compute_pipeline(info)?;
+        self.compute.insert(key, CacheEntry { handle, refs: 1 });
+        Ok(handle)
+    }
+
+    /// Drop one reference to a cached graphics pipeline. Returns false if the
+    /// pipeline is not in the cache or has no references left.
+    pub fn release_graphics_pipeline(&mut self, pipeline: GraphicsPipeline) -> bool {
+        release(self.graphics.values_mut(), pipeline)
+    }
+
+    /// Drop one reference to a cached compute pipeline. Returns false if the
+    /// pipeline is not in the cache or has no references left.
+    pub fn release_compute_pipeline(&mut self, pipeline: ComputePipeline) -> bool {
+        release(self.compute.values_mut(), pipeline)
+    }
+
+    /// Current reference count of a cached graphics pipeline, or `None` if
+    /// it is not in the cache.
+    pub fn graphics_pipeline_refs(&self, pipeline: GraphicsPipeline) -> Option<u32> {
+        self.graphics.values().find(|e| e.handle == pipeline).map(|e| e.refs)
+    }
+
+    /// Current reference count of a cached compute pipeline, or `None` if
+    /// it is not in the cache.
+    pub fn compute_pipeline_refs(&self, pipeline: ComputePipeline) -> Option<u32> {
+        self.compute.values().find(|e| e.handle == pipeline).map(|e| e.refs)
+    }
+
+    /// Destroy every cached pipeline with no references left. Returns how
+    /// many were destroyed.
+    pub fn evict_unused(&mut self, device: &Device) -> usize {
+        let mut evicted = 0;
+        self.graphics.retain(|_, entry| {
+            if entry.refs > 0 {
+                return true;
+            }
+            entry.handle.destroy(device);
+            evicted += 1;
+            false
+        });
+        self.compute.retain(|_, entry| {
+            if entry.refs > 0 {
+                return true;
+            }
+            entry.handle.destroy(device);
+            evicted += 1;
+            false
+        });
+        evicted
+    }
+
+    /// Destroy every cached pipeline. Every reference must have been
+    /// released first; debug builds panic otherwise, since the handles
+    /// still held would become invalid.
+    pub fn clear(&mut self, device: &Device) {
+        debug_assert!(
+            self.graphics.values().all(|e| e.refs == 0) && self.compute.values().all(|e| e.refs == 0),
+            "PipelineCache::clear with pipelines still referenced"
+        );
+        for (_, mut entry) in self.graphics.drain() {
+            entry.handle.destroy(device);
+        }
+        for (_, mut entry) in self.compute.drain() {
+            entry.handle.destroy(device);
+        }
+    }
+
+    /// Number of cached graphics and compute pipelines.
+    pub fn len(&self) -> usize {
+        self.graphics.len() + self.compute.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+fn release<'a, T: PartialEq + 'a>(entries: impl Iterator<Item = &'a mut CacheEntry<T>>, handle: T) -> bool {
+    for entry in entries {
+        if entry.handle == handle {
+            if entry.refs == 0 {
+                return false;
+            }
+            entry.refs -= 1;
+            return true;
+        }
+    }
+    false
+}
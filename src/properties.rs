@@ -1,3 +1,5 @@
+use std::ffi::{c_char, CString};
+
 use sdl3_sys as sys;
 
 pub use sys::properties::SDL_PropertiesID;
@@ -6,8 +8,16 @@ pub use sys::properties::SDL_PropertiesID;
 /// Get a string property from properties by key
 pub fn get_string_property(props: SDL_PropertiesID, key: &str) -> Option<String> {
     let key_cstr = std::ffi::CString::new(key).ok()?;
+    read_string_property(props, key_cstr.as_ptr())
+}
+
+/// [`get_string_property`] for the `SDL_PROP_*` key constants.
+pub(crate) fn read_string_property(props: SDL_PropertiesID, key: *const c_char) -> Option<String> {
+    if props.0 == 0 {
+        return None;
+    }
     let ptr = unsafe {
-        sys::properties::SDL_GetStringProperty(props, key_cstr.as_ptr(), std::ptr::null())
+        sys::properties::SDL_GetStringProperty(props, key, std::ptr::null())
     };
     if ptr.is_null() {
         None
@@ -17,3 +27,40 @@ pub fn get_string_property(props: SDL_PropertiesID, key: &str) -> Option<String>
             .into_owned())
     }
 }
+
+/// `base` with one string property added, for passing e.g. a debug name to
+/// an SDL create call. Without a value it is `base` itself; otherwise a
+/// copy owned by this value and destroyed on drop.
+pub(crate) struct StringProperty {
+    id: SDL_PropertiesID,
+    owned: bool,
+}
+
+impl StringProperty {
+    pub fn new(base: SDL_PropertiesID, key: *const c_char, value: Option<&str>) -> Self {
+        let Some(value) = value else {
+            return Self { id: base, owned: false };
+        };
+        let value = CString::new(value.replace('\0', "")).unwrap_or_default();
+        unsafe {
+            let id = sys::properties::SDL_CreateProperties();
+            if base.0 != 0 {
+                sys::properties::SDL_CopyProperties(base, id);
+            }
+            sys::properties::SDL_SetStringProperty(id, key, value.as_ptr());
+            Self { id, owned: true }
+        }
+    }
+
+    pub fn id(&self) -> SDL_PropertiesID {
+        self.id
+    }
+}
+
+impl Drop for StringProperty {
+    fn drop(&mut self) {
+        if self.owned {
+            unsafe { sys::properties::SDL_DestroyProperties(self.id) }
+        }
+    }
+}
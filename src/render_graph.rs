@@ -0,0 +1,807 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+use crate::device::{
+    ColorTargetInfo, CommandBuffer, ComputePass, CopyPass, DepthStencilTargetInfo, Device, GPUBuffer, RenderPass,
+    SDL_FColor, SDL_GPULoadOp, SDL_GPUStoreOp, StorageBufferReadWriteBinding, StorageTextureReadWriteBinding, Texture,
+    TextureDesc,
+};
+
+/// A texture declared in a [`RenderGraph`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GraphTexture(usize);
+
+/// A buffer declared in a [`RenderGraph`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GraphBuffer(usize);
+
+enum ResourceKind {
+    ImportedTexture(Texture),
+    TransientTexture(TextureDesc),
+    ImportedBuffer(GPUBuffer),
+}
+
+struct ResourceNode {
+    name: String,
+    kind: ResourceKind,
+}
+
+impl ResourceNode {
+    fn is_imported(&self) -> bool {
+        !matches!(self.kind, ResourceKind::TransientTexture(_))
+    }
+}
+
+struct Attachment {
+    texture: usize,
+    clear: Option<SDL_FColor>,
+    resolve: Option<usize>,
+}
+
+struct DepthAttachment {
+    texture: usize,
+    clear: Option<f32>,
+}
+
+type RecordRender<'a> = Box<dyn FnOnce(&RenderPass, &GraphResources) + 'a>;
+type RecordCompute<'a> = Box<dyn FnOnce(&ComputePass, &GraphResources) + 'a>;
+type RecordCopy<'a> = Box<dyn FnOnce(&CopyPass, &GraphResources) + 'a>;
+
+enum Record<'a> {
+    Render(RecordRender<'a>),
+    Compute(RecordCompute<'a>),
+    Copy(RecordCopy<'a>),
+}
+
+/// What a pass declared through its [`PassBuilder`].
+#[derive(Default)]
+struct PassDecl {
+    colors: Vec<Attachment>,
+    depth: Option<DepthAttachment>,
+    storage_textures: Vec<usize>,
+    storage_buffers: Vec<usize>,
+    reads: Vec<usize>,
+    writes: Vec<usize>,
+    side_effects: bool,
+}
+
+struct PassNode<'a> {
+    name: String,
+    decl: PassDecl,
+    record: Record<'a>,
+}
+
+/// Declares the resources one pass reads and writes.
+///
+/// Render targets without a clear value keep their previous contents, so
+/// they count as both read and written.
+pub struct PassBuilder {
+    decl: PassDecl,
+}
+
+impl PassBuilder {
+    fn read(&mut self, index: usize) {
+        if !self.decl.reads.contains(&index) {
+            self.decl.reads.push(index);
+        }
+    }
+
+    fn write(&mut self, index: usize) {
+        if !self.decl.writes.contains(&index) {
+            self.decl.writes.push(index);
+        }
+    }
+
+    /// Render to `texture` as the next color target, clearing it to `clear`
+    /// or keeping its contents if `None`.
+    pub fn color(&mut self, texture: GraphTexture, clear: Option<SDL_FColor>) -> &mut Self {
+        self.color_resolve_opt(texture, clear, None)
+    }
+
+    /// Render to the multisampled `texture` as the next color target and
+    /// resolve it into `resolve`.
+    pub fn color_resolve(&mut self, texture: GraphTexture, clear: Option<SDL_FColor>, resolve: GraphTexture) -> &mut Self {
+        self.color_resolve_opt(texture, clear, Some(resolve))
+    }
+
+    fn color_resolve_opt(&mut self, texture: GraphTexture, clear: Option<SDL_FColor>, resolve: Option<GraphTexture>) -> &mut Self {
+        if clear.is_none() {
+            self.read(texture.0);
+        }
+        self.write(texture.0);
+        if let Some(resolve) = resolve {
+            self.write(resolve.0);
+        }
+        self.decl.colors.push(Attachment { texture: texture.0, clear, resolve: resolve.map(|r| r.0) });
+        self
+    }
+
+    /// Use `texture` as the depth-stencil target, clearing depth to `clear`
+    /// (and stencil to 0) or keeping its contents if `None`.
+    pub fn depth(&mut self, texture: GraphTexture, clear: Option<f32>) -> &mut Self {
+        if clear.is_none() {
+            self.read(texture.0);
+        }
+        self.write(texture.0);
+        self.decl.depth = Some(DepthAttachment { texture: texture.0, clear });
+        self
+    }
+
+    /// Sample or otherwise read `texture` in this pass.
+    pub fn read_texture(&mut self, texture: GraphTexture) -> &mut Self {
+        self.read(texture.0);
+        self
+    }
+
+    /// Read `buffer` in this pass (vertex, index, storage or copy source).
+    pub fn read_buffer(&mut self, buffer: GraphBuffer) -> &mut Self {
+        self.read(buffer.0);
+        self
+    }
+
+    /// Write `texture` outside a render target binding, e.g. as a copy
+    /// destination.
+    pub fn write_texture(&mut self, texture: GraphTexture) -> &mut Self {
+        self.write(texture.0);
+        self
+    }
+
+    /// Write `buffer` outside a storage binding, e.g. as a copy destination.
+    pub fn write_buffer(&mut self, buffer: GraphBuffer) -> &mut Self {
+        self.write(buffer.0);
+        self
+    }
+
+    /// Bind `texture` as a read-write storage texture of a compute pass.
+    /// Also call [`read_texture`](Self::read_texture) if the pass reads its
+    /// previous contents, otherwise they may be discarded.
+    pub fn storage_texture(&mut self, texture: GraphTexture) -> &mut Self {
+        self.write(texture.0);
+        self.decl.storage_textures.push(texture.0);
+        self
+    }
+
+    /// Bind `buffer` as a read-write storage buffer of a compute pass.
+    /// Also call [`read_buffer`](Self::read_buffer) if the pass reads its
+    /// previous contents, otherwise they may be discarded.
+    pub fn storage_buffer(&mut self, buffer: GraphBuffer) -> &mut Self {
+        self.write(buffer.0);
+        self.decl.storage_buffers.push(buffer.0);
+        self
+    }
+
+    /// Never cull this pass, e.g. because it downloads data.
+    pub fn side_effects(&mut self) -> &mut Self {
+        self.decl.side_effects = true;
+        self
+    }
+}
+
+/// The actual textures and buffers behind graph resources, passed to each
+/// pass while it records.
+pub struct GraphResources {
+    textures: Vec<Texture>,
+    buffers: Vec<GPUBuffer>,
+}
+
+impl GraphResources {
+    pub fn texture(&self, texture: GraphTexture) -> Texture {
+        self.textures[texture.0]
+    }
+
+    pub fn buffer(&self, buffer: GraphBuffer) -> GPUBuffer {
+        self.buffers[buffer.0]
+    }
+}
+
+/// Transient textures of one execution. Textures whose last pass has run
+/// are reused by later passes; everything goes back to the device's texture
+/// pool at the end.
+#[derive(Default)]
+struct TransientTextures {
+    free: HashMap<TextureDesc, Vec<Texture>>,
+}
+
+impl TransientTextures {
+    fn acquire(&mut self, device: &Device, desc: &TextureDesc) -> Result<Texture, &'static str> {
+        match self.free.get_mut(desc).and_then(Vec::pop) {
+            Some(texture) => Ok(texture),
+            None => device.acquire_pooled_texture(desc),
+        }
+    }
+
+    fn release(&mut self, desc: TextureDesc, texture: Texture) {
+        self.free.entry(desc).or_default().push(texture);
+    }
+
+    fn return_to_pool(self, device: &Device) {
+        for texture in self.free.into_values().flatten() {
+            device.release_pooled_texture(texture);
+        }
+    }
+}
+
+struct TargetOps {
+    load_op: SDL_GPULoadOp,
+    store_op: SDL_GPUStoreOp,
+    cycle: bool,
+}
+
+/// The result of culling and choosing load/store ops.
+struct Compiled {
+    live: Vec<bool>,
+    /// Live passes, in declaration order.
+    order: Vec<usize>,
+    /// Per pass: ops for each color target, in declaration order.
+    color_ops: Vec<Vec<TargetOps>>,
+    depth_ops: Vec<Option<TargetOps>>,
+    storage_texture_cycle: Vec<Vec<bool>>,
+    storage_buffer_cycle: Vec<Vec<bool>>,
+    /// Per resource: first and last position in `order` that touches it.
+    lifetimes: Vec<Option<(usize, usize)>>,
+}
+
+/// A frame described as passes over declared resources.
+///
+/// Passes are declared in the order their data flows: a pass reading a
+/// resource sees the writes of the passes declared before it, and passes
+/// run in declaration order. On [`execute`](Self::execute) the graph culls
+/// passes whose results are never used, chooses load/store ops and cycling
+/// for every target, takes transient textures from the device's texture
+/// pool (sharing them between passes whose lifetimes don't overlap) and
+/// records the remaining passes into one command buffer.
+///
+/// Imported resources outlive the graph, so passes writing them are kept.
+pub struct RenderGraph<'a> {
+    resources: Vec<ResourceNode>,
+    passes: Vec<PassNode<'a>>,
+}
+
+impl Default for RenderGraph<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> RenderGraph<'a> {
+    pub fn new() -> Self {
+        Self { resources: Vec::new(), passes: Vec::new() }
+    }
+
+    /// Use an existing texture, e.g. [`Texture::SWAPCHAIN`], in the graph.
+    pub fn import_texture(&mut self, name: &str, texture: Texture) -> GraphTexture {
+        self.resources.push(ResourceNode { name: name.to_string(), kind: ResourceKind::ImportedTexture(texture) });
+        GraphTexture(self.resources.len() - 1)
+    }
+
+    /// Use an existing buffer in the graph.
+    pub fn import_buffer(&mut self, name: &str, buffer: GPUBuffer) -> GraphBuffer {
+        self.resources.push(ResourceNode { name: name.to_string(), kind: ResourceKind::ImportedBuffer(buffer) });
+        GraphBuffer(self.resources.len() - 1)
+    }
+
+    /// Declare a texture that only lives for this graph's execution.
+    pub fn create_texture(&mut self, name: &str, desc: TextureDesc) -> GraphTexture {
+        self.resources.push(ResourceNode { name: name.to_string(), kind: ResourceKind::TransientTexture(desc) });
+        GraphTexture(self.resources.len() - 1)
+    }
+
+    fn add_pass(&mut self, name: &str, setup: impl FnOnce(&mut PassBuilder), record: Record<'a>) {
+        let mut builder = PassBuilder { decl: PassDecl::default() };
+        setup(&mut builder);
+        self.passes.push(PassNode { name: name.to_string(), decl: builder.decl, record });
+    }
+
+    /// Add a render pass. `setup` declares its targets and inputs; `record`
+    /// runs inside the pass when the graph executes.
+    pub fn add_render_pass(
+        &mut self,
+        name: &str,
+        setup: impl FnOnce(&mut PassBuilder),
+        record: impl FnOnce(&RenderPass, &GraphResources) + 'a,
+    ) {
+        self.add_pass(name, setup, Record::Render(Box::new(record)));
+    }
+
+    /// Add a compute pass. `setup` declares its storage bindings and inputs;
+    /// `record` runs inside the pass when the graph executes.
+    pub fn add_compute_pass(
+        &mut self,
+        name: &str,
+        setup: impl FnOnce(&mut PassBuilder),
+        record: impl FnOnce(&ComputePass, &GraphResources) + 'a,
+    ) {
+        self.add_pass(name, setup, Record::Compute(Box::new(record)));
+    }
+
+    /// Add a copy pass. `setup` declares what it reads and writes; `record`
+    /// runs inside the pass when the graph executes.
+    pub fn add_copy_pass(
+        &mut self,
+        name: &str,
+        setup: impl FnOnce(&mut PassBuilder),
+        record: impl FnOnce(&CopyPass, &GraphResources) + 'a,
+    ) {
+        self.add_pass(name, setup, Record::Copy(Box::new(record)));
+    }
+
+    fn compile(&self) -> Compiled {
+        let n = self.passes.len();
+
+        // Cull: walk backwards tracking which resources still have readers.
+        let mut needed: Vec<bool> = self.resources.iter().map(ResourceNode::is_imported).collect();
+        let mut live = vec![false; n];
+        for (i, pass) in self.passes.iter().enumerate().rev() {
+            let decl = &pass.decl;
+            if !decl.side_effects && !decl.writes.iter().any(|&r| needed[r]) {
+                continue;
+            }
+            live[i] = true;
+            for &r in &decl.writes {
+                if !decl.reads.contains(&r) {
+                    needed[r] = self.resources[r].is_imported();
+                }
+            }
+            for &r in &decl.reads {
+                needed[r] = true;
+            }
+        }
+
+        // A pass only depends on passes declared before it, so declaration
+        // order is already a valid order.
+        let order: Vec<usize> = (0..n).filter(|&i| live[i]).collect();
+
+        let mut lifetimes: Vec<Option<(usize, usize)>> = vec![None; self.resources.len()];
+        for (pos, &i) in order.iter().enumerate() {
+            let decl = &self.passes[i].decl;
+            for &r in decl.reads.iter().chain(&decl.writes) {
+                let span = lifetimes[r].get_or_insert((pos, pos));
+                span.1 = pos;
+            }
+        }
+        let read_later = |r: usize, pos: usize| {
+            order[pos + 1..].iter().any(|&i| self.passes[i].decl.reads.contains(&r))
+        };
+
+        let mut written = vec![false; self.resources.len()];
+        let mut color_ops: Vec<Vec<TargetOps>> = (0..n).map(|_| Vec::new()).collect();
+        let mut depth_ops: Vec<Option<TargetOps>> = (0..n).map(|_| None).collect();
+        let mut storage_texture_cycle = vec![Vec::new(); n];
+        let mut storage_buffer_cycle = vec![Vec::new(); n];
+        for (pos, &i) in order.iter().enumerate() {
+            let decl = &self.passes[i].decl;
+            let keep = |r: usize| self.resources[r].is_imported() || read_later(r, pos);
+            let load = |r: usize, clear: bool| {
+                if clear {
+                    SDL_GPULoadOp::CLEAR
+                } else if written[r] || self.resources[r].is_imported() {
+                    SDL_GPULoadOp::LOAD
+                } else {
+                    SDL_GPULoadOp::DONT_CARE
+                }
+            };
+            for color in &decl.colors {
+                let load_op = load(color.texture, color.clear.is_some());
+                let store_op = match (color.resolve.is_some(), keep(color.texture)) {
+                    (false, true) => SDL_GPUStoreOp::STORE,
+                    (false, false) => SDL_GPUStoreOp::DONT_CARE,
+                    (true, true) => SDL_GPUStoreOp::RESOLVE_AND_STORE,
+                    (true, false) => SDL_GPUStoreOp::RESOLVE,
+                };
+                color_ops[i].push(TargetOps { load_op, store_op, cycle: load_op != SDL_GPULoadOp::LOAD });
+            }
+            if let Some(depth) = &decl.depth {
+                let load_op = load(depth.texture, depth.clear.is_some());
+                let store_op = if keep(depth.texture) { SDL_GPUStoreOp::STORE } else { SDL_GPUStoreOp::DONT_CARE };
+                depth_ops[i] = Some(TargetOps { load_op, store_op, cycle: load_op != SDL_GPULoadOp::LOAD });
+            }
+            let discardable = |r: &usize| !written[*r] && !decl.reads.contains(r);
+            storage_texture_cycle[i] = decl.storage_textures.iter().map(discardable).collect();
+            storage_buffer_cycle[i] = decl.storage_buffers.iter().map(discardable).collect();
+            for &r in &decl.writes {
+                written[r] = true;
+            }
+        }
+
+        Compiled { live, order, color_ops, depth_ops, storage_texture_cycle, storage_buffer_cycle, lifetimes }
+    }
+
+    /// Dump the compiled graph in Graphviz dot format. Culled passes are
+    /// dashed; edges to render targets are labelled with their load/store ops.
+    pub fn to_graphviz(&self) -> String {
+        fn op_name(load: SDL_GPULoadOp, store: SDL_GPUStoreOp) -> String {
+            let load = match load {
+                SDL_GPULoadOp::CLEAR => "clear",
+                SDL_GPULoadOp::LOAD => "load",
+                _ => "dont_care",
+            };
+            let store = match store {
+                SDL_GPUStoreOp::STORE => "store",
+                SDL_GPUStoreOp::RESOLVE => "resolve",
+                SDL_GPUStoreOp::RESOLVE_AND_STORE => "resolve_and_store",
+                _ => "dont_care",
+            };
+            format!("{load}/{store}")
+        }
+
+        let compiled = self.compile();
+        let mut dot = String::from("digraph render_graph {\n    rankdir=LR;\n");
+        for (r, res) in self.resources.iter().enumerate() {
+            let detail = match &res.kind {
+                ResourceKind::ImportedTexture(_) => "imported texture".to_string(),
+                ResourceKind::ImportedBuffer(_) => "imported buffer".to_string(),
+                ResourceKind::TransientTexture(desc) => format!("{}x{} transient", desc.width, desc.height),
+            };
+            let _ = writeln!(dot, "    r{r} [shape=ellipse, label=\"{}\\n{detail}\"];", escape(&res.name));
+        }
+        for (i, pass) in self.passes.iter().enumerate() {
+            let label = match compiled.order.iter().position(|&p| p == i) {
+                Some(pos) => format!("#{pos} {}", escape(&pass.name)),
+                None => format!("{} (culled)", escape(&pass.name)),
+            };
+            let style = if compiled.live[i] { "solid" } else { "dashed" };
+            let _ = writeln!(dot, "    p{i} [shape=box, style={style}, label=\"{label}\"];");
+        }
+        for (i, pass) in self.passes.iter().enumerate() {
+            let decl = &pass.decl;
+            for &r in &decl.reads {
+                let _ = writeln!(dot, "    r{r} -> p{i};");
+            }
+            for &r in &decl.writes {
+                let color = decl.colors.iter().position(|c| c.texture == r);
+                let label = match (color, &decl.depth) {
+                    (Some(c), _) if compiled.live[i] => {
+                        let ops = &compiled.color_ops[i][c];
+                        op_name(ops.load_op, ops.store_op)
+                    }
+                    (None, Some(depth)) if depth.texture == r && compiled.live[i] => {
+                        let ops = compiled.depth_ops[i].as_ref().unwrap();
+                        op_name(ops.load_op, ops.store_op)
+                    }
+                    _ => String::new(),
+                };
+                if label.is_empty() {
+                    let _ = writeln!(dot, "    p{i} -> r{r};");
+                } else {
+                    let _ = writeln!(dot, "    p{i} -> r{r} [label=\"{label}\"];");
+                }
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Compile the graph and record every live pass into `cmd`, taking
+    /// transient textures from the device's texture pool.
+    pub fn execute(self, cmd: &mut CommandBuffer) -> Result<(), &'static str> {
+        let compiled = self.compile();
+        let device = cmd.device();
+        let mut transients = TransientTextures::default();
+
+        let mut resources = GraphResources {
+            textures: self
+                .resources
+                .iter()
+                .map(|r| match r.kind {
+                    ResourceKind::ImportedTexture(t) => t,
+                    _ => Texture::NONE,
+                })
+                .collect(),
+            buffers: self
+                .resources
+                .iter()
+                .map(|r| match r.kind {
+                    ResourceKind::ImportedBuffer(b) => b,
+                    _ => GPUBuffer::default(),
+                })
+                .collect(),
+        };
+
+        let mut passes: Vec<Option<PassNode>> = self.passes.into_iter().map(Some).collect();
+        let mut result = Ok(());
+        for (pos, &i) in compiled.order.iter().enumerate() {
+            for (r, res) in self.resources.iter().enumerate() {
+                if let (ResourceKind::TransientTexture(desc), Some((first, _))) = (&res.kind, compiled.lifetimes[r])
+                    && first == pos
+                {
+                    match transients.acquire(device, desc) {
+                        Ok(texture) => resources.textures[r] = texture,
+                        Err(e) => result = Err(e),
+                    }
+                }
+            }
+            if result.is_err() {
+                break;
+            }
+
+            let pass = passes[i].take().unwrap();
+            if let Err(e) = record_pass(cmd, pass, &compiled, i, &resources) {
+                result = Err(e);
+                break;
+            }
+
+            for (r, res) in self.resources.iter().enumerate() {
+                if let (ResourceKind::TransientTexture(desc), Some((_, last))) = (&res.kind, compiled.lifetimes[r])
+                    && last == pos
+                {
+                    transients.release(*desc, std::mem::replace(&mut resources.textures[r], Texture::NONE));
+                }
+            }
+        }
+
+        // Return anything still held after an early exit.
+        for (r, res) in self.resources.iter().enumerate() {
+            if let ResourceKind::TransientTexture(desc) = &res.kind
+                && resources.textures[r] != Texture::NONE
+            {
+                transients.release(*desc, resources.textures[r]);
+            }
+        }
+        transients.return_to_pool(device);
+        result
+    }
+}
+
+fn record_pass(
+    cmd: &mut CommandBuffer,
+    pass: PassNode,
+    compiled: &Compiled,
+    index: usize,
+    resources: &GraphResources,
+) -> Result<(), &'static str> {
+    let decl = &pass.decl;
+    match pass.record {
+        Record::Render(record) => {
+            let colors: Vec<ColorTargetInfo> = decl
+                .colors
+                .iter()
+                .zip(&compiled.color_ops[index])
+                .map(|(color, ops)| {
+                    let mut info = ColorTargetInfo::new(resources.textures[color.texture]);
+                    if let Some(clear) = color.clear {
+                        info.clear_color = clear;
+                    }
+                    info.load_op = ops.load_op;
+                    info.store_op = ops.store_op;
+                    info.cycle = ops.cycle;
+                    if let Some(resolve) = color.resolve {
+                        info.resolve_texture = Some(resources.textures[resolve]);
+                        info.cycle_resolve_texture = true;
+                    }
+                    info
+                })
+                .collect();
+            let depth = decl.depth.as_ref().map(|depth| {
+                let ops = compiled.depth_ops[index].as_ref().unwrap();
+                let mut info = DepthStencilTargetInfo::new(resources.textures[depth.texture]);
+                if let Some(clear) = depth.clear {
+                    info.clear_depth = clear;
+                }
+                info.load_op = ops.load_op;
+                info.store_op = ops.store_op;
+                info.stencil_load_op = ops.load_op;
+                info.stencil_store_op = ops.store_op;
+                info.cycle = ops.cycle;
+                info
+            });
+            let render_pass = cmd.begin_render_pass(&colors, depth.as_ref())?;
+            record(&render_pass, resources);
+        }
+        Record::Compute(record) => {
+            let textures: Vec<StorageTextureReadWriteBinding> = decl
+                .storage_textures
+                .iter()
+                .zip(&compiled.storage_texture_cycle[index])
+                .map(|(&r, &cycle)| StorageTextureReadWriteBinding {
+                    texture: resources.textures[r],
+                    mip_level: 0,
+                    layer: 0,
+                    cycle,
+                })
+                .collect();
+            let buffers: Vec<StorageBufferReadWriteBinding> = decl
+                .storage_buffers
+                .iter()
+                .zip(&compiled.storage_buffer_cycle[index])
+                .map(|(&r, &cycle)| StorageBufferReadWriteBinding { buffer: resources.buffers[r], cycle })
+                .collect();
+            let compute_pass = cmd.begin_compute_pass(&textures, &buffers)?;
+            record(&compute_pass, resources);
+        }
+        Record::Copy(record) => {
+            let copy_pass = cmd.begin_copy_pass()?;
+            record(&copy_pass, resources);
+        }
+    }
+    Ok(())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::device::{SDL_GPUSampleCount, SDL_GPUTextureUsageFlags};
+    use crate::null_backend::tests::{COLOR_FORMAT, null_device};
+
+    const BLACK: Option<SDL_FColor> = Some(SDL_FColor { r: 0.0, g: 0.0, b: 0.0, a: 1.0 });
+
+    fn desc() -> TextureDesc {
+        let usage = SDL_GPUTextureUsageFlags::COLOR_TARGET | SDL_GPUTextureUsageFlags::SAMPLER;
+        TextureDesc::new(COLOR_FORMAT, 64, 64, usage)
+    }
+
+    fn render(graph: &mut RenderGraph, name: &str, setup: impl FnOnce(&mut PassBuilder)) {
+        graph.add_render_pass(name, setup, |_, _| {});
+    }
+
+    fn color_ops(compiled: &Compiled, pass: usize) -> Vec<(SDL_GPULoadOp, SDL_GPUStoreOp)> {
+        compiled.color_ops[pass].iter().map(|ops| (ops.load_op, ops.store_op)).collect()
+    }
+
+    #[test]
+    fn passes_without_used_results_are_culled() {
+        let mut graph = RenderGraph::new();
+        let target = graph.import_texture("target", Texture(0));
+        let unused = graph.create_texture("unused", desc());
+        let feeds_unused = graph.create_texture("feeds unused", desc());
+        let shadow = graph.create_texture("shadow", desc());
+        render(&mut graph, "shadow", |b| {
+            b.color(shadow, BLACK);
+        });
+        render(&mut graph, "feeds unused", |b| {
+            b.color(feeds_unused, BLACK);
+        });
+        render(&mut graph, "unused", |b| {
+            b.read_texture(feeds_unused).color(unused, BLACK);
+        });
+        render(&mut graph, "main", |b| {
+            b.read_texture(shadow).color(target, BLACK);
+        });
+        render(&mut graph, "readback", |b| {
+            b.read_texture(unused).side_effects();
+        });
+
+        let compiled = graph.compile();
+        assert_eq!(compiled.live, [true, true, true, true, true]);
+
+        graph.passes.pop();
+        let compiled = graph.compile();
+        assert_eq!(compiled.live, [true, false, false, true]);
+        assert_eq!(compiled.order, [0, 3]);
+        assert_eq!(compiled.lifetimes[feeds_unused.0], None);
+        assert!(graph.to_graphviz().contains("unused (culled)"));
+    }
+
+    #[test]
+    fn load_ops_follow_earlier_writes() {
+        let mut graph = RenderGraph::new();
+        let target = graph.import_texture("target", Texture(0));
+        let scene = graph.create_texture("scene", desc());
+        let overlay = graph.create_texture("overlay", desc());
+        render(&mut graph, "scene", |b| {
+            b.color(scene, BLACK).color(overlay, None);
+        });
+        render(&mut graph, "decals", |b| {
+            b.color(scene, None);
+        });
+        render(&mut graph, "compose", |b| {
+            b.read_texture(scene).read_texture(overlay).color(target, None);
+        });
+
+        let compiled = graph.compile();
+        assert!(color_ops(&compiled, 0) == [
+            (SDL_GPULoadOp::CLEAR, SDL_GPUStoreOp::STORE),
+            (SDL_GPULoadOp::DONT_CARE, SDL_GPUStoreOp::STORE),
+        ]);
+        assert!(color_ops(&compiled, 1) == [(SDL_GPULoadOp::LOAD, SDL_GPUStoreOp::STORE)]);
+        assert!(color_ops(&compiled, 2) == [(SDL_GPULoadOp::LOAD, SDL_GPUStoreOp::STORE)]);
+        let cycles: Vec<bool> = (0..3).flat_map(|i| compiled.color_ops[i].iter().map(|ops| ops.cycle)).collect();
+        assert_eq!(cycles, [true, true, false, false]);
+    }
+
+    #[test]
+    fn targets_not_read_later_are_not_stored() {
+        let mut graph = RenderGraph::new();
+        let target = graph.import_texture("target", Texture(0));
+        let scratch = graph.create_texture("scratch", desc());
+        let depth = graph.create_texture("depth", desc());
+        render(&mut graph, "main", |b| {
+            b.color(target, BLACK).color(scratch, BLACK).depth(depth, Some(1.0));
+        });
+
+        let compiled = graph.compile();
+        assert!(color_ops(&compiled, 0) == [
+            (SDL_GPULoadOp::CLEAR, SDL_GPUStoreOp::STORE),
+            (SDL_GPULoadOp::CLEAR, SDL_GPUStoreOp::DONT_CARE),
+        ]);
+        let depth_ops = compiled.depth_ops[0].as_ref().unwrap();
+        assert!(depth_ops.load_op == SDL_GPULoadOp::CLEAR && depth_ops.store_op == SDL_GPUStoreOp::DONT_CARE);
+    }
+
+    #[test]
+    fn multisampled_targets_resolve() {
+        let msaa_desc = TextureDesc { sample_count: SDL_GPUSampleCount::_4, ..desc() };
+        let mut graph = RenderGraph::new();
+        let target = graph.import_texture("target", Texture(0));
+        let resolved = graph.import_texture("resolved", Texture(1));
+        let msaa = graph.create_texture("msaa", msaa_desc);
+        let kept = graph.create_texture("kept msaa", msaa_desc);
+        render(&mut graph, "resolve only", |b| {
+            b.color_resolve(msaa, BLACK, target);
+        });
+        render(&mut graph, "resolve and keep", |b| {
+            b.color_resolve(kept, BLACK, resolved);
+        });
+        render(&mut graph, "more samples", |b| {
+            b.color(kept, None);
+        });
+        render(&mut graph, "sample", |b| {
+            b.read_texture(kept).side_effects();
+        });
+
+        let compiled = graph.compile();
+        assert!(color_ops(&compiled, 0) == [(SDL_GPULoadOp::CLEAR, SDL_GPUStoreOp::RESOLVE)]);
+        assert!(color_ops(&compiled, 1) == [(SDL_GPULoadOp::CLEAR, SDL_GPUStoreOp::RESOLVE_AND_STORE)]);
+        assert!(color_ops(&compiled, 2) == [(SDL_GPULoadOp::LOAD, SDL_GPUStoreOp::STORE)]);
+    }
+
+    #[test]
+    fn storage_is_cycled_unless_its_contents_are_read() {
+        let mut graph = RenderGraph::new();
+        let fresh = graph.import_buffer("fresh", GPUBuffer::default());
+        let accumulated = graph.import_buffer("accumulated", GPUBuffer::default());
+        graph.add_compute_pass("fill", |b| {
+            b.storage_buffer(fresh);
+            b.read_buffer(accumulated).storage_buffer(accumulated);
+        }, |_, _| {});
+        graph.add_compute_pass("refill", |b| {
+            b.storage_buffer(fresh);
+        }, |_, _| {});
+
+        let compiled = graph.compile();
+        assert_eq!(compiled.storage_buffer_cycle, [vec![true, false], vec![false]]);
+    }
+
+    #[test]
+    fn transient_textures_are_shared_between_disjoint_lifetimes() {
+        let (device, log) = null_device();
+        let target = device.create_texture(&TextureDesc::new(COLOR_FORMAT, 64, 64, SDL_GPUTextureUsageFlags::COLOR_TARGET).to_create_info()).unwrap();
+        let (first, second, overlapping) = (Cell::new(Texture::NONE), Cell::new(Texture::NONE), Cell::new(Texture::NONE));
+
+        let mut graph = RenderGraph::new();
+        let out = graph.import_texture("target", target);
+        let a = graph.create_texture("a", desc());
+        let b = graph.create_texture("b", desc());
+        let c = graph.create_texture("c", desc());
+        graph.add_render_pass("draw a", |p| {
+            p.color(a, BLACK);
+        }, |_, res| first.set(res.texture(a)));
+        graph.add_render_pass("use a", |p| {
+            p.read_texture(a).color(out, None);
+        }, |_, _| {});
+        graph.add_render_pass("draw b and c", |p| {
+            p.color(b, BLACK).color(c, BLACK);
+        }, |_, res| {
+            second.set(res.texture(b));
+            overlapping.set(res.texture(c));
+        });
+        graph.add_render_pass("use b and c", |p| {
+            p.read_texture(b).read_texture(c).color(out, None);
+        }, |_, _| {});
+
+        let mut cmd = device.acquire_command_buffer().unwrap();
+        graph.execute(&mut cmd).unwrap();
+        cmd.submit().unwrap();
+
+        assert_eq!(first.get(), second.get());
+        assert_ne!(second.get(), overlapping.get());
+        let render_passes = log.commands().iter().filter(|c| matches!(c, crate::null_backend::Command::BeginRenderPass { .. })).count();
+        assert_eq!(render_passes, 4);
+        assert_eq!(device.texture_pool_stats().textures, 2);
+    }
+}
@@ -0,0 +1,213 @@
+use crate::device::{
+    ColorTargetInfo, DepthStencilTargetInfo, Device, SDL_FColor, SDL_GPULoadOp, SDL_GPUSampleCount, SDL_GPUStoreOp,
+    SDL_GPUTextureFormat, SDL_GPUTextureUsageFlags, Texture, TextureDesc,
+};
+
+/// How big a [`RenderTarget`] is.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TargetSize {
+    /// The swapchain size times a factor, e.g. `0.5` for half resolution.
+    Scale(f32),
+    /// A fixed size, independent of the swapchain.
+    Fixed(u32, u32),
+}
+
+impl TargetSize {
+    fn resolve(self, swapchain_width: u32, swapchain_height: u32) -> (u32, u32) {
+        match self {
+            TargetSize::Scale(s) => (
+                ((swapchain_width as f32 * s).round() as u32).max(1),
+                ((swapchain_height as f32 * s).round() as u32).max(1),
+            ),
+            TargetSize::Fixed(w, h) => (w, h),
+        }
+    }
+}
+
+/// What a [`RenderTarget`] holds.
+#[derive(Clone, Copy)]
+pub struct RenderTargetDesc {
+    pub size: TargetSize,
+    pub color_format: SDL_GPUTextureFormat,
+    /// Usage of the single-sample color texture (the resolve texture when
+    /// multisampled). `COLOR_TARGET` is always added.
+    pub color_usage: SDL_GPUTextureUsageFlags,
+    /// Format of an optional depth-stencil attachment.
+    pub depth_format: Option<SDL_GPUTextureFormat>,
+    pub sample_count: SDL_GPUSampleCount,
+    /// When multisampled, also allocate a single-sample texture to resolve
+    /// into. Turn off when resolving straight into the swapchain.
+    pub resolve: bool,
+}
+
+impl RenderTargetDesc {
+    /// A swapchain-sized, single-sample, sampleable color target.
+    pub fn new(color_format: SDL_GPUTextureFormat) -> Self {
+        Self {
+            size: TargetSize::Scale(1.0),
+            color_format,
+            color_usage: SDL_GPUTextureUsageFlags::COLOR_TARGET | SDL_GPUTextureUsageFlags::SAMPLER,
+            depth_format: None,
+            sample_count: SDL_GPUSampleCount::_1,
+            resolve: true,
+        }
+    }
+
+    fn is_multisampled(&self) -> bool {
+        self.sample_count != SDL_GPUSampleCount::_1
+    }
+}
+
+/// Color, optional depth-stencil and optional MSAA resolve attachments that
+/// follow the swapchain size.
+///
+/// Textures are created on the first [`update`](Self::update) and recreated
+/// only when the computed size changes.
+pub struct RenderTarget {
+    desc: RenderTargetDesc,
+    color: Texture,
+    resolve: Texture,
+    depth: Texture,
+    size: (u32, u32),
+}
+
+impl RenderTarget {
+    pub fn new(desc: RenderTargetDesc) -> Self {
+        Self {
+            desc,
+            color: Texture::NONE,
+            resolve: Texture::NONE,
+            depth: Texture::NONE,
+            size: (0, 0),
+        }
+    }
+
+    /// Resize to match the swapchain texture of the device's own window.
+    /// Call after `acquire_swapchain_texture` returned a texture. Returns
+    /// true if the textures were recreated, in which case handles taken
+    /// earlier are stale.
+    pub fn update(&mut self, device: &Device) -> Result<bool, &'static str> {
+        self.update_for(device, Texture::SWAPCHAIN)
+    }
+
+    /// Like [`update`](Self::update), for the acquired swapchain texture
+    /// `swapchain` of any claimed window.
+    pub fn update_for(&mut self, device: &Device, swapchain: Texture) -> Result<bool, &'static str> {
+        let (width, height) = device.get_texture_res(swapchain);
+        self.update_for_size(device, width, height)
+    }
+
+    /// Resize for a swapchain of `width` x `height`. Returns true if the
+    /// textures were recreated.
+    pub fn update_for_size(&mut self, device: &Device, width: u32, height: u32) -> Result<bool, &'static str> {
+        let size = self.desc.size.resolve(width, height);
+        if size == self.size && self.color.is_valid() {
+            return Ok(false);
+        }
+        self.destroy(device);
+
+        let desc = &self.desc;
+        let (w, h) = size;
+        let single_usage = desc.color_usage | SDL_GPUTextureUsageFlags::COLOR_TARGET;
+        if desc.is_multisampled() {
+            self.color = device.create_texture(&TextureDesc {
+                sample_count: desc.sample_count,
+                ..TextureDesc::new(desc.color_format, w, h, SDL_GPUTextureUsageFlags::COLOR_TARGET)
+            }.to_create_info())?;
+            if desc.resolve {
+                self.resolve = device.create_texture(&TextureDesc::new(desc.color_format, w, h, single_usage).to_create_info())?;
+            }
+        } else {
+            self.color = device.create_texture(&TextureDesc::new(desc.color_format, w, h, single_usage).to_create_info())?;
+        }
+        if let Some(format) = desc.depth_format {
+            self.depth = device.create_texture(&TextureDesc {
+                sample_count: desc.sample_count,
+                ..TextureDesc::new(format, w, h, SDL_GPUTextureUsageFlags::DEPTH_STENCIL_TARGET)
+            }.to_create_info())?;
+        }
+        self.size = size;
+        Ok(true)
+    }
+
+    /// Current size in pixels, `(0, 0)` before the first update.
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// The single-sample result to sample from: the resolve texture when
+    /// multisampled, otherwise the color texture.
+    pub fn texture(&self) -> Texture {
+        if self.desc.is_multisampled() { self.resolve } else { self.color }
+    }
+
+    /// The texture rendered into, multisampled or not.
+    pub fn color_texture(&self) -> Texture {
+        self.color
+    }
+
+    pub fn depth_texture(&self) -> Option<Texture> {
+        self.depth.is_valid().then_some(self.depth)
+    }
+
+    /// Color target info clearing to `clear` (or loading if `None`), and
+    /// resolving into the resolve texture when multisampled.
+    pub fn color_target(&self, clear: Option<SDL_FColor>) -> ColorTargetInfo {
+        let resolve = self.resolve.is_valid().then_some(self.resolve);
+        self.color_target_resolving_into(resolve, clear)
+    }
+
+    /// Like [`color_target`](Self::color_target), but resolving into
+    /// `resolve` instead, e.g. [`Texture::SWAPCHAIN`].
+    pub fn color_target_resolving_into(&self, resolve: Option<Texture>, clear: Option<SDL_FColor>) -> ColorTargetInfo {
+        let mut info = ColorTargetInfo::new(self.color);
+        match clear {
+            Some(color) => {
+                info.clear_color = color;
+                info.load_op = SDL_GPULoadOp::CLEAR;
+                info.cycle = true;
+            }
+            None => info.load_op = SDL_GPULoadOp::LOAD,
+        }
+        info.store_op = SDL_GPUStoreOp::STORE;
+        if let (true, Some(resolve)) = (self.desc.is_multisampled(), resolve) {
+            // Set RESOLVE_AND_STORE instead if a later pass loads the multisampled texture.
+            info.store_op = SDL_GPUStoreOp::RESOLVE;
+            info.resolve_texture = Some(resolve);
+            info.cycle_resolve_texture = true;
+        }
+        info
+    }
+
+    /// Depth-stencil target info clearing depth to `clear` (or loading if
+    /// `None`), or `None` without a depth attachment.
+    pub fn depth_target(&self, clear: Option<f32>) -> Option<DepthStencilTargetInfo> {
+        let depth = self.depth_texture()?;
+        let mut info = DepthStencilTargetInfo::new(depth);
+        match clear {
+            Some(value) => {
+                info.clear_depth = value;
+                info.load_op = SDL_GPULoadOp::CLEAR;
+                info.stencil_load_op = SDL_GPULoadOp::CLEAR;
+                info.cycle = true;
+            }
+            None => {
+                info.load_op = SDL_GPULoadOp::LOAD;
+                info.stencil_load_op = SDL_GPULoadOp::LOAD;
+            }
+        }
+        info.store_op = SDL_GPUStoreOp::STORE;
+        info.stencil_store_op = SDL_GPUStoreOp::STORE;
+        Some(info)
+    }
+
+    /// Destroy the textures. The target recreates them on the next update.
+    pub fn destroy(&mut self, device: &Device) {
+        for texture in [&mut self.color, &mut self.resolve, &mut self.depth] {
+            if texture.is_valid() {
+                texture.destroy(device);
+            }
+        }
+        self.size = (0, 0);
+    }
+}
@@ -0,0 +1,61 @@
+use std::path::Path;
+
+use sdl3_sys::gpu;
+
+use crate::device::{Device, FenceWaiter, TextureDownload};
+use crate::image::Image;
+
+/// A swapchain readback recorded by
+/// [`CommandBuffer::capture_swapchain`](crate::device::CommandBuffer::capture_swapchain).
+///
+/// The pixels are available once the command buffer that recorded the
+/// capture has been submitted and has completed on the GPU.
+pub struct PendingScreenshot<'a> {
+    device: &'a Device,
+    transfer: *mut gpu::SDL_GPUTransferBuffer,
+    download: TextureDownload,
+    fence: FenceWaiter,
+}
+
+impl<'a> PendingScreenshot<'a> {
+    pub(crate) fn new(
+        device: &'a Device,
+        transfer: *mut gpu::SDL_GPUTransferBuffer,
+        download: TextureDownload,
+        fence: FenceWaiter,
+    ) -> Self {
+        Self { device, transfer, download, fence }
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.download.width, self.download.height)
+    }
+
+    /// Whether the capturing command buffer has completed, so that
+    /// [`wait`](Self::wait) returns without blocking.
+    pub fn is_ready(&self) -> bool {
+        self.fence.get().is_some_and(|fence| fence.is_signaled())
+    }
+
+    /// Wait for the capturing command buffer and convert the pixels to an
+    /// 8-bit sRGB image. Fails if the command buffer was dropped without
+    /// being submitted.
+    pub fn wait(self) -> Result<Image, &'static str> {
+        let fence = self.fence.get().ok_or("the capturing command buffer was not submitted")?;
+        fence.wait()?;
+        let download = self.download;
+        let data = self.device.read_transfer_buffer(self.transfer, download.size)?;
+        Image::from_swapchain_data(download.format, download.width, download.height, &data)
+    }
+
+    /// Wait for the screenshot and write it to `path` as PNG.
+    pub fn save_png(self, path: impl AsRef<Path>) -> Result<(), &'static str> {
+        self.wait()?.save_png(path)
+    }
+}
+
+impl Drop for PendingScreenshot<'_> {
+    fn drop(&mut self) {
+        self.device.release_transfer_buffer(self.transfer);
+    }
+}
@@ -0,0 +1,432 @@
+//! In-process shader compilation with naga (the `naga` feature).
+//!
+//! Parses GLSL or WGSL and writes SPIR-V or MSL following SDL's resource
+//! binding conventions, together with the resource counts `SDL_CreateGPUShader`
+//! and `SDL_CreateGPUComputePipeline` need. No external tools are involved.
+//!
+//! Resources are renumbered to SDL's layout, so the group and binding
+//! numbers in the source only set the order within each kind. naga has no
+//! combined image samplers: declare `texture2D` and `sampler` separately
+//! (`sampler2D(tex, samp)` in GLSL); the n-th sampler pairs with the n-th
+//! sampled texture.
+
+use std::fmt;
+
+use naga::back::{msl, spv};
+use naga::valid::{Capabilities, ValidationFlags, Validator};
+use naga::{AddressSpace, GlobalVariable, Handle, ImageClass, ResourceBinding, StorageAccess, TypeInner};
+
+use crate::tools::ShaderTarget;
+
+/// Source language of a shader.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SourceLanguage {
+    Glsl,
+    Wgsl,
+}
+
+/// Pipeline stage a shader is compiled for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment,
+    Compute,
+}
+
+impl ShaderStage {
+    fn to_naga(self) -> naga::ShaderStage {
+        match self {
+            ShaderStage::Vertex => naga::ShaderStage::Vertex,
+            ShaderStage::Fragment => naga::ShaderStage::Fragment,
+            ShaderStage::Compute => naga::ShaderStage::Compute,
+        }
+    }
+}
+
+/// Resource counts of a compiled shader, as SDL expects them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ShaderReflection {
+    pub num_samplers: u32,
+    pub num_readonly_storage_textures: u32,
+    pub num_readwrite_storage_textures: u32,
+    pub num_readonly_storage_buffers: u32,
+    pub num_readwrite_storage_buffers: u32,
+    pub num_uniform_buffers: u32,
+    /// Workgroup size of a compute shader, `[0; 3]` otherwise.
+    pub threadcount: [u32; 3],
+}
+
+impl ShaderReflection {
+    /// Storage textures of a graphics shader (graphics stages don't split by access).
+    pub fn num_storage_textures(&self) -> u32 {
+        self.num_readonly_storage_textures + self.num_readwrite_storage_textures
+    }
+
+    /// Storage buffers of a graphics shader (graphics stages don't split by access).
+    pub fn num_storage_buffers(&self) -> u32 {
+        self.num_readonly_storage_buffers + self.num_readwrite_storage_buffers
+    }
+
+    /// Reflection JSON in the shape `shadercross -d JSON` writes.
+    pub fn to_json(&self, stage: ShaderStage, entrypoint: &str) -> String {
+        match stage {
+            ShaderStage::Compute => format!(
+                "{{ \"samplers\": {}, \"readonly_storage_textures\": {}, \"readonly_storage_buffers\": {}, \
+                 \"readwrite_storage_textures\": {}, \"readwrite_storage_buffers\": {}, \"uniform_buffers\": {}, \
+                 \"threadcount_x\": {}, \"threadcount_y\": {}, \"threadcount_z\": {}, \"entrypoint\": \"{entrypoint}\" }}\n",
+                self.num_samplers,
+                self.num_readonly_storage_textures,
+                self.num_readonly_storage_buffers,
+                self.num_readwrite_storage_textures,
+                self.num_readwrite_storage_buffers,
+                self.num_uniform_buffers,
+                self.threadcount[0],
+                self.threadcount[1],
+                self.threadcount[2],
+            ),
+            ShaderStage::Vertex | ShaderStage::Fragment => format!(
+                "{{ \"samplers\": {}, \"storage_textures\": {}, \"storage_buffers\": {}, \"uniform_buffers\": {}, \
+                 \"entrypoint\": \"{entrypoint}\" }}\n",
+                self.num_samplers,
+                self.num_storage_textures(),
+                self.num_storage_buffers(),
+                self.num_uniform_buffers,
+            ),
+        }
+    }
+}
+
+/// The output of [`compile`].
+#[derive(Clone, Debug)]
+pub struct CompiledSource {
+    /// SPIR-V words as bytes, or MSL text.
+    pub code: Vec<u8>,
+    /// Entry point name in the output. MSL output may rename it (e.g. `main` → `main_`).
+    pub entrypoint: String,
+    pub stage: ShaderStage,
+    pub reflection: ShaderReflection,
+}
+
+/// A parse, validation or translation failure. `line` and `column` are
+/// 1-based and refer to the source passed to [`compile`]; both are 0 when
+/// the error has no source location.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompileError {
+    pub message: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl CompileError {
+    fn new(message: impl Into<String>) -> Self {
+        Self { message: message.into(), line: 0, column: 0 }
+    }
+
+    fn at(message: impl Into<String>, location: Option<naga::SourceLocation>) -> Self {
+        let (line, column) = location.map_or((0, 0), |l| (l.line_number, l.line_position));
+        Self { message: message.into(), line, column }
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line > 0 {
+            write!(f, "{}:{}: {}", self.line, self.column, self.message)
+        } else {
+            f.write_str(&self.message)
+        }
+    }
+}
+
+impl std::error::Error for CompileError {}
+
+/// Error returned by [`Device::create_shader_from_source`](crate::device::Device::create_shader_from_source)
+/// and [`Device::create_compute_pipeline_from_source`](crate::device::Device::create_compute_pipeline_from_source).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShaderSourceError {
+    /// The source failed to parse, validate or translate.
+    Compile(CompileError),
+    /// The device rejected the compiled shader, or consumes no format naga
+    /// can produce (only SPIR-V and MSL are).
+    Device(&'static str),
+}
+
+impl fmt::Display for ShaderSourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaderSourceError::Compile(e) => e.fmt(f),
+            ShaderSourceError::Device(e) => f.write_str(e),
+        }
+    }
+}
+
+impl std::error::Error for ShaderSourceError {}
+
+impl From<CompileError> for ShaderSourceError {
+    fn from(e: CompileError) -> Self {
+        ShaderSourceError::Compile(e)
+    }
+}
+
+/// What a bound resource is, in SDL's terms. Declaration order matches the
+/// order SDL assigns slots in.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum ResourceKind {
+    SampledTexture,
+    Sampler,
+    ReadonlyStorageTexture,
+    ReadwriteStorageTexture,
+    UniformBuffer,
+    ReadonlyStorageBuffer,
+    ReadwriteStorageBuffer,
+}
+
+/// Every bound global, sorted into SDL slot order. The source's own
+/// group/binding numbers only decide the order within each kind.
+fn resources(module: &naga::Module) -> Vec<(Handle<GlobalVariable>, ResourceBinding, ResourceKind)> {
+    let mut out = Vec::new();
+    for (handle, var) in module.global_variables.iter() {
+        let Some(binding) = var.binding else {
+            continue;
+        };
+        let mut inner = &module.types[var.ty].inner;
+        if let TypeInner::BindingArray { base, .. } = inner {
+            inner = &module.types[*base].inner;
+        }
+        let kind = match (var.space, inner) {
+            (AddressSpace::Uniform, _) => ResourceKind::UniformBuffer,
+            (AddressSpace::Storage { access }, _) if access.contains(StorageAccess::STORE) => {
+                ResourceKind::ReadwriteStorageBuffer
+            }
+            (AddressSpace::Storage { .. }, _) => ResourceKind::ReadonlyStorageBuffer,
+            (AddressSpace::Handle, TypeInner::Sampler { .. }) => ResourceKind::Sampler,
+            (AddressSpace::Handle, TypeInner::Image { class: ImageClass::Storage { access, .. }, .. }) => {
+                if access.contains(StorageAccess::STORE) {
+                    ResourceKind::ReadwriteStorageTexture
+                } else {
+                    ResourceKind::ReadonlyStorageTexture
+                }
+            }
+            (AddressSpace::Handle, TypeInner::Image { .. }) => ResourceKind::SampledTexture,
+            _ => continue,
+        };
+        out.push((handle, binding, kind));
+    }
+    out.sort_by_key(|(_, b, k)| (*k, b.group, b.binding));
+    out
+}
+
+fn reflect(module: &naga::Module, entry: &naga::EntryPoint) -> Result<ShaderReflection, CompileError> {
+    let resources = resources(module);
+    let count = |kind| resources.iter().filter(|(_, _, k)| *k == kind).count() as u32;
+    let textures = count(ResourceKind::SampledTexture);
+    if count(ResourceKind::Sampler) > textures {
+        return Err(CompileError::new("more samplers than sampled textures; SDL binds them in pairs"));
+    }
+    Ok(ShaderReflection {
+        num_samplers: textures,
+        num_readonly_storage_textures: count(ResourceKind::ReadonlyStorageTexture),
+        num_readwrite_storage_textures: count(ResourceKind::ReadwriteStorageTexture),
+        num_readonly_storage_buffers: count(ResourceKind::ReadonlyStorageBuffer),
+        num_readwrite_storage_buffers: count(ResourceKind::ReadwriteStorageBuffer),
+        num_uniform_buffers: count(ResourceKind::UniformBuffer),
+        threadcount: if entry.stage == naga::ShaderStage::Compute { entry.workgroup_size } else { [0; 3] },
+    })
+}
+
+/// Renumber every resource to the descriptor set and binding SDL's Vulkan
+/// backend uses. Vertex resources go in set 0 with uniforms in set 1,
+/// fragment in sets 2 and 3. Compute uses set 0 for sampled and read-only
+/// resources, set 1 for read-write ones and set 2 for uniforms. The n-th
+/// sampler shares a binding with the n-th sampled texture, which Vulkan
+/// accepts for a combined image sampler descriptor.
+fn assign_spirv_bindings(module: &mut naga::Module, stage: naga::ShaderStage) {
+    let (resource_set, uniform_set) = match stage {
+        naga::ShaderStage::Fragment => (2, 3),
+        naga::ShaderStage::Compute => (0, 2),
+        _ => (0, 1),
+    };
+    let mut next = [0u32; 4];
+    let mut texture_bindings = Vec::new();
+    let mut samplers = 0;
+    for (handle, _, kind) in resources(module) {
+        let group = match kind {
+            ResourceKind::UniformBuffer => uniform_set,
+            ResourceKind::ReadwriteStorageTexture | ResourceKind::ReadwriteStorageBuffer
+                if stage == naga::ShaderStage::Compute => 1,
+            _ => resource_set,
+        };
+        let binding = if kind == ResourceKind::Sampler {
+            samplers += 1;
+            texture_bindings[samplers - 1]
+        } else {
+            next[group as usize] += 1;
+            next[group as usize] - 1
+        };
+        if kind == ResourceKind::SampledTexture {
+            texture_bindings.push(binding);
+        }
+        module.global_variables[handle].binding = Some(ResourceBinding { group, binding });
+    }
+}
+
+/// Map every resource to the Metal slot SDL binds it to: textures are sampled
+/// textures then storage textures, the n-th sampler pairs with the n-th
+/// sampled texture, and buffers are uniform buffers then storage buffers
+/// (read-only before read-write for compute). Also returns the first free
+/// buffer slot.
+fn msl_binding_map(module: &naga::Module) -> (msl::BindingMap, u8) {
+    let mut map = msl::BindingMap::new();
+    let mut texture_slot = 0u8;
+    let mut buffer_slot = 0u8;
+    let mut sampler_slot = 0u8;
+    for (_, binding, kind) in resources(module) {
+        let mut target = msl::BindTarget::default();
+        match kind {
+            ResourceKind::SampledTexture | ResourceKind::ReadonlyStorageTexture => {
+                target.texture = Some(texture_slot);
+                texture_slot += 1;
+            }
+            ResourceKind::ReadwriteStorageTexture => {
+                target.texture = Some(texture_slot);
+                target.mutable = true;
+                texture_slot += 1;
+            }
+            ResourceKind::Sampler => {
+                target.sampler = Some(msl::BindSamplerTarget::Resource(sampler_slot));
+                sampler_slot += 1;
+            }
+            ResourceKind::UniformBuffer | ResourceKind::ReadonlyStorageBuffer => {
+                target.buffer = Some(buffer_slot);
+                buffer_slot += 1;
+            }
+            ResourceKind::ReadwriteStorageBuffer => {
+                target.buffer = Some(buffer_slot);
+                target.mutable = true;
+                buffer_slot += 1;
+            }
+        }
+        map.insert(binding, target);
+    }
+    (map, buffer_slot)
+}
+
+fn parse(source: &str, language: SourceLanguage, stage: Option<ShaderStage>, defines: &[String]) -> Result<naga::Module, CompileError> {
+    match language {
+        SourceLanguage::Glsl => {
+            let stage = stage.ok_or_else(|| CompileError::new("GLSL sources need an explicit stage"))?;
+            let mut options = naga::front::glsl::Options::from(stage.to_naga());
+            for define in defines {
+                let (name, value) = define.split_once('=').unwrap_or((define, "1"));
+                options.defines.insert(name.to_string(), value.to_string());
+            }
+            naga::front::glsl::Frontend::default()
+                .parse(&options, source)
+                .map_err(|e| {
+                    let first = e.errors.first();
+                    CompileError::at(
+                        first.map_or_else(|| "GLSL parse failed".to_string(), |err| err.kind.to_string()),
+                        first.and_then(|err| err.location(source)),
+                    )
+                })
+        }
+        SourceLanguage::Wgsl => {
+            if !defines.is_empty() {
+                return Err(CompileError::new("WGSL has no preprocessor; defines are not supported"));
+            }
+            naga::front::wgsl::parse_str(source)
+                .map_err(|e| CompileError::at(e.message().to_string(), e.location(source)))
+        }
+    }
+}
+
+/// Compile `source` for `target`.
+///
+/// `stage` is required for GLSL. For WGSL it selects among the module's entry
+/// points and may be `None` if there is exactly one. `defines` are
+/// `NAME` or `NAME=VALUE` preprocessor definitions (GLSL only).
+/// [`ShaderTarget::Dxil`] is not produced in-process; compile to SPIR-V and
+/// convert that instead.
+pub fn compile(
+    source: &str,
+    language: SourceLanguage,
+    stage: Option<ShaderStage>,
+    defines: &[String],
+    target: ShaderTarget,
+) -> Result<CompiledSource, CompileError> {
+    let mut module = parse(source, language, stage, defines)?;
+
+    let info = Validator::new(ValidationFlags::all(), Capabilities::all())
+        .validate(&module)
+        .map_err(|e| CompileError::at(e.emit_to_string(source).trim().to_string(), e.location(source)))?;
+
+    let entry = match stage {
+        Some(stage) => module.entry_points.iter().find(|ep| ep.stage == stage.to_naga()),
+        None if module.entry_points.len() == 1 => module.entry_points.first(),
+        None => {
+            return Err(CompileError::new(format!(
+                "{} entry points found; specify the stage",
+                module.entry_points.len()
+            )));
+        }
+    }
+    .ok_or_else(|| CompileError::new("no entry point for the requested stage"))?;
+
+    let stage = match entry.stage {
+        naga::ShaderStage::Vertex => ShaderStage::Vertex,
+        naga::ShaderStage::Fragment => ShaderStage::Fragment,
+        naga::ShaderStage::Compute => ShaderStage::Compute,
+        other => return Err(CompileError::new(format!("unsupported stage {other:?}"))),
+    };
+    let reflection = reflect(&module, entry)?;
+    let (naga_stage, name) = (entry.stage, entry.name.clone());
+
+    let (code, entrypoint) = match target {
+        ShaderTarget::Spirv => {
+            let options = spv::Options {
+                lang_version: (1, 0),
+                ..Default::default()
+            };
+            assign_spirv_bindings(&mut module, naga_stage);
+            let pipeline = spv::PipelineOptions {
+                shader_stage: naga_stage,
+                entry_point: name.clone(),
+            };
+            let words = spv::write_vec(&module, &info, &options, Some(&pipeline))
+                .map_err(|e| CompileError::new(format!("SPIR-V output failed: {e}")))?;
+            (bytemuck::cast_slice(&words).to_vec(), name)
+        }
+        ShaderTarget::Msl => {
+            let mut options = msl::Options {
+                lang_version: (2, 0),
+                fake_missing_bindings: false,
+                ..Default::default()
+            };
+            // naga wants a buffer for runtime array lengths; SDL never binds
+            // one, so `.length()` on runtime-sized arrays is unsupported on Metal.
+            let (resources, sizes_slot) = msl_binding_map(&module);
+            options.per_entry_point_map.insert(name.clone(), msl::EntryPointResources {
+                resources,
+                sizes_buffer: Some(sizes_slot),
+                ..Default::default()
+            });
+            let pipeline = msl::PipelineOptions {
+                entry_point: Some((naga_stage, name.clone())),
+                ..Default::default()
+            };
+            let (text, translation) = msl::write_string(&module, &info, &options, &pipeline)
+                .map_err(|e| CompileError::new(format!("MSL output failed: {e}")))?;
+            let entrypoint = match translation.entry_point_names.into_iter().next() {
+                Some(Ok(name)) => name,
+                Some(Err(e)) => return Err(CompileError::new(format!("MSL output failed: {e}"))),
+                None => name,
+            };
+            (text.into_bytes(), entrypoint)
+        }
+        ShaderTarget::Dxil => {
+            return Err(CompileError::new("DXIL is not produced in-process; compile to SPIR-V and convert it"));
+        }
+    };
+
+    Ok(CompiledSource { code, entrypoint, stage, reflection })
+}
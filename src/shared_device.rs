@@ -0,0 +1,228 @@
+//! A thread-safe handle for creating resources and uploading data off the
+//! thread that owns the [`Device`](crate::device::Device).
+//!
+//! `Device` itself stays on one thread: swapchains, command buffer
+//! recording, the texture pool and frame statistics are not shared. Its
+//! resource slot maps are, behind a lock each, so handles created through a
+//! [`SharedDevice`] on an asset-loading thread are ordinary handles the
+//! main thread binds and destroys like its own, and the other way around.
+//!
+//! ```no_run
+//! # use sdl3_gs::device::{Device, SDL_GPUBufferUsageFlags};
+//! # fn run(device: &Device) -> Result<(), &'static str> {
+//! let shared = device.shared();
+//! let loader = std::thread::spawn(move || {
+//!     let data = vec![0u8; 1024];
+//!     let buffer = shared.create_buffer_named(SDL_GPUBufferUsageFlags::VERTEX, data.len() as u32, "mesh")?;
+//!     shared.upload_to_buffer(buffer, 0, &data)?;
+//!     Ok::<_, &'static str>(buffer)
+//! });
+//! let buffer = loader.join().unwrap()?;
+//! # let _ = buffer;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::panic::Location;
+use std::sync::Arc;
+
+use sdl3_sys::gpu;
+
+use crate::backend::Backend;
+use crate::device::{
+    ComputePipeline, ComputePipelineCreateInfo, GPUBuffer, GraphicsPipeline, GraphicsPipelineCreateInfo, Resources,
+    SDL_GPUBufferUsageFlags, Sampler, Shader, ShaderCreateInfo, Texture, TextureRegion,
+};
+use crate::properties::StringProperty;
+
+/// Handle to a device's resources that is `Send + Sync`, created with
+/// [`Device::shared`](crate::device::Device::shared). Cloning it is cheap.
+///
+/// Resources stay alive until both the device and every `SharedDevice` are
+/// dropped. Uploads are submitted on their own command buffer before the
+/// call returns, so command buffers submitted afterwards, from any thread,
+/// see the data. They are not counted in
+/// [`Device::frame_stats`](crate::device::Device::frame_stats).
+#[derive(Clone)]
+pub struct SharedDevice {
+    resources: Arc<Resources>,
+}
+
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<SharedDevice>();
+};
+
+impl SharedDevice {
+    pub(crate) fn new(resources: Arc<Resources>) -> Self {
+        Self { resources }
+    }
+
+    /// See [`Device::create_texture`](crate::device::Device::create_texture).
+    #[track_caller]
+    pub fn create_texture(&self, info: &gpu::SDL_GPUTextureCreateInfo) -> Result<Texture, &'static str> {
+        self.resources.create_texture(info, Location::caller())
+    }
+
+    /// Create a texture named `name` in graphics debuggers.
+    #[track_caller]
+    pub fn create_texture_named(&self, info: &gpu::SDL_GPUTextureCreateInfo, name: &str) -> Result<Texture, &'static str> {
+        let props = StringProperty::new(info.props, gpu::SDL_PROP_GPU_TEXTURE_CREATE_NAME_STRING, Some(name));
+        self.create_texture(&gpu::SDL_GPUTextureCreateInfo { props: props.id(), ..*info })
+    }
+
+    #[track_caller]
+    pub fn create_buffer(&self, usage: SDL_GPUBufferUsageFlags, size: u32) -> Result<GPUBuffer, &'static str> {
+        self.resources.create_buffer(usage, size, None, Location::caller())
+    }
+
+    /// Create a buffer named `name` in graphics debuggers.
+    #[track_caller]
+    pub fn create_buffer_named(&self, usage: SDL_GPUBufferUsageFlags, size: u32, name: &str) -> Result<GPUBuffer, &'static str> {
+        self.resources.create_buffer(usage, size, Some(name), Location::caller())
+    }
+
+    /// See [`Device::create_sampler`](crate::device::Device::create_sampler).
+    #[track_caller]
+    pub fn create_sampler(&self, info: &gpu::SDL_GPUSamplerCreateInfo) -> Result<Sampler, &'static str> {
+        self.resources.create_sampler(info, Location::caller())
+    }
+
+    #[track_caller]
+    pub fn create_shader(&self, info: &ShaderCreateInfo) -> Result<Shader, &'static str> {
+        self.resources.create_shader(info, Location::caller())
+    }
+
+    #[track_caller]
+    pub fn create_graphics_pipeline(&self, info: &GraphicsPipelineCreateInfo) -> Result<GraphicsPipeline, &'static str> {
+        self.resources.create_graphics_pipeline(info, Location::caller())
+    }
+
+    #[track_caller]
+    pub fn create_compute_pipeline(&self, info: &ComputePipelineCreateInfo) -> Result<ComputePipeline, &'static str> {
+        self.resources.create_compute_pipeline(info, Location::caller())
+    }
+
+    pub fn get_texture_res(&self, texture: Texture) -> (u32, u32) {
+        self.resources.texture_res(texture)
+    }
+
+    pub fn get_buffer_size(&self, buffer: GPUBuffer) -> u32 {
+        self.resources.buffer_size(buffer)
+    }
+
+    /// Upload data from a byte slice into a GPU buffer.
+    pub fn upload_to_buffer(&self, buffer: GPUBuffer, offset: u32, data: &[u8]) -> Result<(), &'static str> {
+        let size = data.len() as u32;
+        if offset.saturating_add(size) > self.resources.buffer_size(buffer) {
+            return Err("data exceeds buffer size");
+        }
+        let dst = gpu::SDL_GPUBufferRegion { buffer: self.resources.buffer_raw(buffer), offset, size };
+        self.upload(data, |backend, pass, transfer| {
+            let src = gpu::SDL_GPUTransferBufferLocation { transfer_buffer: transfer, offset: 0 };
+            backend.upload_to_buffer(pass, &src, &dst, false);
+        })
+    }
+
+    /// Upload pixel data from a byte slice into a GPU texture region.
+    /// Swapchain textures belong to the device's thread and are rejected.
+    pub fn upload_to_texture(&self, region: &TextureRegion, data: &[u8]) -> Result<(), &'static str> {
+        if region.texture.is_swapchain() {
+            return Err("can't upload to a swapchain texture from a SharedDevice");
+        }
+        let dst = gpu::SDL_GPUTextureRegion {
+            texture: self.resources.texture_raw(region.texture),
+            mip_level: region.mip_level,
+            layer: region.layer,
+            x: region.x,
+            y: region.y,
+            z: region.z,
+            w: region.w,
+            h: region.h,
+            d: region.d,
+        };
+        self.upload(data, |backend, pass, transfer| {
+            let src = gpu::SDL_GPUTextureTransferInfo {
+                transfer_buffer: transfer,
+                offset: 0,
+                pixels_per_row: 0,
+                rows_per_layer: 0,
+            };
+            backend.upload_to_texture(pass, &src, &dst, false);
+        })
+    }
+
+    /// Stage `data` in a transfer buffer of its own, record the copy with
+    /// `f` on a command buffer of its own and submit it.
+    fn upload(
+        &self,
+        data: &[u8],
+        f: impl FnOnce(&dyn Backend, *mut gpu::SDL_GPUCopyPass, *mut gpu::SDL_GPUTransferBuffer),
+    ) -> Result<(), &'static str> {
+        let backend = &*self.resources.backend;
+        let info = gpu::SDL_GPUTransferBufferCreateInfo {
+            usage: gpu::SDL_GPUTransferBufferUsage::UPLOAD,
+            size: data.len() as u32,
+            props: sdl3_sys::properties::SDL_PropertiesID(0),
+        };
+        let transfer = backend.create_transfer_buffer(&info);
+        if transfer.is_null() {
+            return Err("SDL_CreateGPUTransferBuffer failed");
+        }
+        let ptr = backend.map_transfer_buffer(transfer, false);
+        if ptr.is_null() {
+            backend.release_transfer_buffer(transfer);
+            return Err("SDL_MapGPUTransferBuffer failed");
+        }
+        unsafe { std::ptr::copy_nonoverlapping(data.as_ptr(), ptr, data.len()) };
+        backend.unmap_transfer_buffer(transfer);
+
+        let cmd = backend.acquire_command_buffer();
+        if cmd.is_null() {
+            backend.release_transfer_buffer(transfer);
+            return Err("SDL_AcquireGPUCommandBuffer failed");
+        }
+        let pass = backend.begin_copy_pass(cmd);
+        if pass.is_null() {
+            backend.cancel_command_buffer(cmd);
+            backend.release_transfer_buffer(transfer);
+            return Err("SDL_BeginGPUCopyPass failed");
+        }
+        f(backend, pass, transfer);
+        backend.end_copy_pass(pass);
+        let submitted = backend.submit_command_buffer(cmd);
+        // SDL keeps the transfer buffer alive until the copy has executed.
+        backend.release_transfer_buffer(transfer);
+        if submitted { Ok(()) } else { Err("SDL_SubmitGPUCommandBuffer failed") }
+    }
+
+    pub fn destroy_texture(&self, texture: &mut Texture) {
+        self.resources.destroy_texture(*texture);
+        texture.0 = -1;
+    }
+
+    pub fn destroy_buffer(&self, buffer: &mut GPUBuffer) {
+        self.resources.destroy_buffer(*buffer);
+        buffer.0 = -1;
+    }
+
+    pub fn destroy_sampler(&self, sampler: &mut Sampler) {
+        self.resources.destroy_sampler(*sampler);
+        sampler.0 = -1;
+    }
+
+    pub fn destroy_shader(&self, shader: &mut Shader) {
+        self.resources.destroy_shader(*shader);
+        shader.0 = -1;
+    }
+
+    pub fn destroy_graphics_pipeline(&self, pipeline: &mut GraphicsPipeline) {
+        self.resources.destroy_graphics_pipeline(*pipeline);
+        pipeline.0 = -1;
+    }
+
+    pub fn destroy_compute_pipeline(&self, pipeline: &mut ComputePipeline) {
+        self.resources.destroy_compute_pipeline(*pipeline);
+        pipeline.0 = -1;
+    }
+}
@@ -46,11 +46,14 @@ impl<T> SlotMap<T> {
     /// Panics if the slot is already free.
     pub fn remove(&mut self, index: i32) -> T {
         let entry = &mut self.slots[index as usize];
+        if let SlotEntry::Free { .. } = entry {
+            panic!("slot already free");
+        }
         let old = std::mem::replace(entry, SlotEntry::Free { next_free: self.first_free });
         self.first_free = index;
         match old {
             SlotEntry::Occupied(v) => v,
-            SlotEntry::Free { .. } => panic!("slot already free"),
+            SlotEntry::Free { .. } => unreachable!(),
         }
     }
 
@@ -127,3 +130,58 @@ impl<T> SlotMapRefCell<T> {
         }
     }
 }
+
+/// Like [`SlotMapRefCell`], but behind a `Mutex`, so it can be shared
+/// between threads. Closures passed to [`with`](Self::with) and friends run
+/// with the lock held and must not access the same map again.
+pub struct SlotMapMutex<T> {
+    inner: std::sync::Mutex<SlotMap<T>>,
+}
+
+impl<T> Default for SlotMapMutex<T> {
+    fn default() -> Self {
+        Self {
+            inner: std::sync::Mutex::new(SlotMap::default()),
+        }
+    }
+}
+
+impl<T> SlotMapMutex<T> {
+    /// `SlotMap` panics (removing a free slot, accessing a free or out of
+    /// range slot) happen before anything is modified, so a panic while the
+    /// lock was held leaves the map consistent and poisoning is ignored.
+    fn lock(&self) -> std::sync::MutexGuard<'_, SlotMap<T>> {
+        self.inner.lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Insert a value and return its stable index.
+    pub fn insert(&self, value: T) -> i32 {
+        self.lock().insert(value)
+    }
+
+    /// Remove the entry at `index`, returning the value.
+    ///
+    /// # Panics
+    /// Panics if the slot is already free.
+    pub fn remove(&self, index: i32) -> T {
+        self.lock().remove(index)
+    }
+
+    /// Lock the map and pass the value at `index` to the closure `f`.
+    pub fn with<R>(&self, index: i32, f: impl FnOnce(&T) -> R) -> R {
+        f(self.lock().get(index))
+    }
+
+    /// Lock the map and pass the value at `index` mutably to the closure `f`.
+    pub fn with_mut<R>(&self, index: i32, f: impl FnOnce(&mut T) -> R) -> R {
+        f(self.lock().get_mut(index))
+    }
+
+    /// Iterate over all occupied entries via a closure, with the lock held.
+    pub fn for_each(&self, mut f: impl FnMut(i32, &T)) {
+        let map = self.lock();
+        for (i, v) in map.iter() {
+            f(i, v);
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::device::{Device, GpuFence, Texture, TextureDesc};
+use crate::inventory::texture_bytes;
+
+/// Memory report of a device's texture pool.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TexturePoolStats {
+    /// Textures owned by the pool, in use or not.
+    pub textures: usize,
+    /// Textures currently handed out.
+    pub in_use: usize,
+    /// Textures released but still referenced by command buffers in flight.
+    pub retiring: usize,
+    /// Approximate size of every pooled texture, in bytes.
+    pub bytes: u64,
+    /// Approximate size of the textures currently handed out, in bytes.
+    pub in_use_bytes: u64,
+}
+
+/// Released textures with the descriptions they are pooled under.
+type Batch = Vec<(TextureDesc, Texture)>;
+
+struct FreeTexture {
+    texture: Texture,
+    last_used_frame: u64,
+}
+
+/// The state behind [`Device::acquire_pooled_texture`].
+///
+/// Released textures are held back until the command buffers that may use
+/// them have finished on the GPU: releases collect in `pending`, the next
+/// submit that leaves no command buffer recording takes a fence for them,
+/// and each frame boundary moves the batches whose fence signalled to the
+/// free lists.
+pub(crate) struct TexturePool {
+    free: HashMap<TextureDesc, Vec<FreeTexture>>,
+    in_use: HashMap<Texture, TextureDesc>,
+    pending: Batch,
+    retiring: Vec<(Rc<GpuFence>, Batch)>,
+    frame: u64,
+    max_unused_frames: u32,
+}
+
+impl TexturePool {
+    pub fn new() -> Self {
+        Self {
+            free: HashMap::new(),
+            in_use: HashMap::new(),
+            pending: Vec::new(),
+            retiring: Vec::new(),
+            frame: 0,
+            max_unused_frames: 3,
+        }
+    }
+
+    pub fn acquire(&mut self, device: &Device, desc: &TextureDesc) -> Result<Texture, &'static str> {
+        let texture = match self.free.get_mut(desc).and_then(Vec::pop) {
+            Some(free) => free.texture,
+            None => device.create_texture(&desc.to_create_info())?,
+        };
+        self.in_use.insert(texture, *desc);
+        Ok(texture)
+    }
+
+    pub fn release(&mut self, texture: Texture) {
+        let desc = self.in_use.remove(&texture).expect("texture was not acquired from the pool");
+        self.pending.push((desc, texture));
+    }
+
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Tie everything released so far to `fence`.
+    pub fn retire_pending(&mut self, fence: Rc<GpuFence>) {
+        let batch = std::mem::take(&mut self.pending);
+        self.retiring.push((fence, batch));
+    }
+
+    pub fn set_max_unused_frames(&mut self, frames: u32) {
+        self.max_unused_frames = frames;
+    }
+
+    /// Advance one frame: recycle textures whose command buffers retired and
+    /// destroy free textures unused for more than `max_unused_frames`.
+    pub fn begin_frame(&mut self, device: &Device) {
+        self.frame += 1;
+        let frame = self.frame;
+        let mut i = 0;
+        while i < self.retiring.len() {
+            if self.retiring[i].0.is_signaled() {
+                let (_, batch) = self.retiring.swap_remove(i);
+                for (desc, texture) in batch {
+                    self.free.entry(desc).or_default().push(FreeTexture { texture, last_used_frame: frame });
+                }
+            } else {
+                i += 1;
+            }
+        }
+
+        let max_unused = self.max_unused_frames as u64;
+        self.free.retain(|_, textures| {
+            textures.retain_mut(|free| {
+                if frame - free.last_used_frame <= max_unused {
+                    return true;
+                }
+                free.texture.destroy(device);
+                false
+            });
+            !textures.is_empty()
+        });
+    }
+
+    pub fn stats(&self) -> TexturePoolStats {
+        let free = self.free.iter().flat_map(|(desc, textures)| textures.iter().map(move |_| desc));
+        let retiring = self.pending.iter().chain(self.retiring.iter().flat_map(|(_, batch)| batch)).map(|(desc, _)| desc);
+        let bytes = |desc: &TextureDesc| texture_bytes(&desc.to_create_info());
+        let in_use_bytes: u64 = self.in_use.values().map(bytes).sum();
+        let idle_bytes: u64 = free.clone().chain(retiring.clone()).map(bytes).sum();
+        let retiring = retiring.count();
+        TexturePoolStats {
+            textures: self.in_use.len() + retiring + free.count(),
+            in_use: self.in_use.len(),
+            retiring,
+            bytes: in_use_bytes + idle_bytes,
+            in_use_bytes,
+        }
+    }
+
+    /// Every texture the pool owns, handed out or not.
+    pub fn textures(&self) -> impl Iterator<Item = Texture> + '_ {
+        let free = self.free.values().flatten().map(|free| free.texture);
+        let retiring = self.pending.iter().chain(self.retiring.iter().flat_map(|(_, batch)| batch)).map(|(_, texture)| *texture);
+        self.in_use.keys().copied().chain(free).chain(retiring)
+    }
+
+    /// Release the fences still held. The textures themselves are released
+    /// with the rest of the device's textures.
+    pub fn release_fences(&mut self) {
+        self.retiring.clear();
+    }
+}
@@ -1,22 +1,19 @@
-use std::fs::{self, File};
-use std::io::Write;
+use std::collections::BTreeSet;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::time::SystemTime;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 
-fn modified_time(path: &Path) -> Option<SystemTime> {
-    fs::metadata(path).ok()?.modified().ok()
-}
+mod manifest;
+mod permutations;
 
-fn needs_rebuild(source: &Path, output: &Path) -> bool {
-    let Some(src_time) = modified_time(source) else {
-        return false;
-    };
-    let Some(out_time) = modified_time(output) else {
-        return true;
-    };
-    src_time > out_time
-}
+use manifest::{parse_depfile, Manifest, ManifestEntry};
+pub use permutations::PermutationTable;
+#[cfg(feature = "naga")]
+use crate::shader_compiler::{self, ShaderStage as NagaStage, SourceLanguage};
 
 fn shader_stage(path: &Path) -> Option<&'static str> {
     match path.extension()?.to_str()? {
@@ -30,128 +27,791 @@ fn shader_stage(path: &Path) -> Option<&'static str> {
     }
 }
 
-fn log(msg: &str) {
-    if let Ok(mut tty) = File::create("/dev/tty") {
-        let _ = writeln!(tty, "{msg}");
+fn is_hlsl(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()) == Some("hlsl")
+}
+
+fn is_wgsl(path: &Path) -> bool {
+    cfg!(feature = "naga") && path.extension().and_then(|e| e.to_str()) == Some("wgsl")
+}
+
+fn is_shader_source(path: &Path) -> bool {
+    shader_stage(path).is_some() || is_hlsl(path) || is_wgsl(path)
+}
+
+/// Language and stage of a source naga can compile: GLSL vertex, fragment
+/// and compute shaders, and WGSL. A WGSL stage comes from the name
+/// (`lit.frag.wgsl`); without one the module must have a single entry point.
+#[cfg(feature = "naga")]
+fn naga_source(path: &Path) -> Option<(SourceLanguage, Option<NagaStage>)> {
+    fn stage_from(name: &str) -> Option<NagaStage> {
+        match name {
+            "vert" => Some(NagaStage::Vertex),
+            "frag" => Some(NagaStage::Fragment),
+            "comp" => Some(NagaStage::Compute),
+            _ => None,
+        }
+    }
+
+    let ext = path.extension()?.to_str()?;
+    if ext == "wgsl" {
+        let inner = Path::new(path.file_stem()?).extension().and_then(|e| e.to_str());
+        return Some((SourceLanguage::Wgsl, inner.and_then(stage_from)));
     }
+    Some((SourceLanguage::Glsl, Some(stage_from(ext)?)))
 }
 
-pub fn prepare_shaders(shader_dir : &Path, shader_intermediary_dir : &Path) {
-    let out_dir = PathBuf::from(shader_intermediary_dir);
-    let target_os = std::env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
-    let apple = target_os == "macos" || target_os == "ios";
-    let windows = target_os == "windows";
+/// Stage and entry point of an HLSL source, in shadercross terms.
+///
+/// Taken from `//! stage: vertex|fragment|compute` and `//! entry: Name`
+/// lines if present, otherwise from the name: `lit.vert.hlsl`, `lit.vs.hlsl`
+/// and so on, with entry point `main`.
+fn hlsl_stage_and_entry(path: &Path) -> Result<(&'static str, String), String> {
+    fn stage_from(name: &str) -> Option<&'static str> {
+        match name {
+            "vert" | "vs" | "vertex" => Some("vertex"),
+            "frag" | "ps" | "pixel" | "fragment" => Some("fragment"),
+            "comp" | "cs" | "compute" => Some("compute"),
+            _ => None,
+        }
+    }
 
-    println!("cargo:rerun-if-changed=src/shaders");
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut stage = None;
+    let mut entry = None;
+    for line in text.lines() {
+        let Some(rest) = line.trim_start().strip_prefix("//!") else {
+            continue;
+        };
+        let rest = rest.trim();
+        if let Some(name) = rest.strip_prefix("stage:") {
+            stage = Some(stage_from(name.trim()).ok_or_else(|| format!("unknown stage '{}'", name.trim()))?);
+        } else if let Some(name) = rest.strip_prefix("entry:") {
+            entry = Some(name.trim().to_string());
+        }
+    }
 
-    if !shader_dir.exists() {
-        return;
+    let stage = match stage {
+        Some(stage) => stage,
+        None => Path::new(path.file_stem().unwrap_or_default())
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(stage_from)
+            .ok_or("no stage: name the file like `name.vert.hlsl` or add `//! stage: vertex`")?,
+    };
+    Ok((stage, entry.unwrap_or_else(|| "main".to_string())))
+}
+
+/// Print a tool invocation to stderr. Inside a build script cargo keeps it
+/// in the build output, shown on failure or with `-vv`.
+fn progress(msg: &str) {
+    eprintln!("{msg}");
+}
+
+/// Report a diagnostic through cargo when running inside a build script,
+/// otherwise to stderr.
+fn warn(msg: &str) {
+    if std::env::var_os("OUT_DIR").is_some() {
+        for line in msg.lines() {
+            println!("cargo:warning={line}");
+        }
+    } else {
+        eprintln!("{msg}");
+    }
+}
+
+/// A failure compiling one shader source.
+#[derive(Debug, Clone)]
+pub struct ShaderDiagnostic {
+    /// The file the failing tool was run on.
+    pub path: PathBuf,
+    /// The tool that failed, e.g. `glslc` or `shadercross`.
+    pub tool: &'static str,
+    /// The compiler output, or why the tool could not be run.
+    pub message: String,
+}
+
+impl fmt::Display for ShaderDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} failed for {}", self.tool, self.path.display())?;
+        if !self.message.is_empty() {
+            write!(f, "\n{}", self.message)?;
+        }
+        Ok(())
     }
+}
 
-    fs::create_dir_all(&out_dir).expect("failed to create target/shader_il");
+impl std::error::Error for ShaderDiagnostic {}
 
-    let entries = fs::read_dir(shader_dir).expect("failed to read src/shaders");
+/// Error returned by [`prepare_shaders`].
+#[derive(Debug)]
+pub enum PrepareShadersError {
+    /// Reading the shader dir or creating the intermediary dir failed.
+    Io { path: PathBuf, error: std::io::Error },
+    /// One or more shaders failed to compile. All sources were attempted.
+    Compile(Vec<ShaderDiagnostic>),
+}
 
-    for entry in entries {
-        let entry = entry.expect("failed to read dir entry");
-        let src_path = entry.path();
+impl fmt::Display for PrepareShadersError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrepareShadersError::Io { path, error } => write!(f, "{}: {error}", path.display()),
+            PrepareShadersError::Compile(diagnostics) => {
+                write!(f, "{} shader(s) failed to compile", diagnostics.len())?;
+                for d in diagnostics {
+                    write!(f, "\n{d}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for PrepareShadersError {}
+
+/// The shader bytecode format a source file is compiled to.
+///
+/// HLSL sources go through `shadercross` for every target. With the `naga`
+/// feature, GLSL vertex/fragment/compute and WGSL sources are compiled
+/// in-process instead; only DXIL still needs `shadercross`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShaderTarget {
+    /// SPIR-V via `glslc` (Vulkan).
+    Spirv,
+    /// Metal Shading Language via `glslcc` (Metal).
+    Msl,
+    /// SPIR-V via `glslc`, then DXIL via `shadercross` (D3D12).
+    Dxil,
+}
+
+impl ShaderTarget {
+    /// The target used by `prepare_shaders` for the OS cargo is building for.
+    pub fn for_build_target() -> Self {
+        let target_os = std::env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+        match target_os.as_str() {
+            "macos" | "ios" => ShaderTarget::Msl,
+            "windows" => ShaderTarget::Dxil,
+            _ => ShaderTarget::Spirv,
+        }
+    }
+
+    /// File extension of the compiled output, e.g. `"spv"` for `foo.vert.spv`.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ShaderTarget::Spirv => "spv",
+            ShaderTarget::Msl => "msl",
+            ShaderTarget::Dxil => "dxil",
+        }
+    }
+}
+
+/// Run an external compiler, turning a missing tool or a non-zero exit into an
+/// error message that carries the compiler's own output.
+fn run_tool(cmd: &mut Command, tool: &'static str, input: &Path) -> Result<(), ShaderDiagnostic> {
+    let output = cmd.output().map_err(|e| ShaderDiagnostic {
+        path: input.to_path_buf(),
+        tool,
+        message: format!("failed to run {tool} — is it installed? ({e})"),
+    })?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let mut message = String::new();
+    for stream in [&output.stdout, &output.stderr] {
+        let text = String::from_utf8_lossy(stream);
+        let text = text.trim();
+        if !text.is_empty() {
+            if !message.is_empty() {
+                message.push('\n');
+            }
+            message.push_str(text);
+        }
+    }
+    Err(ShaderDiagnostic { path: input.to_path_buf(), tool, message })
+}
+
+/// `{prefix}{path}` as a single argument, without requiring `path` to be
+/// valid UTF-8.
+fn path_flag(prefix: &str, path: &Path) -> OsString {
+    let mut flag = OsString::from(prefix);
+    flag.push(path);
+    flag
+}
+
+/// The result of compiling one shader source.
+#[derive(Debug, Clone)]
+pub struct CompiledShader {
+    /// The compiled bytecode for the requested target.
+    pub output: PathBuf,
+    /// The source and every file it includes, transitively.
+    pub inputs: Vec<PathBuf>,
+    /// False if the manifest showed the outputs were already up to date.
+    pub rebuilt: bool,
+}
+
+/// Compile a single GLSL, HLSL or (with the `naga` feature) WGSL source file
+/// into `out_dir` for the given target.
+///
+/// Outputs are named `{stem}.{ext}.{target ext}`, with reflection data in
+/// `{stem}.{ext}.json`. The compile is skipped if the manifest in `out_dir`
+/// shows that no transitive input and no compiler option changed since the
+/// outputs were produced.
+///
+/// `variant` selects a permutation by feature mask, as listed in the
+/// source's [`PermutationTable`]; its outputs are named
+/// `{stem}.{ext}.{mask}.*`. The mask must be one the source's spec builds.
+pub fn compile_shader(
+    src_path: &Path,
+    out_dir: &Path,
+    target: ShaderTarget,
+    variant: Option<u32>,
+) -> Result<CompiledShader, ShaderDiagnostic> {
+    let defines = match variant {
+        Some(mask) => {
+            let diagnostic = |message| ShaderDiagnostic { path: src_path.to_path_buf(), tool: "prepare_shaders", message };
+            let spec = permutations::read_spec(src_path)
+                .map_err(diagnostic)?
+                .ok_or_else(|| diagnostic("the source declares no permutations".to_string()))?;
+            if !spec.variants.contains(&mask) {
+                return Err(diagnostic(format!("variant {mask} is not one the source's permutation spec builds")));
+            }
+            spec.defines(mask)
+        }
+        None => Vec::new(),
+    };
+    let variant = variant.map(|mask| Variant { mask, defines: &defines });
+    let mut manifest = Manifest::load(out_dir);
+    let (compiled, entry) = compile_with_manifest(src_path, out_dir, target, variant, &manifest)?;
+    if compiled.rebuilt {
+        manifest.insert(compiled.output.clone(), entry);
+        manifest.save(out_dir).map_err(|e| ShaderDiagnostic {
+            path: out_dir.to_path_buf(),
+            tool: "prepare_shaders",
+            message: format!("failed to write shader manifest: {e}"),
+        })?;
+    }
+    Ok(compiled)
+}
+
+/// The transitive inputs recorded for `src_path` (or its permutation
+/// `variant`) by its last successful compile into `out_dir`, without
+/// compiling anything.
+pub fn recorded_inputs(src_path: &Path, out_dir: &Path, target: ShaderTarget, variant: Option<u32>) -> Vec<PathBuf> {
+    let (Some(stem), Some(ext)) = (src_path.file_stem(), src_path.extension()) else {
+        return Vec::new();
+    };
+    let mut base = format!("{}.{}", stem.to_string_lossy(), ext.to_string_lossy());
+    if let Some(mask) = variant {
+        base.push_str(&format!(".{mask}"));
+    }
+    let output = out_dir.join(format!("{base}.{}", target.extension()));
+    Manifest::load(out_dir)
+        .get(&output)
+        .map(|entry| entry.input_paths())
+        .unwrap_or_default()
+}
+
+/// One permutation of a source: its feature mask and the defines it sets.
+#[derive(Clone, Copy)]
+struct Variant<'a> {
+    mask: u32,
+    defines: &'a [String],
+}
+
+/// Compile one source, or one permutation of it, unless the manifest shows
+/// its outputs are current. Variants are named `{stem}.{ext}.{mask}.*`.
+fn compile_with_manifest(
+    src_path: &Path,
+    out_dir: &Path,
+    target: ShaderTarget,
+    variant: Option<Variant>,
+    manifest: &Manifest,
+) -> Result<(CompiledShader, ManifestEntry), ShaderDiagnostic> {
+    if !is_shader_source(src_path) {
+        return Err(ShaderDiagnostic {
+            path: src_path.to_path_buf(),
+            tool: "prepare_shaders",
+            message: "not a recognised shader source extension".to_string(),
+        });
+    }
+    let hlsl = if is_hlsl(src_path) {
+        let (stage, entry) = hlsl_stage_and_entry(src_path).map_err(|message| ShaderDiagnostic {
+            path: src_path.to_path_buf(),
+            tool: "prepare_shaders",
+            message,
+        })?;
+        Some((stage, entry))
+    } else {
+        None
+    };
+
+    let (Some(stem), Some(ext)) = (
+        src_path.file_stem().and_then(|s| s.to_str()),
+        src_path.extension().and_then(|s| s.to_str()),
+    ) else {
+        return Err(ShaderDiagnostic {
+            path: src_path.to_path_buf(),
+            tool: "prepare_shaders",
+            message: "shader file names must be valid UTF-8".to_string(),
+        });
+    };
+    let base = match &variant {
+        Some(v) => format!("{stem}.{ext}.{}", v.mask),
+        None => format!("{stem}.{ext}"),
+    };
+    let defines = variant.as_ref().map_or(&[][..], |v| v.defines);
+
+    let json_path = out_dir.join(format!("{base}.json"));
+    let output = out_dir.join(format!("{base}.{}", target.extension()));
+    let spv_path = out_dir.join(format!("{base}.spv"));
+    let dep_path = out_dir.join(format!("{base}.d"));
+
+    #[cfg(feature = "naga")]
+    let naga = if hlsl.is_none() { naga_source(src_path) } else { None };
+    #[cfg(not(feature = "naga"))]
+    let naga: Option<()> = None;
+
+    let mut options = format!("{target:?}");
+    if naga.is_some() {
+        options.push_str(" naga");
+    }
+    if let Some((stage, entry)) = &hlsl {
+        options.push_str(&format!(" hlsl {stage} {entry}"));
+    }
+    for define in defines {
+        options.push_str(" -D");
+        options.push_str(define);
+    }
+    let outputs = match target {
+        ShaderTarget::Spirv => vec![output.clone(), json_path.clone()],
+        ShaderTarget::Msl if hlsl.is_none() => vec![output.clone(), json_path.clone()],
+        ShaderTarget::Msl | ShaderTarget::Dxil => vec![output.clone(), spv_path.clone(), json_path.clone()],
+    };
 
-        if !src_path.is_file() {
+    if let Some(entry) = manifest.get(&output)
+        && entry.is_up_to_date(&options, &outputs)
+    {
+        let compiled = CompiledShader { output, inputs: entry.input_paths(), rebuilt: false };
+        return Ok((compiled, entry.clone()));
+    }
+
+    #[cfg(feature = "naga")]
+    if let Some((language, stage)) = naga {
+        let inputs = compile_with_naga(src_path, language, stage, defines, target, &output, &spv_path, &json_path)?;
+        let entry = ManifestEntry::new(options, outputs, &inputs);
+        return Ok((CompiledShader { output, inputs, rebuilt: true }, entry));
+    }
+
+    let inputs = if let Some((stage, entry)) = &hlsl {
+        compile_hlsl(src_path, stage, entry, defines, target, &output, &spv_path, &json_path)?
+    } else if target == ShaderTarget::Msl {
+        // Compile GLSL -> MSL directly with glslcc
+        progress(&format!("glslcc {} --lang=msl --reflect", src_path.display()));
+
+        let mut cmd = Command::new("glslcc");
+        cmd.arg(path_flag(&format!("--{ext}="), src_path))
+            .arg(path_flag("--output=", &output))
+            .arg("--lang=msl")
+            .arg(path_flag("--reflect=", &json_path));
+        if !defines.is_empty() {
+            cmd.arg(format!("--defines={}", defines.join(",")));
+        }
+        run_tool(&mut cmd, "glslcc", src_path)?;
+
+        // glslcc writes no depfile; follow the includes ourselves.
+        let mut includes = BTreeSet::new();
+        collect_includes(src_path, &mut includes);
+        std::iter::once(src_path.to_path_buf()).chain(includes).collect()
+    } else {
+        // Compile GLSL -> SPIR-V with glslc, recording includes in a depfile
+        progress(&format!("glslc {} -o {}", src_path.display(), spv_path.display()));
+
+        run_tool(
+            Command::new("glslc")
+                .arg(src_path)
+                .arg("-o")
+                .arg(&spv_path)
+                .args(["-MD", "-MF"])
+                .arg(&dep_path)
+                .args(defines.iter().map(|d| format!("-D{d}"))),
+            "glslc",
+            src_path,
+        )?;
+
+        // Convert SPIR-V -> DXIL with shadercross
+        if target == ShaderTarget::Dxil {
+            progress(&format!("shadercross {} -o {}", spv_path.display(), output.display()));
+
+            run_tool(
+                Command::new("shadercross").arg(&spv_path).arg("-o").arg(&output),
+                "shadercross",
+                &spv_path,
+            )?;
+        }
+
+        // Generate reflection JSON from SPIR-V with shadercross
+        progress(&format!("shadercross {} -d JSON -o {}", spv_path.display(), json_path.display()));
+
+        run_tool(
+            Command::new("shadercross").arg(&spv_path).args(["-d", "JSON", "-o"]).arg(&json_path),
+            "shadercross (reflect)",
+            &spv_path,
+        )?;
+
+        let mut inputs = vec![src_path.to_path_buf()];
+        if let Ok(text) = fs::read_to_string(&dep_path) {
+            for dep in parse_depfile(&text) {
+                if !inputs.contains(&dep) {
+                    inputs.push(dep);
+                }
+            }
+        }
+        inputs
+    };
+
+    let entry = ManifestEntry::new(options, outputs, &inputs);
+    Ok((CompiledShader { output, inputs, rebuilt: true }, entry))
+}
+
+/// Compile GLSL or WGSL in-process with naga, writing the output and its
+/// reflection JSON. DXIL is converted from the SPIR-V with shadercross.
+#[cfg(feature = "naga")]
+#[allow(clippy::too_many_arguments)]
+fn compile_with_naga(
+    src_path: &Path,
+    language: SourceLanguage,
+    stage: Option<NagaStage>,
+    defines: &[String],
+    target: ShaderTarget,
+    output: &Path,
+    spv_path: &Path,
+    json_path: &Path,
+) -> Result<Vec<PathBuf>, ShaderDiagnostic> {
+    let diagnostic = |message: String| ShaderDiagnostic { path: src_path.to_path_buf(), tool: "naga", message };
+    let write = |path: &Path, data: &[u8]| {
+        fs::write(path, data).map_err(|e| diagnostic(format!("failed to write {}: {e}", path.display())))
+    };
+
+    // naga's GLSL front end has no #include; splice included files in.
+    let mut includes = BTreeSet::new();
+    let source = match language {
+        SourceLanguage::Glsl => expand_includes(src_path, &mut includes).map_err(diagnostic)?,
+        SourceLanguage::Wgsl => fs::read_to_string(src_path).map_err(|e| diagnostic(e.to_string()))?,
+    };
+
+    progress(&format!("naga {} -o {}", src_path.display(), output.display()));
+    let naga_target = if target == ShaderTarget::Msl { ShaderTarget::Msl } else { ShaderTarget::Spirv };
+    let compiled = shader_compiler::compile(&source, language, stage, defines, naga_target)
+        .map_err(|e| diagnostic(e.to_string()))?;
+
+    if target == ShaderTarget::Dxil {
+        write(spv_path, &compiled.code)?;
+        progress(&format!("shadercross {} -o {}", spv_path.display(), output.display()));
+        run_tool(
+            Command::new("shadercross")
+                .arg(spv_path)
+                .args(["-e", compiled.entrypoint.as_str(), "-o"])
+                .arg(output),
+            "shadercross",
+            spv_path,
+        )?;
+    } else {
+        write(output, &compiled.code)?;
+    }
+    write(json_path, compiled.reflection.to_json(compiled.stage, &compiled.entrypoint).as_bytes())?;
+
+    Ok(std::iter::once(src_path.to_path_buf()).chain(includes).collect())
+}
+
+/// Read `path` with every `#include "..."` line replaced by the included
+/// file, recursively. Each file is spliced in at most once.
+#[cfg(feature = "naga")]
+fn expand_includes(path: &Path, found: &mut BTreeSet<PathBuf>) -> Result<String, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let mut out = String::with_capacity(text.len());
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("#extension GL_GOOGLE_include_directive") {
+            out.push('\n');
             continue;
         }
+        let name = trimmed
+            .strip_prefix("#include")
+            .and_then(|rest| rest.trim().strip_prefix('"'))
+            .and_then(|rest| rest.split('"').next());
+        match name {
+            Some(name) => {
+                let include = dir.join(name);
+                if found.insert(include.clone()) {
+                    out.push_str(&expand_includes(&include, found)?);
+                } else {
+                    out.push('\n');
+                }
+            }
+            None => {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Compile HLSL with shadercross: to SPIR-V for reflection (and Vulkan), and
+/// straight from the HLSL to DXIL or MSL when that is the target.
+#[allow(clippy::too_many_arguments)]
+fn compile_hlsl(
+    src_path: &Path,
+    stage: &str,
+    entry: &str,
+    defines: &[String],
+    target: ShaderTarget,
+    output: &Path,
+    spv_path: &Path,
+    json_path: &Path,
+) -> Result<Vec<PathBuf>, ShaderDiagnostic> {
+    let hlsl_cmd = |dest: &str, out: &Path| {
+        let mut cmd = Command::new("shadercross");
+        cmd.arg(src_path)
+            .args(["-s", "HLSL", "-d", dest, "-t", stage, "-e", entry, "-o"])
+            .arg(out);
+        if let Some(dir) = src_path.parent() {
+            cmd.arg("-I").arg(dir);
+        }
+        cmd.args(defines.iter().map(|d| format!("-D{d}")));
+        cmd
+    };
+
+    progress(&format!("shadercross {} -s HLSL -d SPIRV -o {}", src_path.display(), spv_path.display()));
+    run_tool(&mut hlsl_cmd("SPIRV", spv_path), "shadercross", src_path)?;
+
+    let dest = match target {
+        ShaderTarget::Spirv => None,
+        ShaderTarget::Dxil => Some("DXIL"),
+        ShaderTarget::Msl => Some("MSL"),
+    };
+    if let Some(dest) = dest {
+        progress(&format!("shadercross {} -s HLSL -d {dest} -o {}", src_path.display(), output.display()));
+        run_tool(&mut hlsl_cmd(dest, output), "shadercross", src_path)?;
+    }
+
+    // Generate reflection JSON from SPIR-V with shadercross
+    progress(&format!("shadercross {} -d JSON -o {}", spv_path.display(), json_path.display()));
+    run_tool(
+        Command::new("shadercross").arg(spv_path).args(["-d", "JSON", "-o"]).arg(json_path),
+        "shadercross (reflect)",
+        spv_path,
+    )?;
 
-        let Some(_stage) = shader_stage(&src_path) else {
+    // shadercross writes no depfile; follow the includes ourselves.
+    let mut includes = BTreeSet::new();
+    collect_includes(src_path, &mut includes);
+    Ok(std::iter::once(src_path.to_path_buf()).chain(includes).collect())
+}
+
+/// Collect the files pulled in by `#include "..."` directives, recursively,
+/// resolving each relative to the including file.
+fn collect_includes(source: &Path, found: &mut BTreeSet<PathBuf>) {
+    let Ok(text) = fs::read_to_string(source) else {
+        return;
+    };
+    let dir = source.parent().unwrap_or(Path::new(""));
+    for line in text.lines() {
+        let Some(rest) = line.trim_start().strip_prefix("#include") else {
+            continue;
+        };
+        let Some(name) = rest.trim().strip_prefix('"').and_then(|r| r.split('"').next()) else {
             continue;
         };
+        let include = dir.join(name);
+        if include.is_file() && found.insert(include.clone()) {
+            collect_includes(&include, found);
+        }
+    }
+}
+
+/// Compile every shader source in `shader_dir` into `shader_intermediary_dir`
+/// for the target OS of the current cargo build.
+///
+/// GLSL sources are recognised by their stage extension (`.vert`, `.frag`,
+/// `.comp`, ...). `.hlsl` sources declare their stage by name
+/// (`lit.vert.hlsl`, `lit.ps.hlsl`) or with `//! stage: fragment`, and may
+/// name their entry point with `//! entry: PSMain` (default `main`).
+///
+/// Intended to be called from a build script. Emits `cargo:rerun-if-changed`
+/// for the shader dir, every source and every included file, and compiles
+/// independent sources in parallel. All sources are attempted; failures are
+/// reported through `cargo:warning` and returned together.
+///
+/// Sources with a permutation spec (a `{name}.toml` sidecar, or
+/// `//! features:` / `//! variant:` lines) are compiled once per variant,
+/// and a [`PermutationTable`] is written as `{name}.permutations` once all
+/// of its variants compiled.
+pub fn prepare_shaders(shader_dir : &Path, shader_intermediary_dir : &Path) -> Result<(), PrepareShadersError> {
+    let out_dir = PathBuf::from(shader_intermediary_dir);
+    let target = ShaderTarget::for_build_target();
 
-        let stem = src_path.file_stem().unwrap().to_str().unwrap();
-        let ext = src_path.extension().unwrap().to_str().unwrap();
+    println!("cargo:rerun-if-changed={}", shader_dir.display());
 
-        let json_name = format!("{stem}.{ext}.json");
-        let json_path = out_dir.join(&json_name);
+    if !shader_dir.exists() {
+        return Ok(());
+    }
 
-        if apple {
-            // Compile GLSL -> MSL directly with glslcc
-            let msl_name = format!("{stem}.{ext}.msl");
-            let msl_path = out_dir.join(&msl_name);
+    fs::create_dir_all(&out_dir)
+        .map_err(|error| PrepareShadersError::Io { path: out_dir.clone(), error })?;
 
-            if needs_rebuild(&src_path, &msl_path) {
-                let stage_flag = format!("--{}={}", ext, src_path.to_str().unwrap());
-                let output_flag = format!("--output={}", msl_path.to_str().unwrap());
-                let reflect_flag = format!("--reflect={}", json_path.to_str().unwrap());
-                log(&format!("glslcc {} --lang=msl --reflect", src_path.display()));
+    let entries = fs::read_dir(shader_dir)
+        .map_err(|error| PrepareShadersError::Io { path: shader_dir.to_path_buf(), error })?;
 
-                let status = Command::new("glslcc")
-                    .args([&stage_flag, &output_flag, "--lang=msl", &reflect_flag])
-                    .status()
-                    .expect("failed to run glslcc — is it installed?");
+    let mut sources = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|error| PrepareShadersError::Io { path: shader_dir.to_path_buf(), error })?;
+        let src_path = entry.path();
 
-                if !status.success() {
-                    panic!("glslcc failed for {}", src_path.display());
-                }
+        if !src_path.is_file() || !is_shader_source(&src_path) {
+            continue;
+        }
+
+        println!("cargo:rerun-if-changed={}", src_path.display());
+        let sidecar = permutations::sidecar_path(&src_path);
+        if sidecar.is_file() {
+            println!("cargo:rerun-if-changed={}", sidecar.display());
+        }
+        sources.push(src_path);
+    }
+    sources.sort();
+
+    let mut failures = Vec::new();
+    let mut specs = Vec::new();
+    for src_path in &sources {
+        match permutations::read_spec(src_path) {
+            Ok(spec) => specs.push(spec),
+            Err(message) => {
+                let diagnostic = ShaderDiagnostic { path: src_path.clone(), tool: "prepare_shaders", message };
+                warn(&diagnostic.to_string());
+                failures.push(diagnostic);
+                specs.push(None);
             }
-        } else {
-            // Compile GLSL -> SPIR-V with glslc
-            let spv_name = format!("{stem}.{ext}.spv");
-            let spv_path = out_dir.join(&spv_name);
-
-            if needs_rebuild(&src_path, &spv_path) {
-                log(&format!("glslc {} -o {}", src_path.display(), spv_path.display()));
-
-                let status = Command::new("glslc")
-                    .args([
-                        src_path.to_str().unwrap(),
-                        "-o",
-                        spv_path.to_str().unwrap(),
-                    ])
-                    .status()
-                    .expect("failed to run glslc — is it installed?");
-
-                if !status.success() {
-                    panic!("glslc failed for {}", src_path.display());
+        }
+    }
+    let defines: Vec<Vec<Vec<String>>> = specs
+        .iter()
+        .map(|spec| match spec {
+            Some(spec) => spec.variants.iter().map(|&m| spec.defines(m)).collect(),
+            None => Vec::new(),
+        })
+        .collect();
+    // One job per source, or per variant for sources with a spec.
+    let mut jobs: Vec<(usize, Option<Variant>)> = Vec::new();
+    for (i, spec) in specs.iter().enumerate() {
+        match spec {
+            Some(spec) => {
+                for (v, &mask) in spec.variants.iter().enumerate() {
+                    jobs.push((i, Some(Variant { mask, defines: &defines[i][v] })));
                 }
             }
+            None => jobs.push((i, None)),
+        }
+    }
+
+    let manifest = Manifest::load(&out_dir);
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::new());
+    let workers = std::thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(jobs.len());
 
-            // Convert SPIR-V -> DXIL with shadercross (Windows only)
-            if windows {
-                let dxil_name = format!("{stem}.{ext}.dxil");
-                let dxil_path = out_dir.join(&dxil_name);
-
-                if needs_rebuild(&spv_path, &dxil_path) {
-                    log(&format!("shadercross {} -o {}", spv_path.display(), dxil_path.display()));
-
-                    let status = Command::new("shadercross")
-                        .args([
-                            spv_path.to_str().unwrap(),
-                            "-o",
-                            dxil_path.to_str().unwrap(),
-                        ])
-                        .status()
-                        .expect("failed to run shadercross — is it installed?");
-
-                    if !status.success() {
-                        panic!("shadercross failed for {}", spv_path.display());
-                    }
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(&(src, variant)) = jobs.get(i) else {
+                    break;
+                };
+                let mask = variant.as_ref().map(|v| v.mask);
+                let result = compile_with_manifest(&sources[src], &out_dir, target, variant, &manifest);
+                if let Err(diagnostic) = &result {
+                    warn(&diagnostic.to_string());
                 }
-            }
+                results.lock().unwrap().push((src, mask, result));
+            });
+        }
+    });
 
-            // Generate reflection JSON from SPIR-V with shadercross
-            if needs_rebuild(&spv_path, &json_path) {
-                log(&format!("shadercross {} -d JSON -o {}", spv_path.display(), json_path.display()));
-
-                let status = Command::new("shadercross")
-                    .args([
-                        spv_path.to_str().unwrap(),
-                        "-d", "JSON",
-                        "-o", json_path.to_str().unwrap(),
-                    ])
-                    .status()
-                    .expect("failed to run shadercross — is it installed?");
-
-                if !status.success() {
-                    panic!("shadercross (reflect) failed for {}", spv_path.display());
+    let mut manifest = manifest;
+    let mut includes = BTreeSet::new();
+    let mut variant_files: Vec<Vec<(u32, String)>> = vec![Vec::new(); sources.len()];
+    for (src, mask, result) in results.into_inner().unwrap() {
+        match result {
+            Ok((compiled, entry)) => {
+                if let (Some(mask), Some(name)) = (mask, compiled.output.file_name()) {
+                    variant_files[src].push((mask, name.to_string_lossy().into_owned()));
+                }
+                includes.extend(compiled.inputs.into_iter().filter(|p| !sources.contains(p)));
+                manifest.insert(compiled.output, entry);
+            }
+            Err(diagnostic) => failures.push(diagnostic),
+        }
+    }
+    for ((src_path, spec), files) in sources.iter().zip(&specs).zip(variant_files) {
+        let Some(spec) = spec else {
+            continue;
+        };
+        let name = src_path.file_name().unwrap().to_string_lossy();
+        let table_path = out_dir.join(format!("{name}.permutations"));
+        if files.len() < spec.variants.len() {
+            // A variant failed and is reported above; a table without it,
+            // or one left from an earlier build, would hide that.
+            match fs::remove_file(&table_path) {
+                Err(error) if error.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(PrepareShadersError::Io { path: table_path, error });
                 }
+                _ => continue,
             }
         }
+        let table = PermutationTable::new(spec, files);
+        fs::write(&table_path, table.to_text())
+            .map_err(|error| PrepareShadersError::Io { path: table_path, error })?;
+    }
+    for include in &includes {
+        println!("cargo:rerun-if-changed={}", include.display());
+    }
+    manifest.save(&out_dir)
+        .map_err(|error| PrepareShadersError::Io { path: out_dir.clone(), error })?;
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        failures.sort_by(|a, b| a.path.cmp(&b.path));
+        Err(PrepareShadersError::Compile(failures))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permutation_table_is_not_written_when_a_variant_fails() {
+        let dir = std::env::temp_dir().join(format!("sdl3_gs_prepare_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let (shader_dir, out_dir) = (dir.join("shaders"), dir.join("out"));
+        fs::create_dir_all(&shader_dir).unwrap();
+        fs::create_dir_all(&out_dir).unwrap();
+        fs::write(
+            shader_dir.join("lit.frag"),
+            "#version 450\n//! features: BROKEN\n#ifdef BROKEN\n#error broken variant\n#endif\nvoid main() {}\n",
+        )
+        .unwrap();
+        let table_path = out_dir.join("lit.frag.permutations");
+        fs::write(&table_path, "stale").unwrap();
+
+        let Err(PrepareShadersError::Compile(failures)) = prepare_shaders(&shader_dir, &out_dir) else {
+            panic!("the BROKEN variant should fail");
+        };
+        assert!(failures.iter().all(|f| f.path == shader_dir.join("lit.frag")));
+        assert!(!table_path.exists());
+        fs::remove_dir_all(&dir).unwrap();
     }
 }
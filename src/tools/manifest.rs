@@ -0,0 +1,220 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const FILE_NAME: &str = "shader_manifest.txt";
+
+/// 64-bit FNV-1a. Stable across Rust versions, unlike `DefaultHasher`,
+/// so manifests stay valid when the toolchain changes.
+pub(super) fn hash_bytes(data: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &b in data {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+fn hash_file(path: &Path) -> Option<u64> {
+    fs::read(path).ok().map(|data| hash_bytes(&data))
+}
+
+/// What one compiled shader was built from: the compiler options, the
+/// outputs it produced, and the content hash of every transitive input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct ManifestEntry {
+    pub options: String,
+    pub outputs: Vec<PathBuf>,
+    pub inputs: Vec<(PathBuf, u64)>,
+}
+
+impl ManifestEntry {
+    /// Record `inputs` as they are on disk now.
+    pub fn new(options: String, outputs: Vec<PathBuf>, inputs: &[PathBuf]) -> Self {
+        let inputs = inputs
+            .iter()
+            .map(|p| (p.clone(), hash_file(p).unwrap_or(0)))
+            .collect();
+        Self { options, outputs, inputs }
+    }
+
+    /// True if the options match, every output exists, and no input's
+    /// content changed since the entry was recorded.
+    pub fn is_up_to_date(&self, options: &str, outputs: &[PathBuf]) -> bool {
+        self.options == options
+            && self.outputs == outputs
+            && self.outputs.iter().all(|p| p.is_file())
+            && self.inputs.iter().all(|(p, h)| hash_file(p) == Some(*h))
+    }
+
+    pub fn input_paths(&self) -> Vec<PathBuf> {
+        self.inputs.iter().map(|(p, _)| p.clone()).collect()
+    }
+}
+
+/// The manifest stored in the shader intermediary dir, keyed by each
+/// shader's primary output path.
+#[derive(Default)]
+pub(super) struct Manifest {
+    entries: BTreeMap<PathBuf, ManifestEntry>,
+}
+
+impl Manifest {
+    /// Load the manifest from `out_dir`. A missing or unreadable manifest is
+    /// treated as empty, which rebuilds everything.
+    pub fn load(out_dir: &Path) -> Self {
+        let mut manifest = Manifest::default();
+        let Ok(text) = fs::read_to_string(out_dir.join(FILE_NAME)) else {
+            return manifest;
+        };
+        let mut current: Option<(PathBuf, ManifestEntry)> = None;
+        for line in text.lines() {
+            let mut fields = line.splitn(3, '\t');
+            match (fields.next(), fields.next(), fields.next()) {
+                (Some("shader"), Some(key), options) => {
+                    if let Some((k, e)) = current.take() {
+                        manifest.entries.insert(k, e);
+                    }
+                    current = Some((PathBuf::from(key), ManifestEntry {
+                        options: options.unwrap_or("").to_string(),
+                        outputs: Vec::new(),
+                        inputs: Vec::new(),
+                    }));
+                }
+                (Some("output"), Some(path), None) => {
+                    if let Some((_, e)) = current.as_mut() {
+                        e.outputs.push(PathBuf::from(path));
+                    }
+                }
+                (Some("input"), Some(hash), Some(path)) => {
+                    if let (Some((_, e)), Ok(hash)) = (current.as_mut(), u64::from_str_radix(hash, 16)) {
+                        e.inputs.push((PathBuf::from(path), hash));
+                    }
+                }
+                _ => {}
+            }
+        }
+        if let Some((k, e)) = current {
+            manifest.entries.insert(k, e);
+        }
+        manifest
+    }
+
+    pub fn save(&self, out_dir: &Path) -> io::Result<()> {
+        let mut text = String::new();
+        for (key, entry) in &self.entries {
+            text.push_str(&format!("shader\t{}\t{}\n", key.display(), entry.options));
+            for output in &entry.outputs {
+                text.push_str(&format!("output\t{}\n", output.display()));
+            }
+            for (path, hash) in &entry.inputs {
+                text.push_str(&format!("input\t{hash:016x}\t{}\n", path.display()));
+            }
+        }
+        fs::write(out_dir.join(FILE_NAME), text)
+    }
+
+    pub fn get(&self, key: &Path) -> Option<&ManifestEntry> {
+        self.entries.get(key)
+    }
+
+    pub fn insert(&mut self, key: PathBuf, entry: ManifestEntry) {
+        self.entries.insert(key, entry);
+    }
+}
+
+/// Parse a Makefile-style depfile as written by `glslc -MD -MF`, returning
+/// the prerequisites of its rule.
+pub(super) fn parse_depfile(text: &str) -> Vec<PathBuf> {
+    let joined = text.replace("\\\r\n", " ").replace("\\\n", " ");
+    let Some((_, deps)) = joined.split_once(": ") else {
+        return Vec::new();
+    };
+    let mut paths = Vec::new();
+    let mut current = String::new();
+    let mut chars = deps.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&' ') => {
+                current.push(' ');
+                chars.next();
+            }
+            c if c.is_whitespace() => {
+                if !current.is_empty() {
+                    paths.push(PathBuf::from(std::mem::take(&mut current)));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        paths.push(PathBuf::from(current));
+    }
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sdl3_gs_manifest_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn parse_depfile_reads_prerequisites() {
+        let deps = parse_depfile("out/lit.frag.spv: shaders/lit.frag shaders/common.glsl\n");
+        assert_eq!(deps, [PathBuf::from("shaders/lit.frag"), PathBuf::from("shaders/common.glsl")]);
+    }
+
+    #[test]
+    fn parse_depfile_handles_continuations_and_escaped_spaces() {
+        let text = "out/a.spv: shaders/a.vert \\\n  shaders/my\\ lib/common.glsl \\\r\n  shaders/b.glsl\n";
+        let deps = parse_depfile(text);
+        assert_eq!(deps, [
+            PathBuf::from("shaders/a.vert"),
+            PathBuf::from("shaders/my lib/common.glsl"),
+            PathBuf::from("shaders/b.glsl"),
+        ]);
+    }
+
+    #[test]
+    fn parse_depfile_without_rule_is_empty() {
+        assert!(parse_depfile("").is_empty());
+        assert!(parse_depfile("no rule here").is_empty());
+    }
+
+    #[test]
+    fn manifest_round_trips() {
+        let dir = temp_dir("round_trip");
+        let input = dir.join("lit.frag");
+        fs::write(&input, "void main() {}").unwrap();
+        let output = dir.join("lit.frag.spv");
+        fs::write(&output, [0u8; 4]).unwrap();
+
+        let entry = ManifestEntry::new("Spirv -DSHADOWS".to_string(), vec![output.clone()], std::slice::from_ref(&input));
+        let mut manifest = Manifest::default();
+        manifest.insert(output.clone(), entry.clone());
+        manifest.save(&dir).unwrap();
+
+        let loaded = Manifest::load(&dir);
+        assert_eq!(loaded.get(&output), Some(&entry));
+        assert!(loaded.get(&output).unwrap().is_up_to_date("Spirv -DSHADOWS", std::slice::from_ref(&output)));
+        assert!(!loaded.get(&output).unwrap().is_up_to_date("Spirv", std::slice::from_ref(&output)));
+
+        fs::write(&input, "void main() { }").unwrap();
+        assert!(!loaded.get(&output).unwrap().is_up_to_date("Spirv -DSHADOWS", std::slice::from_ref(&output)));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing_manifest_loads_empty() {
+        let dir = temp_dir("missing");
+        assert!(Manifest::load(&dir).get(&dir.join("any.spv")).is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,401 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Most features a spec without listed variants may declare: every
+/// combination is built, so each one doubles the compile jobs.
+const MAX_EXPANDED_FEATURES: usize = 8;
+
+/// The variants to build from one shader source.
+///
+/// Bit `i` of a variant mask means feature `i` is defined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct PermutationSpec {
+    pub features: Vec<String>,
+    pub variants: Vec<u32>,
+}
+
+impl PermutationSpec {
+    /// The `-D` defines for a variant mask.
+    pub fn defines(&self, mask: u32) -> Vec<String> {
+        self.features
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| mask & (1 << i) != 0)
+            .map(|(_, f)| f.clone())
+            .collect()
+    }
+
+    fn from_sets(mut features: Vec<String>, sets: Vec<Vec<String>>) -> Result<Self, String> {
+        for set in &sets {
+            for f in set {
+                if !features.contains(f) {
+                    features.push(f.clone());
+                }
+            }
+        }
+        if features.len() > 32 {
+            return Err(format!("{} features declared, at most 32 are supported", features.len()));
+        }
+        if sets.is_empty() && features.len() > MAX_EXPANDED_FEATURES {
+            return Err(format!(
+                "{} features would build {} variants; list the ones to build with `variant:` lines \
+                 (or a `variants` array), or declare at most {MAX_EXPANDED_FEATURES} features",
+                features.len(),
+                1u64 << features.len()
+            ));
+        }
+        let mut variants: Vec<u32> = if sets.is_empty() {
+            (0..1u32 << features.len()).collect()
+        } else {
+            sets.iter()
+                .map(|set| {
+                    set.iter()
+                        .map(|f| 1u32 << features.iter().position(|x| x == f).unwrap())
+                        .fold(0, |a, b| a | b)
+                })
+                .collect()
+        };
+        variants.sort_unstable();
+        variants.dedup();
+        Ok(Self { features, variants })
+    }
+}
+
+/// Path of the sidecar spec for a source, e.g. `lit.frag.toml` for `lit.frag`.
+pub(super) fn sidecar_path(src_path: &Path) -> PathBuf {
+    let mut name = src_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".toml");
+    src_path.with_file_name(name)
+}
+
+/// Read the permutation spec for `src_path`, from its sidecar TOML if one
+/// exists, otherwise from `//! features:` and `//! variant:` lines in the
+/// source. Returns `Ok(None)` for sources without permutations.
+///
+/// `features` alone builds every combination, for up to 8 features;
+/// `variant` lines (or a `variants` array) build only the listed define sets.
+pub(super) fn read_spec(src_path: &Path) -> Result<Option<PermutationSpec>, String> {
+    let sidecar = sidecar_path(src_path);
+    if sidecar.is_file() {
+        let text = fs::read_to_string(&sidecar).map_err(|e| format!("{}: {e}", sidecar.display()))?;
+        return parse_sidecar(&text)
+            .map(Some)
+            .map_err(|e| format!("{}: {e}", sidecar.display()));
+    }
+
+    let Ok(text) = fs::read_to_string(src_path) else {
+        return Ok(None);
+    };
+    let mut features = Vec::new();
+    let mut sets = Vec::new();
+    let mut any = false;
+    for line in text.lines() {
+        let Some(rest) = line.trim_start().strip_prefix("//!") else {
+            continue;
+        };
+        let rest = rest.trim();
+        if let Some(list) = rest.strip_prefix("features:") {
+            features.extend(list.split_whitespace().map(str::to_string));
+            any = true;
+        } else if let Some(list) = rest.strip_prefix("variant:") {
+            sets.push(list.split_whitespace().map(str::to_string).collect());
+            any = true;
+        }
+    }
+    if !any {
+        return Ok(None);
+    }
+    PermutationSpec::from_sets(features, sets).map(Some)
+}
+
+#[derive(Debug, PartialEq)]
+enum Value {
+    Str(String),
+    Array(Vec<Value>),
+}
+
+/// Parse the TOML subset used by sidecar specs:
+///
+/// ```toml
+/// features = ["SKINNING", "SHADOWS", "ALPHA_TEST"]
+/// variants = [[], ["SKINNING"], ["SHADOWS", "ALPHA_TEST"]]
+/// ```
+fn parse_sidecar(text: &str) -> Result<PermutationSpec, String> {
+    let mut chars = text.chars().peekable();
+    let mut features = Vec::new();
+    let mut sets = Vec::new();
+
+    fn skip_ws(chars: &mut std::iter::Peekable<std::str::Chars>) {
+        while let Some(&c) = chars.peek() {
+            if c == '#' {
+                while chars.next_if(|&c| c != '\n').is_some() {}
+            } else if c.is_whitespace() {
+                chars.next();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn value(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<Value, String> {
+        skip_ws(chars);
+        match chars.next() {
+            Some('"') => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => return Ok(Value::Str(s)),
+                        Some(c) => s.push(c),
+                        None => return Err("unterminated string".to_string()),
+                    }
+                }
+            }
+            Some('[') => {
+                let mut items = Vec::new();
+                loop {
+                    skip_ws(chars);
+                    if chars.next_if_eq(&']').is_some() {
+                        return Ok(Value::Array(items));
+                    }
+                    items.push(value(chars)?);
+                    skip_ws(chars);
+                    if chars.next_if_eq(&',').is_none() {
+                        skip_ws(chars);
+                        if chars.next_if_eq(&']').is_some() {
+                            return Ok(Value::Array(items));
+                        }
+                        return Err("expected ',' or ']'".to_string());
+                    }
+                }
+            }
+            Some(c) => Err(format!("unexpected '{c}'")),
+            None => Err("unexpected end of file".to_string()),
+        }
+    }
+
+    fn strings(v: Value) -> Result<Vec<String>, String> {
+        match v {
+            Value::Array(items) => items
+                .into_iter()
+                .map(|i| match i {
+                    Value::Str(s) => Ok(s),
+                    Value::Array(_) => Err("expected a string".to_string()),
+                })
+                .collect(),
+            Value::Str(_) => Err("expected an array of strings".to_string()),
+        }
+    }
+
+    loop {
+        skip_ws(&mut chars);
+        if chars.peek().is_none() {
+            break;
+        }
+        let mut key = String::new();
+        while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+            key.push(c);
+        }
+        skip_ws(&mut chars);
+        if chars.next_if_eq(&'=').is_none() {
+            return Err(format!("expected '=' after '{key}'"));
+        }
+        let v = value(&mut chars)?;
+        match key.as_str() {
+            "features" => features = strings(v)?,
+            "variants" => match v {
+                Value::Array(items) => {
+                    sets = items.into_iter().map(strings).collect::<Result<_, _>>()?;
+                }
+                Value::Str(_) => return Err("`variants` must be an array of arrays".to_string()),
+            },
+            _ => return Err(format!("unknown key '{key}'")),
+        }
+    }
+
+    PermutationSpec::from_sets(features, sets)
+}
+
+/// Maps feature bitmasks to the compiled variants of one shader source.
+///
+/// Written by [`prepare_shaders`](super::prepare_shaders) next to the
+/// compiled outputs as `{stem}.{ext}.permutations`; bit `i` of a mask
+/// stands for `features()[i]`.
+#[derive(Debug, Clone, Default)]
+pub struct PermutationTable {
+    features: Vec<String>,
+    variants: BTreeMap<u32, String>,
+}
+
+impl PermutationTable {
+    pub(super) fn new(spec: &PermutationSpec, file_names: impl IntoIterator<Item = (u32, String)>) -> Self {
+        Self {
+            features: spec.features.clone(),
+            variants: file_names.into_iter().collect(),
+        }
+    }
+
+    /// Load the table for `source_name` (e.g. `"lit.frag"`) from the
+    /// shader intermediary dir.
+    pub fn load(shader_intermediary_dir: &Path, source_name: &str) -> Result<Self, String> {
+        let path = shader_intermediary_dir.join(format!("{source_name}.permutations"));
+        let text = fs::read_to_string(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+        Self::parse(&text)
+    }
+
+    /// Parse a table, e.g. one embedded with `include_str!`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut table = Self::default();
+        for line in text.lines() {
+            let mut fields = line.split('\t');
+            match (fields.next(), fields.next(), fields.next()) {
+                (Some("feature"), Some(name), None) => table.features.push(name.to_string()),
+                (Some("variant"), Some(mask), Some(file)) => {
+                    let mask = mask.parse().map_err(|_| format!("invalid variant mask '{mask}'"))?;
+                    table.variants.insert(mask, file.to_string());
+                }
+                (Some(""), None, None) => {}
+                _ => return Err(format!("invalid permutation table line '{line}'")),
+            }
+        }
+        Ok(table)
+    }
+
+    pub(super) fn to_text(&self) -> String {
+        let mut text = String::new();
+        for f in &self.features {
+            text.push_str(&format!("feature\t{f}\n"));
+        }
+        for (mask, file) in &self.variants {
+            text.push_str(&format!("variant\t{mask}\t{file}\n"));
+        }
+        text
+    }
+
+    /// Feature names, in bit order.
+    pub fn features(&self) -> &[String] {
+        &self.features
+    }
+
+    /// The bitmask for a set of feature names, or `None` if one is unknown.
+    pub fn mask(&self, features: &[&str]) -> Option<u32> {
+        features.iter().try_fold(0u32, |mask, name| {
+            let bit = self.features.iter().position(|f| f == name)?;
+            Some(mask | (1 << bit))
+        })
+    }
+
+    /// File name of the compiled variant for `mask`, relative to the
+    /// shader intermediary dir.
+    pub fn variant(&self, mask: u32) -> Option<&str> {
+        self.variants.get(&mask).map(String::as_str)
+    }
+
+    /// Iterate over `(mask, file name)` for every compiled variant.
+    pub fn variants(&self) -> impl Iterator<Item = (u32, &str)> {
+        self.variants.iter().map(|(m, f)| (*m, f.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn features_alone_build_every_combination() {
+        let spec = PermutationSpec::from_sets(strings(&["A", "B"]), Vec::new()).unwrap();
+        assert_eq!(spec.variants, [0, 1, 2, 3]);
+        assert_eq!(spec.defines(2), ["B"]);
+        assert_eq!(spec.defines(3), ["A", "B"]);
+    }
+
+    #[test]
+    fn listed_variants_build_only_those() {
+        let sets = vec![Vec::new(), strings(&["C"]), strings(&["A", "C"]), strings(&["C"])];
+        let spec = PermutationSpec::from_sets(strings(&["A"]), sets).unwrap();
+        assert_eq!(spec.features, ["A", "C"]);
+        assert_eq!(spec.variants, [0, 2, 3]);
+    }
+
+    #[test]
+    fn many_features_without_variants_are_rejected() {
+        let features: Vec<String> = (0..9).map(|i| format!("F{i}")).collect();
+        let err = PermutationSpec::from_sets(features.clone(), Vec::new()).unwrap_err();
+        assert!(err.contains("variant:"), "{err}");
+        assert!(PermutationSpec::from_sets(features, vec![Vec::new()]).is_ok());
+    }
+
+    #[test]
+    fn more_than_32_features_are_rejected() {
+        let features: Vec<String> = (0..33).map(|i| format!("F{i}")).collect();
+        assert!(PermutationSpec::from_sets(features, vec![Vec::new()]).is_err());
+    }
+
+    #[test]
+    fn sidecar_is_parsed() {
+        let text = r#"
+            # permutations of lit.frag
+            features = ["SKINNING", "SHADOWS", "ALPHA_TEST"]
+            variants = [[], ["SKINNING"], ["SHADOWS", "ALPHA_TEST"],]
+        "#;
+        let spec = parse_sidecar(text).unwrap();
+        assert_eq!(spec.features, ["SKINNING", "SHADOWS", "ALPHA_TEST"]);
+        assert_eq!(spec.variants, [0, 1, 2 | 4]);
+    }
+
+    #[test]
+    fn sidecar_errors_are_reported() {
+        assert!(parse_sidecar("features = [\"A\"").is_err());
+        assert!(parse_sidecar("features [\"A\"]").is_err());
+        assert!(parse_sidecar("colors = [\"A\"]").is_err());
+        assert!(parse_sidecar("variants = \"A\"").is_err());
+        assert!(parse_sidecar("features = [\"A\" \"B\"]").is_err());
+    }
+
+    #[test]
+    fn spec_is_read_from_source_comments() {
+        let dir = std::env::temp_dir().join(format!("sdl3_gs_permutations_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let src = dir.join("lit.frag");
+        fs::write(&src, "//! features: SHADOWS FOG\n//! variant:\n//! variant: FOG\nvoid main() {}\n").unwrap();
+        let spec = read_spec(&src).unwrap().unwrap();
+        assert_eq!(spec.features, ["SHADOWS", "FOG"]);
+        assert_eq!(spec.variants, [0, 2]);
+
+        fs::write(sidecar_path(&src), "features = [\"A\"]\n").unwrap();
+        assert_eq!(read_spec(&src).unwrap().unwrap().features, ["A"]);
+
+        let plain = dir.join("plain.vert");
+        fs::write(&plain, "void main() {}\n").unwrap();
+        assert_eq!(read_spec(&plain).unwrap(), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn sidecar_path_appends_toml() {
+        assert_eq!(sidecar_path(Path::new("shaders/lit.frag")), Path::new("shaders/lit.frag.toml"));
+    }
+
+    #[test]
+    fn table_round_trips() {
+        let spec = PermutationSpec::from_sets(strings(&["A", "B"]), Vec::new()).unwrap();
+        let table = PermutationTable::new(&spec, [(0, "lit.frag.0.spv".to_string()), (3, "lit.frag.3.spv".to_string())]);
+        let parsed = PermutationTable::parse(&table.to_text()).unwrap();
+        assert_eq!(parsed.features(), ["A", "B"]);
+        assert_eq!(parsed.mask(&["B", "A"]), Some(3));
+        assert_eq!(parsed.mask(&["C"]), None);
+        assert_eq!(parsed.variant(3), Some("lit.frag.3.spv"));
+        assert_eq!(parsed.variant(1), None);
+        assert_eq!(parsed.variants().collect::<Vec<_>>(), [(0, "lit.frag.0.spv"), (3, "lit.frag.3.spv")]);
+    }
+
+    #[test]
+    fn invalid_table_lines_are_rejected() {
+        assert!(PermutationTable::parse("variant\tx\tlit.spv\n").is_err());
+        assert!(PermutationTable::parse("bogus\n").is_err());
+    }
+}
@@ -0,0 +1,359 @@
+//! Draw-state validation behind [`Device::set_draw_validation`].
+//!
+//! Pipelines record what their shaders declare; passes record which slots
+//! were bound. Before each draw or dispatch the two are compared, so a
+//! missing binding panics with a message naming the call, pipeline and slot
+//! instead of surfacing later as a driver error or garbage on screen.
+//!
+//! [`Device::set_draw_validation`]: crate::device::Device::set_draw_validation
+
+use crate::device::{SDL_GPUSampleCount, SDL_GPUTextureFormat};
+
+/// Resource slots a shader stage declares.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct StageResources {
+    pub samplers: u32,
+    pub storage_textures: u32,
+    pub storage_buffers: u32,
+}
+
+/// What a graphics pipeline needs bound before a draw.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct GraphicsRequirements {
+    /// Vertex buffer slots read by the vertex input state, as a bit mask.
+    pub vertex_buffers: u64,
+    pub vertex: StageResources,
+    pub fragment: StageResources,
+}
+
+/// What a compute pipeline needs bound before a dispatch. Read-write
+/// storage is bound when the pass begins and is not tracked.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct ComputeRequirements {
+    pub samplers: u32,
+    pub readonly_storage_textures: u32,
+    pub readonly_storage_buffers: u32,
+}
+
+/// Formats of a render pass's targets, or of the targets a graphics
+/// pipeline was created for. Checked when a pipeline is bound, with draw
+/// validation on.
+#[derive(Clone)]
+pub(crate) struct TargetFormats {
+    pub colors: Vec<SDL_GPUTextureFormat>,
+    pub depth_stencil: Option<SDL_GPUTextureFormat>,
+    pub sample_count: SDL_GPUSampleCount,
+}
+
+impl TargetFormats {
+    /// Why a pipeline created for `self` can't render into a pass with
+    /// `pass` targets. A pipeline without depth-stencil may still be used
+    /// in a pass that has one.
+    pub fn mismatch(&self, pass: &TargetFormats) -> Option<String> {
+        if self.colors.len() != pass.colors.len() {
+            return Some(format!(
+                "it has {} color targets, the pass has {}",
+                self.colors.len(),
+                pass.colors.len()
+            ));
+        }
+        if let Some(i) = (0..self.colors.len()).find(|&i| self.colors[i] != pass.colors[i]) {
+            return Some(format!(
+                "color target {i} has format {}, the pass target has format {}",
+                self.colors[i].0, pass.colors[i].0
+            ));
+        }
+        if let Some(format) = self.depth_stencil {
+            match pass.depth_stencil {
+                None => return Some("it has a depth-stencil target, the pass has none".to_string()),
+                Some(target) if target != format => {
+                    return Some(format!(
+                        "its depth-stencil target has format {}, the pass target has format {}",
+                        format.0, target.0
+                    ));
+                }
+                Some(_) => {}
+            }
+        }
+        if self.sample_count != pass.sample_count {
+            return Some(format!(
+                "it renders with {} samples, the pass targets have {}",
+                1 << self.sample_count.0,
+                1 << pass.sample_count.0
+            ));
+        }
+        None
+    }
+}
+
+/// Bindings made so far in a render pass.
+#[derive(Default)]
+pub(crate) struct RenderPassState {
+    pub pipeline: Option<(GraphicsRequirements, Option<String>)>,
+    pub index_buffer: bool,
+    pub vertex_buffers: u64,
+    pub vertex_samplers: u64,
+    pub vertex_storage_textures: u64,
+    pub vertex_storage_buffers: u64,
+    pub fragment_samplers: u64,
+    pub fragment_storage_textures: u64,
+    pub fragment_storage_buffers: u64,
+}
+
+/// Bindings made so far in a compute pass.
+#[derive(Default)]
+pub(crate) struct ComputePassState {
+    pub pipeline: Option<(ComputeRequirements, Option<String>)>,
+    pub samplers: u64,
+    pub storage_textures: u64,
+    pub storage_buffers: u64,
+}
+
+/// Bit mask of `count` slots starting at `first`.
+pub(crate) fn slots(first: u32, count: usize) -> u64 {
+    let mask = if count >= 64 { u64::MAX } else { (1u64 << count) - 1 };
+    mask.checked_shl(first).unwrap_or(0)
+}
+
+/// Panic with `call` and the pipeline's name in front of `problem`.
+fn fail(call: &str, pipeline: &Option<String>, problem: String) -> ! {
+    match pipeline {
+        Some(name) => panic!("{call} with pipeline \"{name}\": {problem}"),
+        None => panic!("{call}: {problem}"),
+    }
+}
+
+/// First of the `required` slots missing from `bound`.
+fn first_unbound(bound: u64, required: u32) -> Option<u32> {
+    (0..required.min(64)).find(|slot| bound & (1 << slot) == 0)
+}
+
+fn check_slots(call: &str, pipeline: &Option<String>, what: &str, bound: u64, required: u32) {
+    if let Some(slot) = first_unbound(bound, required) {
+        fail(call, pipeline, format!("{what} slot {slot} is not bound (the shader declares {required})"));
+    }
+}
+
+impl RenderPassState {
+    pub fn check_draw(&self, call: &str, indexed: bool) {
+        let Some((req, name)) = &self.pipeline else {
+            panic!("{call}: no graphics pipeline bound");
+        };
+        if indexed && !self.index_buffer {
+            fail(call, name, "no index buffer bound".to_string());
+        }
+        let missing = req.vertex_buffers & !self.vertex_buffers;
+        if missing != 0 {
+            fail(call, name, format!("vertex buffer slot {} is not bound", missing.trailing_zeros()));
+        }
+        check_slots(call, name, "vertex sampler", self.vertex_samplers, req.vertex.samplers);
+        check_slots(call, name, "vertex storage texture", self.vertex_storage_textures, req.vertex.storage_textures);
+        check_slots(call, name, "vertex storage buffer", self.vertex_storage_buffers, req.vertex.storage_buffers);
+        check_slots(call, name, "fragment sampler", self.fragment_samplers, req.fragment.samplers);
+        check_slots(call, name, "fragment storage texture", self.fragment_storage_textures, req.fragment.storage_textures);
+        check_slots(call, name, "fragment storage buffer", self.fragment_storage_buffers, req.fragment.storage_buffers);
+    }
+}
+
+impl ComputePassState {
+    pub fn check_dispatch(&self, call: &str) {
+        let Some((req, name)) = &self.pipeline else {
+            panic!("{call}: no compute pipeline bound");
+        };
+        check_slots(call, name, "sampler", self.samplers, req.samplers);
+        check_slots(call, name, "storage texture", self.storage_textures, req.readonly_storage_textures);
+        check_slots(call, name, "storage buffer", self.storage_buffers, req.readonly_storage_buffers);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::{
+        ColorTargetInfo, Device, GPUBuffer, GPUBufferBinding, GraphicsPipeline, RenderPass, SDL_GPUBufferUsageFlags,
+        SDL_GPUIndexElementSize, SDL_GPUShaderStage, SDL_GPUTextureFormat, SDL_GPUTextureUsageFlags,
+        StorageTextureReadWriteBinding, TextureSamplerBinding,
+    };
+    use crate::null_backend::tests::{COLOR_FORMAT, null_device, pipeline, sampler, shader, texture, vertex_buffer};
+
+    /// A validated device and a pipeline whose shaders declare
+    /// `vertex_samplers` and `fragment_samplers`.
+    fn setup(vertex_samplers: u32, fragment_samplers: u32) -> (Device, GraphicsPipeline) {
+        let (device, _log) = null_device();
+        device.set_draw_validation(true);
+        let vs = shader(&device, SDL_GPUShaderStage::VERTEX, vertex_samplers, 0);
+        let fs = shader(&device, SDL_GPUShaderStage::FRAGMENT, fragment_samplers, 0);
+        let pipeline = pipeline(&device, vs, fs, COLOR_FORMAT);
+        (device, pipeline)
+    }
+
+    /// Run `f` in a render pass into a fresh color target.
+    fn in_pass(device: &Device, f: impl FnOnce(&RenderPass, GPUBuffer)) {
+        let target = texture(device, SDL_GPUTextureUsageFlags::COLOR_TARGET);
+        let vertices = vertex_buffer(device);
+        let mut cmd = device.acquire_command_buffer().unwrap();
+        {
+            let pass = cmd.begin_render_pass(&[ColorTargetInfo::new(target)], None).unwrap();
+            f(&pass, vertices);
+        }
+        cmd.submit().unwrap();
+    }
+
+    #[test]
+    fn slot_masks() {
+        assert_eq!(super::slots(0, 0), 0);
+        assert_eq!(super::slots(2, 3), 0b11100);
+        assert_eq!(super::slots(0, 64), u64::MAX);
+        assert_eq!(super::slots(64, 1), 0);
+    }
+
+    #[test]
+    fn complete_bindings_draw() {
+        let (device, pipeline) = setup(1, 1);
+        let sampled = texture(&device, SDL_GPUTextureUsageFlags::SAMPLER);
+        let sampler = sampler(&device);
+        let index_buffer = device.create_buffer(SDL_GPUBufferUsageFlags::INDEX, 12).unwrap();
+        in_pass(&device, |pass, vertices| {
+            pass.bind_graphics_pipeline(pipeline);
+            pass.bind_vertex_buffers(0, &[GPUBufferBinding { buffer: vertices, offset: 0 }]);
+            pass.bind_vertex_samplers(0, &[TextureSamplerBinding { texture: sampled, sampler }]);
+            pass.bind_fragment_samplers(0, &[TextureSamplerBinding { texture: sampled, sampler }]);
+            pass.draw_primitives(3, 1, 0, 0);
+            pass.bind_index_buffer(&GPUBufferBinding { buffer: index_buffer, offset: 0 }, SDL_GPUIndexElementSize::_16BIT);
+            pass.draw_indexed_primitives(3, 1, 0, 0, 0);
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "draw_primitives: no graphics pipeline bound")]
+    fn draw_without_pipeline_panics() {
+        let (device, _) = setup(0, 0);
+        in_pass(&device, |pass, _| pass.draw_primitives(3, 1, 0, 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "vertex buffer slot 0 is not bound")]
+    fn draw_without_vertex_buffer_panics() {
+        let (device, pipeline) = setup(0, 0);
+        in_pass(&device, |pass, _| {
+            pass.bind_graphics_pipeline(pipeline);
+            pass.draw_primitives(3, 1, 0, 0);
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "draw_indexed_primitives with pipeline \"test pipeline\": no index buffer bound")]
+    fn indexed_draw_without_index_buffer_panics() {
+        let (device, pipeline) = setup(0, 0);
+        in_pass(&device, |pass, vertices| {
+            pass.bind_graphics_pipeline(pipeline);
+            pass.bind_vertex_buffers(0, &[GPUBufferBinding { buffer: vertices, offset: 0 }]);
+            pass.draw_indexed_primitives(3, 1, 0, 0, 0);
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "fragment sampler slot 1 is not bound (the shader declares 2)")]
+    fn draw_without_fragment_sampler_panics() {
+        let (device, pipeline) = setup(0, 2);
+        let sampled = texture(&device, SDL_GPUTextureUsageFlags::SAMPLER);
+        let sampler = sampler(&device);
+        in_pass(&device, |pass, vertices| {
+            pass.bind_graphics_pipeline(pipeline);
+            pass.bind_vertex_buffers(0, &[GPUBufferBinding { buffer: vertices, offset: 0 }]);
+            pass.bind_fragment_samplers(0, &[TextureSamplerBinding { texture: sampled, sampler }]);
+            pass.draw_primitives(3, 1, 0, 0);
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "vertex sampler slot 0 is not bound")]
+    fn draw_without_vertex_sampler_panics() {
+        let (device, pipeline) = setup(1, 0);
+        in_pass(&device, |pass, vertices| {
+            pass.bind_graphics_pipeline(pipeline);
+            pass.bind_vertex_buffers(0, &[GPUBufferBinding { buffer: vertices, offset: 0 }]);
+            pass.draw_primitives(3, 1, 0, 0);
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "dispatch: no compute pipeline bound")]
+    fn dispatch_without_pipeline_panics() {
+        let (device, _) = setup(0, 0);
+        let mut cmd = device.acquire_command_buffer().unwrap();
+        let pass = cmd.begin_compute_pass(&[], &[]).unwrap();
+        pass.dispatch(1, 1, 1);
+    }
+
+    #[test]
+    fn missing_bindings_are_ignored_without_validation() {
+        let (device, pipeline) = setup(1, 1);
+        device.set_draw_validation(false);
+        in_pass(&device, |pass, _| {
+            pass.draw_primitives(3, 1, 0, 0);
+            pass.bind_graphics_pipeline(pipeline);
+            pass.draw_indexed_primitives(3, 1, 0, 0, 0);
+        });
+    }
+
+    #[test]
+    fn render_target_without_color_target_usage_is_an_error() {
+        let (device, _log) = null_device();
+        let sampled = texture(&device, SDL_GPUTextureUsageFlags::SAMPLER);
+        let mut cmd = device.acquire_command_buffer().unwrap();
+        let err = cmd.begin_render_pass(&[ColorTargetInfo::new(sampled)], None).err();
+        assert_eq!(err, Some("color target texture was not created with COLOR_TARGET usage"));
+    }
+
+    #[test]
+    fn storage_texture_without_write_usage_is_an_error() {
+        let (device, _log) = null_device();
+        let sampled = texture(&device, SDL_GPUTextureUsageFlags::SAMPLER);
+        let mut cmd = device.acquire_command_buffer().unwrap();
+        let binding = StorageTextureReadWriteBinding { texture: sampled, mip_level: 0, layer: 0, cycle: false };
+        let err = cmd.begin_compute_pass(&[binding], &[]).err();
+        assert_eq!(err, Some("read-write storage texture was not created with COMPUTE_STORAGE_WRITE usage"));
+    }
+
+    #[test]
+    #[should_panic(expected = "bind_fragment_samplers: texture \"target\" was not created with SAMPLER usage")]
+    fn sampling_without_sampler_usage_panics() {
+        let (device, pipeline) = setup(0, 1);
+        let target = texture(&device, SDL_GPUTextureUsageFlags::COLOR_TARGET);
+        device.set_texture_name(target, "target");
+        let sampler = sampler(&device);
+        in_pass(&device, |pass, _| {
+            pass.bind_graphics_pipeline(pipeline);
+            pass.bind_fragment_samplers(0, &[TextureSamplerBinding { texture: target, sampler }]);
+        });
+    }
+
+    #[test]
+    fn sampler_usage_is_not_checked_without_validation() {
+        let (device, _) = setup(0, 1);
+        device.set_draw_validation(false);
+        let target = texture(&device, SDL_GPUTextureUsageFlags::COLOR_TARGET);
+        let sampler = sampler(&device);
+        in_pass(&device, |pass, _| pass.bind_fragment_samplers(0, &[TextureSamplerBinding { texture: target, sampler }]));
+    }
+
+    #[test]
+    #[should_panic(expected = "doesn't match the pass targets: color target 0 has format")]
+    fn pipeline_format_mismatch_panics() {
+        let (device, _log) = null_device();
+        device.set_draw_validation(true);
+        let vs = shader(&device, SDL_GPUShaderStage::VERTEX, 0, 0);
+        let fs = shader(&device, SDL_GPUShaderStage::FRAGMENT, 0, 0);
+        let pipeline = pipeline(&device, vs, fs, SDL_GPUTextureFormat::B8G8R8A8_UNORM);
+        in_pass(&device, |pass, _| pass.bind_graphics_pipeline(pipeline));
+    }
+
+    #[test]
+    fn pipeline_formats_are_not_checked_without_validation() {
+        let (device, _log) = null_device();
+        let vs = shader(&device, SDL_GPUShaderStage::VERTEX, 0, 0);
+        let fs = shader(&device, SDL_GPUShaderStage::FRAGMENT, 0, 0);
+        let pipeline = pipeline(&device, vs, fs, SDL_GPUTextureFormat::B8G8R8A8_UNORM);
+        in_pass(&device, |pass, _| pass.bind_graphics_pipeline(pipeline));
+    }
+}
@@ -1,6 +1,9 @@
 use sdl3_sys as sys;
 use sys::*;
 
+/// Identifies a window, also in [`Event`](crate::event::Event)s.
+pub type WindowId = video::SDL_WindowID;
+
 /// Safe wrapper around `SDL_DisplayMode`.
 #[derive(Debug, Clone, Copy)]
 pub struct DisplayMode {
@@ -97,6 +100,10 @@ impl  Window {
         }
     }
 
+    pub fn id(&self) -> WindowId {
+        unsafe { video::SDL_GetWindowID(self.inner) }
+    }
+
     pub(crate) fn raw(&self) -> *mut video::SDL_Window {
         self.inner
     }